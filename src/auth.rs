@@ -0,0 +1,202 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{DateTime, Local, Utc};
+use jito_protos::auth::auth_service_client::AuthServiceClient;
+use jito_protos::auth::{
+    GenerateAuthChallengeRequest, GenerateAuthTokensRequest, RefreshAccessTokenRequest, Role, Token,
+};
+use jito_protos::tonic::service::Interceptor;
+use jito_protos::tonic::transport::Channel;
+use jito_protos::tonic::{Request, Status};
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use tokio::sync::watch;
+use tokio::time::Duration;
+
+use crate::backoff::Backoff;
+
+// 提前刷新访问令牌的时间上限, 令牌有效期较短时提前剩余时间的一半
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+type AuthResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+// Jito block engine 鉴权参数
+#[derive(Clone)]
+pub struct AuthConfig {
+    pub url: String,
+    pub keypair: Arc<Keypair>,
+    // 令牌的角色: Shredstream 和心跳需要 SHREDSTREAM_SUBSCRIBER, SearcherService 需要 SEARCHER
+    pub role: Role,
+}
+
+impl AuthConfig {
+    // 同一个地址和密钥对, 换成另一个角色
+    pub fn with_role(&self, role: Role) -> Self {
+        Self { role, ..self.clone() }
+    }
+}
+
+pub fn load_keypair(path: &Path) -> Result<Keypair, String> {
+    read_keypair_file(path).map_err(|e| format!("读取密钥对 {} 失败: {}", path.display(), e))
+}
+
+// 鉴权地址和密钥对必须同时设置, 都没有设置时不鉴权; 默认申请 SHREDSTREAM_SUBSCRIBER 令牌
+pub fn resolve(url: Option<String>, keypair: Option<&Path>) -> Result<Option<AuthConfig>, String> {
+    match (url, keypair) {
+        (Some(url), Some(path)) => Ok(Some(AuthConfig { url, keypair: Arc::new(load_keypair(path)?), role: Role::ShredstreamSubscriber })),
+        (None, None) => Ok(None),
+        _ => Err("auth_url 和 auth_keypair 必须同时设置".to_string()),
+    }
+}
+
+// 后台维护访问令牌: 用密钥对签名挑战换取令牌, 在过期前刷新, 刷新令牌也快过期或刷新失败时重新鉴权
+// 可以克隆, 所有克隆共用同一个令牌
+#[derive(Clone)]
+pub struct Authenticator {
+    token: watch::Receiver<Option<String>>,
+}
+
+impl Authenticator {
+    // 启动后台鉴权任务, 所有 Authenticator 和拦截器都被丢弃后任务退出
+    pub fn spawn(config: AuthConfig) -> Self {
+        let (tx, rx) = watch::channel(None);
+        tokio::spawn(maintain(config, tx));
+        Self { token: rx }
+    }
+
+    // 等待第一次鉴权成功
+    pub async fn ready(&self) -> Result<(), String> {
+        let mut token = self.token.clone();
+        token.wait_for(Option::is_some).await.map(|_| ()).map_err(|_| "鉴权任务已退出".to_string())
+    }
+
+    // 给请求加上 authorization: Bearer <访问令牌>
+    pub fn interceptor(&self) -> BearerInterceptor {
+        BearerInterceptor { token: self.token.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct BearerInterceptor {
+    token: watch::Receiver<Option<String>>,
+}
+
+impl Interceptor for BearerInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let value = match self.token.borrow().as_deref() {
+            Some(token) => format!("Bearer {}", token),
+            None => return Err(Status::unauthenticated("尚未取得访问令牌")),
+        };
+        let value = value.parse().map_err(|_| Status::internal("访问令牌不是合法的 metadata"))?;
+        request.metadata_mut().insert("authorization", value);
+        Ok(request)
+    }
+}
+
+// 没有配置鉴权时不携带令牌
+pub struct OptionalBearer(Option<BearerInterceptor>);
+
+impl OptionalBearer {
+    pub fn new(auth: Option<&Authenticator>) -> Self {
+        Self(auth.map(Authenticator::interceptor))
+    }
+}
+
+impl Interceptor for OptionalBearer {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        match &mut self.0 {
+            Some(interceptor) => interceptor.call(request),
+            None => Ok(request),
+        }
+    }
+}
+
+async fn maintain(config: AuthConfig, tx: watch::Sender<Option<String>>) {
+    let mut backoff = Backoff::default();
+    loop {
+        let result = refresh_loop(&config, &tx, &mut backoff).await;
+        if tx.is_closed() {
+            return;
+        }
+        // 出错时保留旧令牌, 过期前仍然可用
+        let delay = backoff.next_delay();
+        println!("[{}] WARN: 鉴权失败: {}, {:.1}秒后第 {} 次重试",
+            Local::now().format("%H:%M:%S%.3f"),
+            result.err().map(|e| e.to_string()).unwrap_or_default(),
+            delay.as_secs_f64(),
+            backoff.attempt()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+// 鉴权并不断刷新, 只在出错或没有接收者时返回
+async fn refresh_loop(config: &AuthConfig, tx: &watch::Sender<Option<String>>, backoff: &mut Backoff) -> AuthResult<()> {
+    let mut client = AuthServiceClient::connect(config.url.clone()).await?;
+    loop {
+        let (mut access, refresh) = authenticate(&mut client, &config.keypair, config.role).await?;
+        backoff.reset();
+        println!("[{}] INFO: 鉴权成功, 访问令牌有效期至 {}",
+            Local::now().format("%H:%M:%S%.3f"), expires_at(&access)?.with_timezone(&Local).to_rfc3339());
+        tx.send_replace(Some(access.value.clone()));
+
+        loop {
+            tokio::time::sleep(refresh_delay(&access)?).await;
+            if tx.is_closed() {
+                return Ok(());
+            }
+            // 刷新令牌比当前访问令牌先过期时重新鉴权
+            if expires_at(&refresh)? <= expires_at(&access)? {
+                break;
+            }
+            let response = client
+                .refresh_access_token(RefreshAccessTokenRequest { refresh_token: refresh.value.clone() })
+                .await?
+                .into_inner();
+            access = response.access_token.ok_or("刷新响应中缺少访问令牌")?;
+            println!("[{}] INFO: 访问令牌已刷新, 有效期至 {}",
+                Local::now().format("%H:%M:%S%.3f"), expires_at(&access)?.with_timezone(&Local).to_rfc3339());
+            tx.send_replace(Some(access.value.clone()));
+        }
+    }
+}
+
+// 挑战 - 签名 - 换取令牌, 返回 (访问令牌, 刷新令牌)
+async fn authenticate(client: &mut AuthServiceClient<Channel>, keypair: &Keypair, role: Role) -> AuthResult<(Token, Token)> {
+    let pubkey = keypair.pubkey();
+    let challenge = client
+        .generate_auth_challenge(GenerateAuthChallengeRequest {
+            role: role as i32,
+            pubkey: pubkey.to_bytes().to_vec(),
+        })
+        .await?
+        .into_inner()
+        .challenge;
+
+    // 签名内容为 "<公钥>-<挑战>", 与 block engine 的约定一致
+    let challenge = format!("{}-{}", pubkey, challenge);
+    let signed_challenge = keypair.sign_message(challenge.as_bytes());
+    let tokens = client
+        .generate_auth_tokens(GenerateAuthTokensRequest {
+            challenge,
+            client_pubkey: pubkey.to_bytes().to_vec(),
+            signed_challenge: signed_challenge.as_ref().to_vec(),
+        })
+        .await?
+        .into_inner();
+    Ok((
+        tokens.access_token.ok_or("鉴权响应中缺少访问令牌")?,
+        tokens.refresh_token.ok_or("鉴权响应中缺少刷新令牌")?,
+    ))
+}
+
+fn expires_at(token: &Token) -> AuthResult<DateTime<Utc>> {
+    let expires = token.expires_at_utc.as_ref().ok_or("令牌缺少过期时间")?;
+    DateTime::from_timestamp(expires.seconds, expires.nanos.max(0) as u32).ok_or_else(|| "令牌过期时间无效".into())
+}
+
+// 距离过期还剩 REFRESH_MARGIN (有效期较短时为剩余时间的一半) 时刷新
+fn refresh_delay(access: &Token) -> AuthResult<Duration> {
+    let remaining = (expires_at(access)? - Utc::now()).to_std().unwrap_or_default();
+    Ok(remaining - REFRESH_MARGIN.min(remaining / 2))
+}
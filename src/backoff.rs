@@ -0,0 +1,41 @@
+use rand::Rng;
+use tokio::time::Duration;
+
+// 带抖动的指数退避, 用于断线重连
+pub struct Backoff {
+    attempt: u32,
+    base: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self { attempt: 0, base, max }
+    }
+
+    // 下一次重连前的等待时间: 在 [上限/2, 上限] 之间随机, 上限按 2 的幂增长
+    pub fn next_delay(&mut self) -> Duration {
+        let cap = self
+            .base
+            .saturating_mul(1u32 << self.attempt.min(16))
+            .min(self.max);
+        self.attempt = self.attempt.saturating_add(1);
+        let half = cap / 2;
+        half + rand::thread_rng().gen_range(Duration::ZERO..=half)
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    // 连接恢复并收到数据后重置
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500), Duration::from_secs(30))
+    }
+}
@@ -0,0 +1,257 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use jito_protos::prost::Message as _;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use yellowstone_grpc_proto::geyser::SubscribeUpdate;
+use yellowstone_grpc_proto::prost::Message as _;
+
+use crate::clock::Timestamp;
+use crate::shred::Shred;
+use crate::source::{CompareMode, Decoder, Source, SourceEvent, SourceKind, StreamOptions};
+
+// 抓包文件格式 (小端):
+//   魔数 "SVGCAP04"
+//   u32 头部长度 + JSON 头部 (数据源列表)
+//   若干帧: u16 数据源序号, u8 帧类型, u64 单调时钟纳秒, u64 系统时间纳秒, u32 负载长度, 负载
+// 负载: Geyser 为 protobuf SubscribeUpdate, Shredstream 为 protobuf Entry { slot, entries }, 本地 UDP 为原始 shred 数据包;
+// 连接建立和断开的帧没有负载
+const MAGIC: &[u8; 8] = b"SVGCAP04";
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    sources: Vec<HeaderSource>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeaderSource {
+    label: String,
+    kind: String,
+    url: String,
+}
+
+// 帧类型: 收到的数据, 以及连接建立和断开, 回放时还原为 Connected / Disconnected 事件
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FrameKind {
+    Data,
+    Connected,
+    Disconnected,
+}
+
+impl FrameKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            FrameKind::Data => 0,
+            FrameKind::Connected => 1,
+            FrameKind::Disconnected => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(FrameKind::Data),
+            1 => Some(FrameKind::Connected),
+            2 => Some(FrameKind::Disconnected),
+            _ => None,
+        }
+    }
+}
+
+pub struct Frame {
+    pub source: usize,
+    pub kind: FrameKind,
+    pub timestamp: Timestamp,
+    pub payload: Vec<u8>,
+}
+
+// 多个订阅任务共用的抓包写入器
+#[derive(Clone)]
+pub struct CaptureWriter {
+    inner: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl CaptureWriter {
+    pub fn create(path: &Path, sources: &[Source]) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let header = Header {
+            sources: sources
+                .iter()
+                .map(|s| HeaderSource { label: s.label.clone(), kind: s.kind.name().to_string(), url: s.url.clone() })
+                .collect(),
+        };
+        let header = serde_json::to_vec(&header)?;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(header.len() as u32).to_le_bytes())?;
+        writer.write_all(&header)?;
+        Ok(Self { inner: Arc::new(Mutex::new(writer)) })
+    }
+
+    // 写入收到的数据
+    pub fn write(&self, source: usize, timestamp: Timestamp, payload: &[u8]) -> io::Result<()> {
+        self.write_frame(source, FrameKind::Data, timestamp, payload)
+    }
+
+    // 写入连接建立或断开
+    pub fn write_event(&self, source: usize, kind: FrameKind, timestamp: Timestamp) -> io::Result<()> {
+        self.write_frame(source, kind, timestamp, &[])
+    }
+
+    fn write_frame(&self, source: usize, kind: FrameKind, timestamp: Timestamp, payload: &[u8]) -> io::Result<()> {
+        let mut writer = self.inner.lock().unwrap();
+        writer.write_all(&(source as u16).to_le_bytes())?;
+        writer.write_all(&[kind.to_byte()])?;
+        writer.write_all(&timestamp.mono_ns.to_le_bytes())?;
+        writer.write_all(&timestamp.wall_ns.to_le_bytes())?;
+        writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+        writer.write_all(payload)
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        self.inner.lock().unwrap().flush()
+    }
+}
+
+pub struct CaptureReader {
+    reader: BufReader<File>,
+    pub sources: Vec<Source>,
+}
+
+impl CaptureReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} 不是抓包文件", path.display())));
+        }
+
+        let len = read_u32(&mut reader)? as usize;
+        let mut header = vec![0u8; len];
+        reader.read_exact(&mut header)?;
+        let header: Header = serde_json::from_slice(&header)?;
+        let sources = header
+            .sources
+            .into_iter()
+            .map(|s| {
+                let kind = SourceKind::parse(&s.kind).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("未知的数据源类型: {}", s.kind))
+                })?;
+                Ok(Source { label: s.label, kind, url: s.url })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(Self { reader, sources })
+    }
+
+    // 读取下一帧, 文件结束时返回 None
+    pub fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        let mut source = [0u8; 2];
+        match self.reader.read_exact(&mut source) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let mut kind = [0u8; 1];
+        self.reader.read_exact(&mut kind)?;
+        let kind = FrameKind::from_byte(kind[0])
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("未知的帧类型 {}", kind[0])))?;
+        let mono_ns = read_u64(&mut self.reader)?;
+        let timestamp = Timestamp { mono_ns, wall_ns: read_u64(&mut self.reader)? };
+        let len = read_u32(&mut self.reader)? as usize;
+        let mut payload = vec![0u8; len];
+        self.reader.read_exact(&mut payload)?;
+        Ok(Some(Frame { source: u16::from_le_bytes(source) as usize, kind, timestamp, payload }))
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+// 把一帧解码为对比事件
+pub fn decode_frame(decoder: &mut Decoder, index: usize, kind: SourceKind, mode: CompareMode, frame: &Frame) -> Vec<SourceEvent> {
+    match frame.kind {
+        FrameKind::Connected => return vec![SourceEvent::Connected(index, frame.timestamp)],
+        FrameKind::Disconnected => return vec![SourceEvent::Disconnected(index, frame.timestamp)],
+        FrameKind::Data => {}
+    }
+    match kind {
+        SourceKind::Geyser => match SubscribeUpdate::decode(frame.payload.as_slice()) {
+            Ok(update) => decoder.geyser(index, mode, &update, frame.timestamp),
+            Err(_) => Vec::new(),
+        },
+        SourceKind::Shredstream => match jito_protos::shredstream::Entry::decode(frame.payload.as_slice()) {
+            Ok(entry) => decoder.shred(index, mode, &entry, frame.timestamp),
+            Err(_) => Vec::new(),
+        },
+        SourceKind::Shreds => match Shred::parse(&frame.payload) {
+            Ok(shred) => decoder.shreds(index, mode, shred, frame.timestamp),
+            Err(_) => Vec::new(),
+        },
+    }
+}
+
+// 打开多个抓包文件, 返回合并后的数据源列表, 并在后台按接收时间顺序回放所有帧
+// 标签重复时加上文件名以区分
+pub fn spawn_replay(paths: &[PathBuf], options: &StreamOptions, tx: mpsc::Sender<SourceEvent>) -> io::Result<Vec<Source>> {
+    let mut readers = Vec::new();
+    let mut sources: Vec<Source> = Vec::new();
+    // 每个文件的数据源在合并列表中的起始序号
+    let mut offsets = Vec::new();
+    for path in paths {
+        let reader = CaptureReader::open(path)?;
+        offsets.push(sources.len());
+        for source in &reader.sources {
+            let mut source = source.clone();
+            if sources.iter().any(|s| s.label == source.label) {
+                let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                source.label = format!("{}@{}", source.label, stem);
+            }
+            sources.push(source);
+        }
+        readers.push(reader);
+    }
+
+    let kinds: Vec<SourceKind> = sources.iter().map(|s| s.kind).collect();
+    let counts: Vec<usize> = readers.iter().map(|r| r.sources.len()).collect();
+    let mode = options.mode;
+    let options = options.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut decoders: Vec<Decoder> = kinds.iter().map(|_| Decoder::new(&options)).collect();
+        let mut heads: Vec<Option<Frame>> = readers
+            .iter_mut()
+            .map(|r| r.next_frame().ok().flatten())
+            .collect();
+
+        // 多路归并: 每次取接收时间最早的一帧
+        while let Some(file) = heads
+            .iter()
+            .enumerate()
+            .filter_map(|(i, head)| head.as_ref().map(|f| (i, f.timestamp.mono_ns)))
+            .min_by_key(|(_, ts)| *ts)
+            .map(|(i, _)| i)
+        {
+            let frame = heads[file].take().unwrap();
+            if frame.source < counts[file] {
+                let index = offsets[file] + frame.source;
+                for event in decode_frame(&mut decoders[index], index, kinds[index], mode, &frame) {
+                    if tx.blocking_send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+            heads[file] = readers[file].next_frame().ok().flatten();
+        }
+    });
+    Ok(sources)
+}
@@ -0,0 +1,302 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset};
+use clap::{Args, Parser, Subcommand};
+
+use crate::config::{Commitment, OutputFormat};
+use crate::source::CompareMode;
+
+#[derive(Parser)]
+#[command(name = "shred-vs-grpc", version, about = "对比 Solana Shredstream 与 Geyser gRPC 的数据到达速度")]
+pub struct Cli {
+    /// TOML 配置文件路径, 未指定时只读取环境变量和 .env
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// 在固定时长内对比各端点, 结束后输出统计结果 (默认)
+    Compare(RunArgs),
+    /// 长时间监控各端点的最新 slot
+    Monitor(RunArgs),
+    /// 逐 slot 核对 Shredstream 是否送达了 Geyser 推送的每一笔交易
+    Verify(RunArgs),
+    /// 打印 Geyser 端点每个 slot 的首次到达时间
+    TailGrpc(TailArgs),
+    /// 打印 Shredstream 端点每个 slot 的首次到达时间
+    TailShred(TailArgs),
+    /// 向 Shredstream 服务发送心跳, 在本地 UDP 端口接收 shred
+    Heartbeat(HeartbeatArgs),
+    /// 查询 Jito block engine 的区域、下一个 Jito leader 和各区域连接的 leader
+    Searcher(SearcherArgs),
+    /// 启动本地模拟服务端, 推送合成的 entry 数据
+    Mock(MockArgs),
+}
+
+#[derive(Args, Default)]
+pub struct RunArgs {
+    /// 数据源, 可重复指定, 覆盖配置文件和 SOURCES
+    #[arg(long = "source", value_name = "LABEL=KIND:URL")]
+    pub sources: Vec<String>,
+
+    /// 运行时长 (秒), compare 默认 30 (设置了 --to-slot 或 --end-at 时不限), monitor 默认 3600, 0 表示不限
+    #[arg(long, value_name = "SECS")]
+    pub duration: Option<u64>,
+
+    /// 预热时长 (秒), 期间的样本不计入统计
+    #[arg(long, value_name = "SECS")]
+    pub warmup: Option<u64>,
+
+    /// 统计窗口的起始 slot (含), 之前的样本不计入统计
+    #[arg(long, value_name = "SLOT")]
+    pub from_slot: Option<u64>,
+
+    /// 统计窗口的结束 slot (含), 所有数据源都越过该 slot 后结束 (仅 compare)
+    #[arg(long, value_name = "SLOT")]
+    pub to_slot: Option<u64>,
+
+    /// 统计窗口的开始时间 (RFC 3339), 之前的样本不计入统计
+    #[arg(long, value_name = "TIME")]
+    pub start_at: Option<DateTime<FixedOffset>>,
+
+    /// 统计窗口的结束时间 (RFC 3339), 到达后结束 (仅 compare)
+    #[arg(long, value_name = "TIME")]
+    pub end_at: Option<DateTime<FixedOffset>>,
+
+    /// Geyser 订阅的提交级别, verify 默认 confirmed, 其他默认 processed
+    #[arg(long, value_enum)]
+    pub commitment: Option<Commitment>,
+
+    /// 只保留投票 (true) 或非投票 (false) 交易, 默认 false
+    #[arg(long, value_name = "BOOL")]
+    pub vote: Option<bool>,
+
+    /// 只保留失败 (true) 或成功 (false) 交易, 默认 false; Shredstream 端无法判断
+    #[arg(long, value_name = "BOOL")]
+    pub failed: Option<bool>,
+
+    /// 交易至少包含其中一个账户, 可重复指定
+    #[arg(long, value_name = "PUBKEY")]
+    pub account_include: Vec<String>,
+
+    /// 交易不包含其中任何账户, 可重复指定
+    #[arg(long, value_name = "PUBKEY")]
+    pub account_exclude: Vec<String>,
+
+    /// 交易包含其中所有账户, 可重复指定
+    #[arg(long, value_name = "PUBKEY")]
+    pub account_required: Vec<String>,
+
+    /// 匹配方式: 按 slot、交易签名、entry 序号或 entry 哈希
+    #[arg(long, value_enum)]
+    pub mode: Option<CompareMode>,
+
+    /// 结果输出格式
+    #[arg(long, value_enum)]
+    pub output: Option<OutputFormat>,
+
+    /// 事件通道容量 (每个数据源)
+    #[arg(long, value_name = "N")]
+    pub channel_size: Option<usize>,
+
+    /// 按 slot 保留匹配记录的深度, 落后于最新 slot 超过该值的记录被淘汰, 仍未收到的数据源计为漏收
+    #[arg(long, value_name = "SLOTS")]
+    pub slot_depth: Option<u64>,
+
+    /// 把各端点的原始数据流写入抓包文件
+    #[arg(long, value_name = "FILE")]
+    pub capture: Option<PathBuf>,
+
+    /// 回放抓包文件代替连接端点, 可重复指定 (仅 compare)
+    #[arg(long, value_name = "FILE", conflicts_with = "capture")]
+    pub replay: Vec<PathBuf>,
+
+    /// Prometheus 指标导出地址, 提供 GET /metrics (仅 monitor)
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_listen: Option<SocketAddr>,
+
+    /// 把 JSON 格式的结果文档写入文件 (仅 compare)
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+
+    /// 把每个计入统计的样本写入 CSV 文件 (仅 compare)
+    #[arg(long, value_name = "FILE")]
+    pub samples_csv: Option<PathBuf>,
+
+    /// 把每个 slot 内各批内容的到达时间写入 CSV 文件, 并统计 slot 填充时间 (仅 compare)
+    #[arg(long, value_name = "FILE")]
+    pub timeline: Option<PathBuf>,
+
+    /// leader 表: getLeaderSchedule 的响应或 solana leader-schedule --output json 的输出, 用于按 leader 分组统计 (仅 compare)
+    #[arg(long, value_name = "FILE")]
+    pub leader_schedule: Option<PathBuf>,
+
+    /// getLeaderSchedule 响应对应 epoch 的第一个 slot
+    #[arg(long, value_name = "SLOT")]
+    pub leader_first_slot: Option<u64>,
+
+    /// Jito GetConnectedLeadersRegioned 的响应 (JSON), 用于按 Jito 区域分组统计 (仅 compare)
+    #[arg(long, value_name = "FILE")]
+    pub leader_regions: Option<PathBuf>,
+
+    /// Jito SearcherService 地址, 启动时查询所有在线区域连接的 leader, 用于按 Jito 区域分组统计 (仅 compare)
+    #[arg(long, value_name = "URL")]
+    pub searcher_url: Option<String>,
+
+    /// Jito block engine 鉴权服务地址, 设置后 Shredstream 连接携带访问令牌
+    #[arg(long, value_name = "URL", requires = "auth_keypair")]
+    pub auth_url: Option<String>,
+
+    /// 鉴权用的 Solana 密钥对文件 (JSON)
+    #[arg(long, value_name = "FILE", requires = "auth_url")]
+    pub auth_keypair: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct TailArgs {
+    /// 端点地址, 默认读取 GRPC_URL 或 SHRED_URL
+    #[arg(long)]
+    pub url: Option<String>,
+
+    /// Geyser 订阅的提交级别
+    #[arg(long, value_enum)]
+    pub commitment: Option<Commitment>,
+}
+
+#[derive(Args)]
+pub struct HeartbeatArgs {
+    /// block engine 的 Shredstream 服务地址
+    #[arg(long, value_name = "URL")]
+    pub url: Option<String>,
+
+    /// 本地接收 shred 的 UDP 地址, 默认 0.0.0.0:20000
+    #[arg(long, value_name = "ADDR")]
+    pub listen: Option<SocketAddr>,
+
+    /// 向服务端登记的公网地址, 监听地址不是具体地址时必须指定
+    #[arg(long, value_name = "ADDR")]
+    pub public_addr: Option<SocketAddr>,
+
+    /// 接收 shred 的区域, 可重复指定, 覆盖配置文件
+    #[arg(long = "region", value_name = "REGION")]
+    pub regions: Vec<String>,
+
+    /// Jito block engine 鉴权服务地址
+    #[arg(long, value_name = "URL", requires = "auth_keypair")]
+    pub auth_url: Option<String>,
+
+    /// 鉴权用的 Solana 密钥对文件 (JSON)
+    #[arg(long, value_name = "FILE", requires = "auth_url")]
+    pub auth_keypair: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct SearcherArgs {
+    /// block engine 的 SearcherService 地址
+    #[arg(long, value_name = "URL")]
+    pub url: Option<String>,
+
+    /// 查询的区域, 可重复指定, 默认所有在线区域
+    #[arg(long = "region", value_name = "REGION")]
+    pub regions: Vec<String>,
+
+    /// 把各区域连接的 leader 写入 JSON 文件, 格式与 GetConnectedLeadersRegioned 响应相同, 可用于 --leader-regions
+    #[arg(long, value_name = "FILE")]
+    pub save: Option<PathBuf>,
+
+    /// Jito block engine 鉴权服务地址
+    #[arg(long, value_name = "URL", requires = "auth_keypair")]
+    pub auth_url: Option<String>,
+
+    /// 鉴权用的 Solana 密钥对文件 (JSON)
+    #[arg(long, value_name = "FILE", requires = "auth_url")]
+    pub auth_keypair: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct MockArgs {
+    /// 模拟 ShredstreamProxy 的监听地址
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:9999")]
+    pub shred_listen: SocketAddr,
+
+    /// 每个 slot 的时长 (毫秒)
+    #[arg(long, value_name = "MS", default_value_t = 400)]
+    pub slot_ms: u64,
+
+    /// 每个 slot 的 entry 数
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub entries_per_slot: usize,
+
+    /// 每个 entry 的交易数
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    pub txs_per_entry: usize,
+
+    /// 起始 slot
+    #[arg(long, value_name = "SLOT", default_value_t = 1)]
+    pub start_slot: u64,
+
+    /// Shredstream 端的固定延迟 (毫秒)
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    pub shred_delay_ms: u64,
+
+    /// Shredstream 端的随机延迟上限 (毫秒)
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    pub shred_jitter_ms: u64,
+
+    /// Shredstream 端丢弃 entry 的概率
+    #[arg(long, value_name = "RATE", default_value_t = 0.0)]
+    pub shred_drop_rate: f64,
+
+    /// 模拟 Geyser 的监听地址, 不指定时不启动
+    #[arg(long, value_name = "ADDR")]
+    pub geyser_listen: Option<SocketAddr>,
+
+    /// Geyser 端的固定延迟 (毫秒)
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    pub geyser_delay_ms: u64,
+
+    /// Geyser 端的随机延迟上限 (毫秒)
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    pub geyser_jitter_ms: u64,
+
+    /// Geyser 端丢弃 entry 的概率
+    #[arg(long, value_name = "RATE", default_value_t = 0.0)]
+    pub geyser_drop_rate: f64,
+
+    /// Geyser 端主动发送 Ping 的间隔 (秒)
+    #[arg(long, value_name = "SECS", default_value_t = 10)]
+    pub geyser_ping_secs: u64,
+
+    /// 模拟 Shredstream 心跳服务的监听地址, 向登记的 UDP 地址转发数据包
+    #[arg(long, value_name = "ADDR")]
+    pub heartbeat_listen: Option<SocketAddr>,
+
+    /// 模拟心跳服务返回的有效期 (毫秒)
+    #[arg(long, value_name = "MS", default_value_t = 5000)]
+    pub heartbeat_ttl_ms: u32,
+
+    /// 模拟 SearcherService 的监听地址, 提供 leader 和区域查询
+    #[arg(long, value_name = "ADDR")]
+    pub searcher_listen: Option<SocketAddr>,
+
+    /// 把模拟 leader 轮换的 getLeaderSchedule 响应写入文件 (第一个 epoch, first_slot 为 0)
+    #[arg(long, value_name = "FILE")]
+    pub leader_schedule_out: Option<PathBuf>,
+
+    /// 模拟 Jito 鉴权服务的监听地址, 指定后模拟 ShredstreamProxy 要求请求携带访问令牌
+    #[arg(long, value_name = "ADDR")]
+    pub auth_listen: Option<SocketAddr>,
+
+    /// 模拟鉴权服务签发的访问令牌有效期 (秒)
+    #[arg(long, value_name = "SECS", default_value_t = 60)]
+    pub auth_access_secs: u64,
+
+    /// 模拟鉴权服务签发的刷新令牌有效期 (秒)
+    #[arg(long, value_name = "SECS", default_value_t = 600)]
+    pub auth_refresh_secs: u64,
+}
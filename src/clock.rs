@@ -0,0 +1,44 @@
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// 进程内第一次取时间时的单调时钟读数和对应的墙上时间
+struct Anchor {
+    instant: Instant,
+    wall_ns: u64,
+}
+
+static ANCHOR: OnceLock<Anchor> = OnceLock::new();
+
+fn anchor() -> &'static Anchor {
+    ANCHOR.get_or_init(|| Anchor { instant: Instant::now(), wall_ns: wall_now_ns() })
+}
+
+fn wall_now_ns() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64
+}
+
+// 接收时间, 单位均为纳秒
+// mono_ns 为单调时钟, 以锚点的墙上时间为起点, 不受系统时间调整影响, 用于计算延迟;
+// 不同进程 (例如多个抓包文件) 之间仍大致可比, 误差取决于各自启动时的系统时间
+// wall_ns 为系统时间 (Unix 纳秒), 用于展示和对齐外部数据
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Timestamp {
+    pub mono_ns: u64,
+    pub wall_ns: u64,
+}
+
+impl Timestamp {
+    pub fn now() -> Self {
+        let anchor = anchor();
+        let mono_ns = anchor.wall_ns + anchor.instant.elapsed().as_nanos() as u64;
+        Self { mono_ns, wall_ns: wall_now_ns() }
+    }
+
+    pub fn wall_ms(&self) -> f64 {
+        ns_to_ms(self.wall_ns)
+    }
+}
+
+pub fn ns_to_ms(ns: u64) -> f64 {
+    ns as f64 / 1_000_000.0
+}
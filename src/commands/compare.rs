@@ -0,0 +1,230 @@
+use chrono::{DateTime, Local};
+use jito_protos::auth::Role;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+
+use crate::auth::Authenticator;
+use crate::capture::{self, CaptureWriter};
+use crate::clock::Timestamp;
+use crate::compare::Comparator;
+use crate::config::{Config, OutputFormat};
+use crate::leaders::LeaderSchedule;
+use crate::report::{Report, SampleWriter};
+use crate::searcher;
+use crate::source::{self, CompareMode, SourceEvent};
+use crate::timeline::{Timeline, TimelineWriter};
+
+// 查询 SearcherService 的超时, 包括等待鉴权
+const SEARCHER_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let replaying = !config.replay.is_empty();
+    // 在连接数据源之前查询, 避免事件在通道中积压
+    let leaders = resolve_leaders(&config).await;
+    let (tx, mut rx) = mpsc::channel::<SourceEvent>(config.channel_size * config.sources.len().max(2));
+
+    // 回放时数据源来自抓包文件头部, 否则连接配置中的端点
+    let mut capture = None;
+    let sources = if replaying {
+        capture::spawn_replay(&config.replay, &config.stream_options(), tx)?
+    } else {
+        if config.sources.len() >= 2 {
+            if let Some(path) = &config.capture {
+                capture = Some(CaptureWriter::create(path, &config.sources)?);
+            }
+            source::spawn_sources(&config.sources, config.stream_options(), capture.clone(), None, tx);
+        }
+        config.sources.clone()
+    };
+    if sources.len() < 2 {
+        return Err("至少需要两个数据源, 请使用 --source、配置文件或 SOURCES/GRPC_URL/SHRED_URL".into());
+    }
+
+    let labels: Vec<String> = sources.iter().map(|s| s.label.clone()).collect();
+    println!("[{}] INFO: 开始对比 {} 个端点的服务性能...", Local::now().format("%H:%M:%S%.3f"), labels.len());
+    println!("[{}] INFO: 测试持续时间: {}秒, 预热: {}秒",
+        Local::now().format("%H:%M:%S%.3f"), config.duration, config.warmup);
+    println!("[{}] INFO: 测试端点: {}", Local::now().format("%H:%M:%S%.3f"), labels.join(", "));
+    println!("[{}] INFO: 对比模式: {}", Local::now().format("%H:%M:%S%.3f"), config.mode.name());
+    if let Some(window) = describe_window(&config) {
+        println!("[{}] INFO: 统计窗口: {}", Local::now().format("%H:%M:%S%.3f"), window);
+    }
+    if let Some(schedule) = &leaders {
+        println!("[{}] INFO: 已加载 {} 个 slot 的 leader 信息{}",
+            Local::now().format("%H:%M:%S%.3f"),
+            schedule.slots(),
+            if schedule.has_regions() { ", 含 Jito 区域" } else { "" }
+        );
+    }
+    if replaying {
+        let files: Vec<String> = config.replay.iter().map(|p| p.display().to_string()).collect();
+        println!("[{}] INFO: 回放抓包文件: {}", Local::now().format("%H:%M:%S%.3f"), files.join(", "));
+    }
+
+    let mut latest_slots = vec![0; labels.len()];
+    let mut timeline = match &config.timeline {
+        Some(path) => {
+            let mut timeline = Timeline::new(labels.clone(), config.slot_depth);
+            timeline.set_window(config.window);
+            timeline.set_writer(TimelineWriter::create(path)?);
+            Some(timeline)
+        }
+        None => None,
+    };
+    let mut comparator = Comparator::new(labels, config.slot_depth);
+    comparator.set_window(config.window);
+    comparator.set_count_holes(config.mode == CompareMode::Slot || config.mode.is_entry());
+    if let Some(path) = &config.samples_csv {
+        comparator.set_sample_writer(SampleWriter::create(path, config.mode, leaders.is_some())?);
+    }
+    if let Some(schedule) = leaders {
+        comparator.set_leaders(schedule);
+    }
+    let started_at = Local::now();
+    let warmup = Duration::from_secs(config.warmup);
+    // duration 为 0 时不限时长
+    let window_end = match config.duration {
+        0 => None,
+        secs => Some(warmup + Duration::from_secs(secs)),
+    };
+    let start_time = Instant::now();
+    let mut deadline = window_end.map(|end| start_time + end);
+    if let Some(end_ns) = config.window.end_ns {
+        let until_end = Duration::from_nanos(end_ns.saturating_sub(Timestamp::now().wall_ns));
+        deadline = Some(deadline.map_or(start_time + until_end, |d| d.min(start_time + until_end)));
+    }
+    // 回放时按抓包中的接收时间计算预热和时长
+    let mut replay_start = None;
+    let mut replay_elapsed = Duration::ZERO;
+    let mut warming_up = config.warmup > 0;
+    let mut first_slot_received = false;
+    comparator.set_recording(!warming_up);
+    if let Some(timeline) = &mut timeline {
+        timeline.set_recording(!warming_up);
+    }
+
+    loop {
+        tokio::select! {
+            Some(event) = rx.recv() => {
+                let elapsed = if replaying {
+                    let timestamp = event.timestamp().mono_ns;
+                    let first = *replay_start.get_or_insert(timestamp);
+                    replay_elapsed = Duration::from_nanos(timestamp.saturating_sub(first));
+                    replay_elapsed
+                } else {
+                    start_time.elapsed()
+                };
+                if window_end.is_some_and(|end| elapsed >= end) {
+                    break;
+                }
+                if warming_up && elapsed >= warmup {
+                    println!("[{}] INFO: 预热结束", Local::now().format("%H:%M:%S%.3f"));
+                    warming_up = false;
+                    comparator.set_recording(true);
+                    if let Some(timeline) = &mut timeline {
+                        timeline.set_recording(true);
+                    }
+                }
+                if let SourceEvent::Batch(batch) = &event {
+                    if let Some(timeline) = &mut timeline {
+                        timeline.record(batch);
+                    }
+                    continue;
+                }
+                let wall_ns = event.timestamp().wall_ns;
+                if let SourceEvent::Arrival(arrival) = &event {
+                    latest_slots[arrival.source] = latest_slots[arrival.source].max(arrival.slot);
+                }
+                if comparator.handle(event) && !first_slot_received {
+                    println!("[{}] INFO: 已有端点共同接收到窗口内第一个 {}, 开始正式统计...",
+                        Local::now().format("%H:%M:%S%.3f"), config.mode.name());
+                    first_slot_received = true;
+                }
+                // 所有数据源都越过结束 slot, 或到达结束时间
+                let slowest = latest_slots.iter().copied().min().unwrap_or(0);
+                if config.window.is_past(slowest, wall_ns) {
+                    println!("[{}] INFO: 统计窗口结束", Local::now().format("%H:%M:%S%.3f"));
+                    break;
+                }
+            }
+            _ = sleep_until(deadline), if !replaying && deadline.is_some() => break,
+            else => break,
+        }
+    }
+
+    if let Some(capture) = capture {
+        capture.flush()?;
+    }
+    comparator.finish();
+    comparator.flush_samples()?;
+    if let Some(timeline) = &mut timeline {
+        timeline.finish()?;
+    }
+
+    let elapsed = if replaying { replay_elapsed } else { start_time.elapsed() };
+    let report = Report::new(&config, &sources, started_at, elapsed.as_secs_f64(), comparator.summary(), timeline.as_ref().map(Timeline::summary));
+    if let Some(path) = &config.report {
+        report.write(path)?;
+        println!("[{}] INFO: 结果已写入 {}", Local::now().format("%H:%M:%S%.3f"), path.display());
+    }
+    match config.output {
+        OutputFormat::Text => {
+            comparator.print_stats();
+            if let Some(timeline) = &timeline {
+                timeline.print_stats();
+            }
+        }
+        OutputFormat::Json => println!("{}", report.to_json()?),
+    }
+    Ok(())
+}
+
+// 离线的 leader 信息, 配置了 SearcherService 时合并各区域连接的 leader; 查询失败时不影响对比
+async fn resolve_leaders(config: &Config) -> Option<LeaderSchedule> {
+    let Some(url) = &config.searcher_url else {
+        return config.leaders.clone();
+    };
+    // SearcherService 需要 SEARCHER 角色的令牌, 与数据源共用的令牌分开申请
+    let auth = config.auth.as_ref().map(|auth| Authenticator::spawn(auth.with_role(Role::Searcher)));
+    let mut schedule = config.leaders.clone().unwrap_or_default();
+    match tokio::time::timeout(SEARCHER_TIMEOUT, searcher::load_connected_leaders(url, auth.as_ref(), &mut schedule)).await {
+        Ok(Ok((regions, count))) => {
+            println!("[{}] INFO: 从 {} 查询到 {} 个区域连接的 {} 个 Jito leader",
+                Local::now().format("%H:%M:%S%.3f"), url, regions, count);
+            Some(schedule)
+        }
+        Ok(Err(e)) => {
+            println!("[{}] WARN: 查询 Jito leader 失败: {}, 只使用离线的 leader 信息", Local::now().format("%H:%M:%S%.3f"), e);
+            config.leaders.clone()
+        }
+        Err(_) => {
+            println!("[{}] WARN: 查询 Jito leader 超时, 只使用离线的 leader 信息", Local::now().format("%H:%M:%S%.3f"));
+            config.leaders.clone()
+        }
+    }
+}
+
+// deadline 为 None 时永不返回
+pub(crate) async fn sleep_until(deadline: Option<Instant>) {
+    if let Some(deadline) = deadline {
+        tokio::time::sleep_until(deadline).await;
+    }
+}
+
+// 统计窗口的可读描述, 未设置任何边界时返回 None
+fn describe_window(config: &Config) -> Option<String> {
+    let window = &config.window;
+    let mut parts = Vec::new();
+    if window.from_slot.is_some() || window.to_slot.is_some() {
+        let bound = |slot: Option<u64>| slot.map(|s| s.to_string()).unwrap_or_default();
+        parts.push(format!("slot {}..={}", bound(window.from_slot), bound(window.to_slot)));
+    }
+    if window.start_ns.is_some() || window.end_ns.is_some() {
+        let bound = |ns: Option<u64>| {
+            ns.map(|ns| DateTime::from_timestamp_nanos(ns as i64).with_timezone(&Local).to_rfc3339())
+                .unwrap_or_default()
+        };
+        parts.push(format!("时间 {} ~ {}", bound(window.start_ns), bound(window.end_ns)));
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
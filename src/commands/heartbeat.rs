@@ -0,0 +1,83 @@
+use chrono::Local;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::UdpSocket;
+use tokio::time::Duration;
+
+use crate::auth::Authenticator;
+use crate::cli::HeartbeatArgs;
+use crate::config::FileConfig;
+use crate::heartbeat::{HeartbeatClient, HeartbeatSettings};
+
+// 打印接收统计的间隔
+const STATS_INTERVAL: Duration = Duration::from_secs(5);
+// 单个 shred 数据包不超过 1232 字节, 留出余量
+const PACKET_BUFFER: usize = 2048;
+
+// 维持心跳, 并在本地 UDP 端口接收服务端推送的 shred, 定期打印接收速率
+// 运行中在标准输入输入以逗号分隔的区域列表可以更换区域
+pub async fn run(args: HeartbeatArgs, file: &FileConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = HeartbeatSettings::resolve(&args, file)?;
+    let socket = UdpSocket::bind(settings.listen)
+        .await
+        .map_err(|e| format!("绑定 UDP 地址 {} 失败: {}", settings.listen, e))?;
+    println!("[{}] INFO: 在 {} 接收 shred, 向 {} 登记地址 {}, 区域 {:?}",
+        Local::now().format("%H:%M:%S%.3f"),
+        settings.listen,
+        settings.url,
+        settings.public_addr,
+        settings.regions
+    );
+
+    let auth = settings.auth.map(Authenticator::spawn);
+    let client = HeartbeatClient::spawn(settings.url, settings.public_addr, settings.regions, auth);
+
+    let mut stdin = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdin_open = true;
+    let mut buffer = vec![0u8; PACKET_BUFFER];
+    let (mut packets, mut bytes) = (0u64, 0u64);
+    let mut interval = tokio::time::interval(STATS_INTERVAL);
+    interval.tick().await;
+    loop {
+        tokio::select! {
+            received = socket.recv_from(&mut buffer) => {
+                let (len, _) = received?;
+                packets += 1;
+                bytes += len as u64;
+            }
+            line = stdin.next_line(), if stdin_open => {
+                // 标准输入关闭 (例如在后台运行) 后不再读取
+                let Ok(Some(line)) = line else {
+                    stdin_open = false;
+                    continue;
+                };
+                let regions = parse_regions(&line);
+                if regions.is_empty() {
+                    println!("[{}] WARN: 至少需要指定一个区域, 输入以逗号分隔的区域列表", Local::now().format("%H:%M:%S%.3f"));
+                    continue;
+                }
+                println!("[{}] INFO: 更换区域为 {:?}", Local::now().format("%H:%M:%S%.3f"), regions);
+                client.set_regions(regions);
+            }
+            _ = interval.tick() => {
+                let status = client.status();
+                let heartbeat = match status.last_success {
+                    Some(last) if !status.expired() => format!("正常 (上次成功 {:.1}秒前)", last.elapsed().as_secs_f64()),
+                    Some(last) => format!("已过期 (上次成功 {:.1}秒前, 连续失败 {} 次)", last.elapsed().as_secs_f64(), status.failures),
+                    None => format!("尚未登记 (连续失败 {} 次)", status.failures),
+                };
+                println!("[{}] INFO: {:.1} 包/秒, {:.1} KB/秒, 心跳: {}",
+                    Local::now().format("%H:%M:%S%.3f"),
+                    packets as f64 / STATS_INTERVAL.as_secs_f64(),
+                    bytes as f64 / 1024.0 / STATS_INTERVAL.as_secs_f64(),
+                    heartbeat
+                );
+                packets = 0;
+                bytes = 0;
+            }
+        }
+    }
+}
+
+fn parse_regions(line: &str) -> Vec<String> {
+    line.split(',').map(str::trim).filter(|region| !region.is_empty()).map(str::to_string).collect()
+}
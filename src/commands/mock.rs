@@ -0,0 +1,97 @@
+use chrono::Local;
+use tokio::time::Duration;
+
+use crate::cli::MockArgs;
+use crate::mock::feed::{self, DelayConfig, FeedConfig};
+use crate::mock::auth::{self, MockAuthState};
+use crate::mock::{geyser, heartbeat, searcher, shredstream};
+
+pub async fn run(args: MockArgs) -> Result<(), Box<dyn std::error::Error>> {
+    // 两个模拟服务端共用同一个合成数据源, 推送完全相同的交易
+    let feed = feed::spawn_feed(FeedConfig {
+        start_slot: args.start_slot,
+        slot_duration: Duration::from_millis(args.slot_ms),
+        entries_per_slot: args.entries_per_slot,
+        txs_per_entry: args.txs_per_entry,
+    });
+
+    let shred_config = DelayConfig {
+        delay: Duration::from_millis(args.shred_delay_ms),
+        jitter: Duration::from_millis(args.shred_jitter_ms),
+        drop_rate: args.shred_drop_rate,
+    };
+    println!("[{}] INFO: 模拟 ShredstreamProxy 监听 {}, 延迟 {}ms, 抖动 {}ms, 丢弃率 {}",
+        Local::now().format("%H:%M:%S%.3f"),
+        args.shred_listen,
+        args.shred_delay_ms,
+        args.shred_jitter_ms,
+        args.shred_drop_rate
+    );
+    let auth = args.auth_listen.map(|auth_listen| {
+        println!("[{}] INFO: 模拟鉴权服务监听 {}, 访问令牌有效期 {}秒, 刷新令牌有效期 {}秒",
+            Local::now().format("%H:%M:%S%.3f"),
+            auth_listen,
+            args.auth_access_secs,
+            args.auth_refresh_secs
+        );
+        let state = MockAuthState::new(Duration::from_secs(args.auth_access_secs), Duration::from_secs(args.auth_refresh_secs));
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = auth::serve(auth_listen, server_state).await {
+                println!("[{}] ERROR: 模拟鉴权服务失败: {}", Local::now().format("%H:%M:%S%.3f"), e);
+            }
+        });
+        state
+    });
+    if let Some(heartbeat_listen) = args.heartbeat_listen {
+        println!("[{}] INFO: 模拟 Shredstream 心跳服务监听 {}, 有效期 {}ms",
+            Local::now().format("%H:%M:%S%.3f"), heartbeat_listen, args.heartbeat_ttl_ms);
+        // 与 ShredstreamProxy 使用相同的延迟和丢弃率
+        let server = heartbeat::serve(
+            heartbeat_listen,
+            feed.clone(),
+            shred_config.clone(),
+            Duration::from_millis(args.heartbeat_ttl_ms as u64),
+            auth.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                println!("[{}] ERROR: 模拟心跳服务失败: {}", Local::now().format("%H:%M:%S%.3f"), e);
+            }
+        });
+    }
+    if let Some(searcher_listen) = args.searcher_listen {
+        println!("[{}] INFO: 模拟 SearcherService 监听 {}", Local::now().format("%H:%M:%S%.3f"), searcher_listen);
+        let server = searcher::serve(searcher_listen, feed.clone(), auth.clone());
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                println!("[{}] ERROR: 模拟 SearcherService 失败: {}", Local::now().format("%H:%M:%S%.3f"), e);
+            }
+        });
+    }
+    if let Some(path) = &args.leader_schedule_out {
+        searcher::write_leader_schedule(path).map_err(|e| format!("写入 leader 表 {} 失败: {}", path.display(), e))?;
+        println!("[{}] INFO: 模拟 leader 表已写入 {} (每个 epoch {} 个 slot, 第一个 epoch 从 slot 0 开始)",
+            Local::now().format("%H:%M:%S%.3f"), path.display(), searcher::EPOCH_SLOTS);
+    }
+    let shred_server = tokio::spawn(shredstream::serve(args.shred_listen, feed.clone(), shred_config, auth));
+
+    if let Some(geyser_listen) = args.geyser_listen {
+        let geyser_config = DelayConfig {
+            delay: Duration::from_millis(args.geyser_delay_ms),
+            jitter: Duration::from_millis(args.geyser_jitter_ms),
+            drop_rate: args.geyser_drop_rate,
+        };
+        println!("[{}] INFO: 模拟 Geyser 监听 {}, 延迟 {}ms, 抖动 {}ms, 丢弃率 {}",
+            Local::now().format("%H:%M:%S%.3f"),
+            geyser_listen,
+            args.geyser_delay_ms,
+            args.geyser_jitter_ms,
+            args.geyser_drop_rate
+        );
+        let ping_interval = Duration::from_secs(args.geyser_ping_secs.max(1));
+        tokio::spawn(geyser::serve(geyser_listen, feed, geyser_config, ping_interval)).await??;
+    }
+    shred_server.await??;
+    Ok(())
+}
@@ -0,0 +1,7 @@
+pub mod compare;
+pub mod heartbeat;
+pub mod mock;
+pub mod monitor;
+pub mod searcher;
+pub mod tail;
+pub mod verify;
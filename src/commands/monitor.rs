@@ -0,0 +1,126 @@
+use chrono::Local;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+use crate::capture::CaptureWriter;
+use crate::config::Config;
+use crate::metrics::{self, Metrics};
+use crate::source::{self, CompareMode, SourceEvent, StreamOptions};
+
+// 定义共享结构来存储每个数据源最新的 slot 信息
+struct SlotTracker {
+    labels: Vec<String>,
+    latest_slots: Vec<u64>,
+}
+
+impl SlotTracker {
+    fn new(labels: Vec<String>) -> Self {
+        let latest_slots = vec![0; labels.len()];
+        Self { labels, latest_slots }
+    }
+
+    // 更新某个数据源的最新 slot 并显示对比
+    fn update_slot(&mut self, index: usize, slot: u64) -> bool {
+        let is_new = slot > self.latest_slots[index];
+        if is_new {
+            self.latest_slots[index] = slot;
+            self.print_comparison();
+        }
+        is_new
+    }
+
+    // 打印各数据源与最快者之间的 slot 差距
+    fn print_comparison(&self) {
+        let tip = self.latest_slots.iter().copied().max().unwrap_or(0);
+        let width = self.labels.iter().map(|l| l.len()).max().unwrap_or(0).max(5);
+
+        println!("┌─────────────────────────────────────────────────┐");
+        println!("│ [{}] 实时 Slot 对比                       │",
+            Local::now().format("%H:%M:%S"));
+        println!("├─────────────────────────────────────────────────┤");
+        for (label, slot) in self.labels.iter().zip(&self.latest_slots) {
+            let behind = tip - slot;
+            if behind == 0 {
+                println!("│ 🟢 {:<width$} 最新 Slot: {:12} (领先)", label, slot, width = width);
+            } else {
+                println!("│ 🔴 {:<width$} 最新 Slot: {:12} (落后 {} 个 slot)", label, slot, behind, width = width);
+            }
+        }
+        println!("└─────────────────────────────────────────────────┘");
+    }
+}
+
+pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    println!("⭐ 启动 slot 对比监控 ⭐");
+    println!("目标端点: ");
+    for source in &config.sources {
+        println!("  {} ({}): {}", source.label, source.kind.name(), source.url);
+    }
+    if config.sources.is_empty() {
+        return Err("未配置数据源, 请使用 --source、配置文件或 SOURCES/GRPC_URL/SHRED_URL".into());
+    }
+    if !config.replay.is_empty() {
+        return Err("monitor 不支持 --replay, 请使用 compare 回放抓包文件".into());
+    }
+    let capture = match &config.capture {
+        Some(path) => Some(CaptureWriter::create(path, &config.sources)?),
+        None => None,
+    };
+
+    // 增大通道缓冲区大小，减少背压
+    let (tx, mut rx) = mpsc::channel::<SourceEvent>(config.channel_size * config.sources.len());
+
+    let labels: Vec<String> = config.sources.iter().map(|s| s.label.clone()).collect();
+    let metrics = config.metrics_listen.map(|_| Metrics::new(labels.clone(), config.slot_depth));
+    if let (Some(addr), Some(metrics)) = (config.metrics_listen, &metrics) {
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(addr, metrics).await {
+                println!("[{}] ERROR: 指标导出服务失败: {}", Local::now().format("%H:%M:%S%.3f"), e);
+            }
+        });
+    }
+
+    // 启动订阅任务, 监控只关心 slot
+    let options = StreamOptions { mode: CompareMode::Slot, timeline: false, ..config.stream_options() };
+    source::spawn_sources(&config.sources, options, capture.clone(), metrics.clone(), tx);
+
+    // 创建 slot 跟踪器
+    let mut tracker = SlotTracker::new(labels);
+
+    // 监控持续时间, 为 0 时一直运行
+    let monitor_duration = Duration::from_secs(config.duration);
+    let start_time = tokio::time::Instant::now();
+
+    // 处理接收到的 slot 数据
+    loop {
+        tokio::select! {
+            Some(event) = rx.recv() => {
+                if let Some(metrics) = &metrics {
+                    metrics.observe(event.clone());
+                }
+                match event {
+                    SourceEvent::Arrival(arrival) => {
+                        tracker.update_slot(arrival.source, arrival.slot);
+                    }
+                    SourceEvent::Disconnected(index, _) => {
+                        println!("⚠️ {} 连接断开, 正在重连...", tracker.labels[index]);
+                    }
+                    SourceEvent::Connected(index, _) => {
+                        println!("✅ {} 订阅成功, 开始接收数据...", tracker.labels[index]);
+                    }
+                    SourceEvent::Batch(_) => {}
+                }
+            }
+            _ = tokio::time::sleep_until(start_time + monitor_duration), if config.duration > 0 => {
+                println!("监控时间结束");
+                break;
+            }
+            else => break,
+        }
+    }
+    if let Some(capture) = capture {
+        capture.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use chrono::Local;
+use jito_protos::auth::Role;
+use serde_json::{json, Map, Value};
+
+use crate::auth::{self, Authenticator};
+use crate::cli::SearcherArgs;
+use crate::config::FileConfig;
+use crate::searcher::{ConnectedLeaders, SearcherClient};
+
+// 打印 block engine 的区域、下一个 Jito leader 和各区域连接的 leader 数
+pub async fn run(args: SearcherArgs, file: &FileConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let url = args
+        .url
+        .clone()
+        .or_else(|| file.leaders.as_ref().and_then(|leaders| leaders.searcher_url.clone()))
+        .ok_or("未指定 SearcherService 地址, 请使用 --url 或 [leaders] searcher_url")?;
+    let auth = auth::resolve(
+        args.auth_url.clone().or(file.auth_url.clone()),
+        args.auth_keypair.as_deref().or(file.auth_keypair.as_deref()),
+    )?
+    .map(|auth| Authenticator::spawn(auth.with_role(Role::Searcher)));
+    let mut client = SearcherClient::connect(&url, auth.as_ref())
+        .await
+        .map_err(|e| format!("连接 SearcherService {} 失败: {}", url, e))?;
+
+    let regions = client.regions().await.map_err(|e| format!("查询区域失败: {}", e))?;
+    println!("[{}] INFO: 当前连接的区域 {}, 在线区域: {}",
+        Local::now().format("%H:%M:%S%.3f"), regions.current_region, regions.available_regions.join(", "));
+    let queried = if args.regions.is_empty() { regions.available_regions } else { args.regions.clone() };
+
+    match client.next_scheduled_leader(queried.clone()).await {
+        Ok(next) => println!("[{}] INFO: 当前 slot {}, 下一个 Jito leader {} 在 slot {} (还有 {} 个 slot), 区域 {}",
+            Local::now().format("%H:%M:%S%.3f"),
+            next.current_slot,
+            next.next_leader_identity,
+            next.next_leader_slot,
+            next.next_leader_slot.saturating_sub(next.current_slot),
+            next.next_leader_region
+        ),
+        Err(e) => println!("[{}] WARN: 查询下一个 Jito leader 失败: {}", Local::now().format("%H:%M:%S%.3f"), e),
+    }
+
+    let connected = client
+        .connected_leaders_regioned(queried)
+        .await
+        .map_err(|e| format!("查询各区域连接的 leader 失败: {}", e))?;
+    for (region, leaders) in connected.iter().collect::<BTreeMap<_, _>>() {
+        let slots: usize = leaders.values().map(Vec::len).sum();
+        println!("[{}] INFO: 区域 {}: {} 个 leader, 本 epoch 共 {} 个 leader slot",
+            Local::now().format("%H:%M:%S%.3f"), region, leaders.len(), slots);
+    }
+
+    if let Some(path) = &args.save {
+        std::fs::write(path, serde_json::to_vec_pretty(&to_json(&connected))?)
+            .map_err(|e| format!("写入 {} 失败: {}", path.display(), e))?;
+        println!("[{}] INFO: 各区域连接的 leader 已写入 {}", Local::now().format("%H:%M:%S%.3f"), path.display());
+    }
+    Ok(())
+}
+
+// 与 grpcurl 输出的 GetConnectedLeadersRegioned 响应格式相同
+fn to_json(connected: &ConnectedLeaders) -> Value {
+    let regions: Map<String, Value> = connected
+        .iter()
+        .map(|(region, leaders)| {
+            let leaders: Map<String, Value> =
+                leaders.iter().map(|(leader, slots)| (leader.clone(), json!({ "slots": slots }))).collect();
+            (region.clone(), json!({ "connectedValidators": leaders }))
+        })
+        .collect();
+    json!({ "connectedValidators": regions })
+}
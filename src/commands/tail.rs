@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use chrono::Local;
+use tokio::sync::mpsc;
+
+use crate::cli::TailArgs;
+use crate::config::{Commitment, FileConfig};
+use crate::filter::TransactionFilter;
+use crate::slot_window::DEFAULT_SLOT_DEPTH;
+use crate::source::{self, CompareMode, Source, SourceEvent, SourceKind, StreamOptions};
+use crate::subscription::Subscription;
+
+// 打印单个端点每个 slot 的首次到达时间
+pub async fn run(
+    kind: SourceKind,
+    args: TailArgs,
+    file: &FileConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let source = resolve_source(kind, &args, file)?;
+    let commitment = args.commitment.or(file.commitment).unwrap_or(Commitment::Processed);
+    // 与 compare 使用同样的 [geyser] 订阅和 [filter] 过滤条件
+    let filter = file.filter.as_ref().map(TransactionFilter::from_config).transpose()?;
+    let subscription = Subscription::from_config(&file.geyser.clone().unwrap_or_default(), commitment.to_proto(), filter)?;
+    let options = StreamOptions {
+        mode: CompareMode::Slot,
+        slot_depth: DEFAULT_SLOT_DEPTH,
+        subscription: Arc::new(subscription),
+        auth: None,
+        heartbeat: None,
+        timeline: false,
+    };
+
+    let (tx, mut rx) = mpsc::channel::<SourceEvent>(1000);
+    source::spawn_sources(std::slice::from_ref(&source), options, None, None, tx);
+
+    while let Some(event) = rx.recv().await {
+        match event {
+            SourceEvent::Arrival(arrival) => {
+                println!("Slot: {}, Timestamp: {:.3}", arrival.slot, arrival.timestamp.wall_ms());
+            }
+            SourceEvent::Disconnected(..) => {
+                println!("[{}] WARN: {} 连接断开, 正在重连...", Local::now().format("%H:%M:%S%.3f"), source.label);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// 地址优先级: --url > 配置文件中第一个同类型数据源 > GRPC_URL/SHRED_URL
+fn resolve_source(
+    kind: SourceKind,
+    args: &TailArgs,
+    file: &FileConfig,
+) -> Result<Source, Box<dyn std::error::Error>> {
+    if let Some(url) = &args.url {
+        return Ok(Source { label: kind.name().to_uppercase(), kind, url: url.clone() });
+    }
+    if let Some(source) = file.sources()?.into_iter().find(|s| s.kind == kind) {
+        return Ok(source);
+    }
+    source::sources_from_env()?
+        .into_iter()
+        .find(|s| s.kind == kind)
+        .ok_or_else(|| format!("未找到 {} 端点, 请使用 --url 或设置环境变量", kind.name()).into())
+}
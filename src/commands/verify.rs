@@ -0,0 +1,149 @@
+use chrono::Local;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+
+use crate::capture::{self, CaptureWriter};
+use crate::commands::compare::sleep_until;
+use crate::compare::FINISH_GRACE_SLOTS;
+use crate::config::{Commitment, Config, OutputFormat};
+use crate::source::{self, CompareMode, Source, SourceEvent, SourceKind, StreamOptions};
+use crate::verify::{format_recall, SlotRecall, Verifier};
+
+// 逐 slot 核对 Shredstream 送达的交易是否完整, 以 Geyser 推送的交易为准
+pub async fn run(mut config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let replaying = !config.replay.is_empty();
+    if config.commitment == Commitment::Processed {
+        println!("[{}] WARN: processed 级别的交易可能属于最终被放弃的分叉, 核对结果会偏低, 建议使用 --commitment confirmed",
+            Local::now().format("%H:%M:%S%.3f"));
+    }
+    // Shred 端无法判断交易是否失败, 订阅 Geyser 的失败交易以便把它们单独统计, 而不是计为仅 Shred 端收到
+    for (_, filter) in &mut config.subscription.transactions {
+        if filter.failed == Some(false) {
+            filter.failed = None;
+        }
+    }
+    let options = StreamOptions { mode: CompareMode::Signature, timeline: false, ..config.stream_options() };
+    let (tx, mut rx) = mpsc::channel::<SourceEvent>(config.channel_size * 2);
+
+    let mut capture = None;
+    let sources = if replaying {
+        capture::spawn_replay(&config.replay, &options, tx)?
+    } else {
+        if find_pair(&config.sources).is_some() {
+            if let Some(path) = &config.capture {
+                capture = Some(CaptureWriter::create(path, &config.sources)?);
+            }
+            source::spawn_sources(&config.sources, options, capture.clone(), None, tx);
+        }
+        config.sources.clone()
+    };
+    let Some((grpc, shred)) = find_pair(&sources) else {
+        return Err("verify 需要恰好一个 geyser 数据源和一个 shredstream 或 shreds 数据源".into());
+    };
+
+    let (grpc_label, shred_label) = (sources[grpc].label.as_str(), sources[shred].label.as_str());
+    println!("[{}] INFO: 开始核对 {} 相对 {} 的送达完整性...", Local::now().format("%H:%M:%S%.3f"), shred_label, grpc_label);
+    println!("[{}] INFO: 测试持续时间: {}秒, 预热: {}秒",
+        Local::now().format("%H:%M:%S%.3f"), config.duration, config.warmup);
+    println!("[{}] INFO: slot 落后最新 slot {} 个后核对", Local::now().format("%H:%M:%S%.3f"), config.slot_depth);
+
+    let mut verifier = Verifier::new(grpc, shred, config.slot_depth);
+    verifier.set_window(config.window);
+    let warmup = Duration::from_secs(config.warmup);
+    // duration 为 0 时不限时长
+    let window_end = match config.duration {
+        0 => None,
+        secs => Some(warmup + Duration::from_secs(secs)),
+    };
+    let start_time = Instant::now();
+    let deadline = window_end.map(|end| start_time + end);
+    let mut latest_slots = vec![0; sources.len()];
+    let mut replay_start = None;
+    let mut warming_up = config.warmup > 0;
+    verifier.set_recording(!warming_up);
+
+    loop {
+        tokio::select! {
+            Some(event) = rx.recv() => {
+                let elapsed = if replaying {
+                    let timestamp = event.timestamp().mono_ns;
+                    let first = *replay_start.get_or_insert(timestamp);
+                    Duration::from_nanos(timestamp.saturating_sub(first))
+                } else {
+                    start_time.elapsed()
+                };
+                if window_end.is_some_and(|end| elapsed >= end) {
+                    break;
+                }
+                if warming_up && elapsed >= warmup {
+                    println!("[{}] INFO: 预热结束", Local::now().format("%H:%M:%S%.3f"));
+                    warming_up = false;
+                    verifier.set_recording(true);
+                }
+                let SourceEvent::Arrival(arrival) = event else {
+                    continue;
+                };
+                // 两端都越过结束 slot, 或到达结束时间
+                latest_slots[arrival.source] = latest_slots[arrival.source].max(arrival.slot);
+                let slowest = latest_slots.iter().copied().min().unwrap_or(0);
+                if config.window.is_past(slowest, arrival.timestamp.wall_ns) {
+                    println!("[{}] INFO: 统计窗口结束", Local::now().format("%H:%M:%S%.3f"));
+                    break;
+                }
+                for result in verifier.record(&arrival) {
+                    print_slot(&config, &result, grpc_label, shred_label);
+                }
+            }
+            _ = sleep_until(deadline), if !replaying && deadline.is_some() => break,
+            else => break,
+        }
+    }
+
+    if let Some(capture) = capture {
+        capture.flush()?;
+    }
+    for result in verifier.finish(FINISH_GRACE_SLOTS) {
+        print_slot(&config, &result, grpc_label, shred_label);
+    }
+
+    let report = verifier.report(grpc_label, shred_label);
+    if let Some(path) = &config.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)? + "\n")?;
+        println!("[{}] INFO: 结果已写入 {}", Local::now().format("%H:%M:%S%.3f"), path.display());
+    }
+    match config.output {
+        OutputFormat::Text => verifier.print_stats(grpc_label, shred_label),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+// 恰好一个 Geyser 数据源和一个 Shredstream (或 shreds) 数据源时返回两者的序号
+fn find_pair(sources: &[Source]) -> Option<(usize, usize)> {
+    let find = |matches: fn(SourceKind) -> bool| {
+        let mut matching = sources.iter().enumerate().filter(|(_, s)| matches(s.kind));
+        match (matching.next(), matching.next()) {
+            (Some((index, _)), None) => Some(index),
+            _ => None,
+        }
+    };
+    Some((find(|kind| kind == SourceKind::Geyser)?, find(|kind| kind.is_shred())?))
+}
+
+fn print_slot(config: &Config, result: &SlotRecall, grpc: &str, shred: &str) {
+    if config.output != OutputFormat::Text {
+        return;
+    }
+    println!("[{}] INFO: slot {}: 两端都收到 {}, 仅 {} {}, 仅 {} {}, 失败 {}, 召回率 {}{}",
+        Local::now().format("%H:%M:%S%.3f"),
+        result.slot,
+        result.both,
+        grpc,
+        result.grpc_only,
+        shred,
+        result.shred_only,
+        result.failed,
+        format_recall(result.recall),
+        if result.truncated { ", 疑似截断" } else { "" }
+    );
+}
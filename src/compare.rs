@@ -0,0 +1,906 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Local;
+use serde::Serialize;
+
+use solana_sdk::hash::Hash;
+
+use crate::clock::{ns_to_ms, Timestamp};
+use crate::histogram::{LatencyHistogram, PERCENTILES};
+use crate::leaders::{LeaderSchedule, LeaderStats, LeaderSummary};
+use crate::report::SampleWriter;
+use crate::slot_window::SlotWindow;
+use crate::source::{Arrival, EntryInfo, MatchKey, SourceEvent};
+
+// 单个数据源的统计
+struct SourceStats {
+    label: String,
+    // 首先接收的次数
+    first: u64,
+    // 参与对比的样本数 (至少有两个数据源收到)
+    samples: u64,
+    // 落后于最先到达者时的延迟分布
+    lag: LatencyHistogram,
+    // 是否已成功订阅过, 之后的每次订阅成功都算一次重连
+    connected_once: bool,
+    reconnects: u64,
+    // 断线区间 (开始, 结束), 单调时钟纳秒, 结束为 None 表示仍在重连
+    gaps: Vec<(u64, Option<u64>)>,
+    // 因落在断线区间内而被排除的样本数
+    excluded: u64,
+    // 其他数据源收到、该数据源直到淘汰时仍未收到的匹配键数
+    missed: u64,
+    // 直到淘汰时只有该数据源收到的匹配键数
+    only: u64,
+    // 同一匹配键重复到达的次数
+    duplicates: u64,
+    // 按 entry 对比时与先到达者核对过的 entry 数, 以及 PoH 哈希、交易数不一致的次数
+    entries_checked: u64,
+    hash_mismatches: u64,
+    tx_count_mismatches: u64,
+    // 同一 slot 内其他序号上出现了该哈希, 即 entry 序号与先到达者错位 (例如中途丢了消息), 不计为哈希不一致
+    index_drifts: u64,
+}
+
+impl SourceStats {
+    // [from, to] 时间段内是否处于断线状态
+    fn in_gap(&self, from: u64, to: u64) -> bool {
+        self.gaps
+            .iter()
+            .any(|(start, end)| *start <= to && end.is_none_or(|end| end >= from))
+    }
+
+    // 当前是否处于断线状态
+    fn disconnected(&self) -> bool {
+        self.gaps.last().is_some_and(|(_, end)| end.is_none())
+    }
+
+    fn downtime_ms(&self, now: u64) -> f64 {
+        let downtime_ns: u64 = self
+            .gaps
+            .iter()
+            .map(|(start, end)| end.unwrap_or(now).saturating_sub(*start))
+            .sum();
+        ns_to_ms(downtime_ns)
+    }
+}
+
+// 统计窗口: 只计入 slot 范围和系统时间范围内的样本, 未设置的边界不限制
+// 时间按最先到达者的接收时间判断, 这样同一个样本不会因为落后一方跨过边界而被截断
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct Window {
+    pub from_slot: Option<u64>,
+    pub to_slot: Option<u64>,
+    // Unix 纳秒
+    pub start_ns: Option<u64>,
+    pub end_ns: Option<u64>,
+}
+
+impl Window {
+    pub fn contains(&self, slot: u64, wall_ns: u64) -> bool {
+        self.from_slot.is_none_or(|from| slot >= from)
+            && self.to_slot.is_none_or(|to| slot <= to)
+            && self.start_ns.is_none_or(|start| wall_ns >= start)
+            && self.end_ns.is_none_or(|end| wall_ns < end)
+    }
+
+    // 该 slot 或时间之后不会再有样本落入窗口
+    pub fn is_past(&self, slot: u64, wall_ns: u64) -> bool {
+        self.to_slot.is_some_and(|to| slot > to) || self.end_ns.is_some_and(|end| wall_ns >= end)
+    }
+}
+
+// 运行结束时最近几个 slot 可能仍在传输中, 不判定为漏收
+pub const FINISH_GRACE_SLOTS: u64 = 2;
+
+// 单个匹配键在各数据源的首次到达时间, 以及各数据源报告的 entry 内容 (只在按 entry 对比时有)
+#[derive(Default)]
+struct KeyArrivals {
+    arrivals: Vec<Option<Timestamp>>,
+    entries: Vec<Option<EntryInfo>>,
+    // 第一次到达时是否在记录 (例如不在预热期间)
+    recording: bool,
+    // 已计入延迟统计, 之后到达的数据源计为排除
+    scored: bool,
+}
+
+type Arrivals = HashMap<MatchKey, KeyArrivals>;
+
+// N 路对比器: 记录每个匹配键在各数据源的首次到达时间
+pub struct Comparator {
+    sources: Vec<SourceStats>,
+    // 各数据源的首次到达时间, 按 slot 分组, 只保留最近的 slot
+    seen: SlotWindow<Arrivals>,
+    // 上一个被淘汰的 slot, 与下一个之间缺失的 slot 没有任何数据源收到
+    last_evicted: Option<u64>,
+    // 只在按 slot 或 entry 对比时统计: 按交易对比时大多数 slot 本来就没有匹配的交易
+    holes: Option<u64>,
+    // 两两之间的延迟差: pair_sum[i][j] 为 (t_i - t_j) 之和, 纳秒
+    pair_sum: Vec<Vec<i128>>,
+    pair_count: Vec<Vec<u64>>,
+    total: u64,
+    // 最近一次事件的时间, 用于计算仍未结束的断线时长 (回放时也适用)
+    last_ns: u64,
+    // 为 false 时 (例如预热期间) 只记录到达时间, 不计入统计
+    recording: bool,
+    window: Window,
+    // 逐样本 CSV, 只写入计入统计的样本
+    samples: Option<SampleWriter>,
+    // 按 leader 和 Jito 区域分组的统计, 只在提供 leader 信息时启用
+    leaders: Option<LeaderStats>,
+}
+
+// 单个数据源的结果摘要, 用于 JSON 输出
+#[derive(Serialize)]
+pub struct SourceSummary {
+    pub label: String,
+    pub first: u64,
+    pub first_percent: f64,
+    pub samples: u64,
+    pub lag_mean_ms: f64,
+    pub overall_avg_ms: f64,
+    pub lag_percentiles_ms: BTreeMap<String, f64>,
+    pub lag_max_ms: f64,
+    pub reconnects: u64,
+    pub downtime_ms: f64,
+    pub excluded: u64,
+    pub missed: u64,
+    pub only: u64,
+    pub duplicates: u64,
+    pub entries_checked: u64,
+    pub hash_mismatches: u64,
+    pub tx_count_mismatches: u64,
+    pub index_drifts: u64,
+}
+
+#[derive(Serialize)]
+pub struct Summary {
+    pub total: u64,
+    // 所有数据源都未收到的 slot 数 (slot 序列中的空洞, 包括主网上被跳过的 slot), 按交易对比时不统计
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot_holes: Option<u64>,
+    pub sources: Vec<SourceSummary>,
+    // 两两平均延迟差 (行 - 列, ms), 无共同样本时为 null
+    pub pair_lag_ms: Vec<Vec<Option<f64>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaders: Option<LeaderSummary>,
+}
+
+impl Comparator {
+    // slot_depth: 匹配键按 slot 保留的深度, 落后于最新 slot 超过该值后淘汰
+    pub fn new(labels: Vec<String>, slot_depth: u64) -> Self {
+        let n = labels.len();
+        Self {
+            sources: labels
+                .into_iter()
+                .map(|label| SourceStats {
+                    label,
+                    first: 0,
+                    samples: 0,
+                    lag: LatencyHistogram::new(),
+                    connected_once: false,
+                    reconnects: 0,
+                    gaps: Vec::new(),
+                    excluded: 0,
+                    missed: 0,
+                    only: 0,
+                    duplicates: 0,
+                    entries_checked: 0,
+                    hash_mismatches: 0,
+                    tx_count_mismatches: 0,
+                    index_drifts: 0,
+                })
+                .collect(),
+            seen: SlotWindow::new(slot_depth),
+            last_evicted: None,
+            holes: None,
+            pair_sum: vec![vec![0; n]; n],
+            pair_count: vec![vec![0; n]; n],
+            total: 0,
+            last_ns: 0,
+            recording: true,
+            window: Window::default(),
+            samples: None,
+            leaders: None,
+        }
+    }
+
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+    }
+
+    pub fn set_window(&mut self, window: Window) {
+        self.window = window;
+    }
+
+    // 每个 slot 都应有匹配键时 (按 slot 或 entry 对比) 才统计空洞
+    pub fn set_count_holes(&mut self, enabled: bool) {
+        self.holes = enabled.then_some(0);
+    }
+
+    pub fn set_leaders(&mut self, schedule: LeaderSchedule) {
+        self.leaders = Some(LeaderStats::new(schedule, self.sources.len()));
+    }
+
+    pub fn set_sample_writer(&mut self, samples: SampleWriter) {
+        self.samples = Some(samples);
+    }
+
+    pub fn flush_samples(&mut self) -> std::io::Result<()> {
+        match &mut self.samples {
+            Some(samples) => samples.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn write_sample(&mut self, key: &MatchKey, slot: u64, source: usize, timestamp: Timestamp, lag_ns: u64) {
+        if let Some(samples) = &mut self.samples {
+            let leader = self.leaders.as_ref().and_then(|leaders| leaders.leader(slot));
+            if let Err(e) = samples.write(key, slot, &self.sources[source].label, timestamp, lag_ns, leader) {
+                println!("[{}] WARN: 写入样本文件失败: {}", Local::now().format("%H:%M:%S%.3f"), e);
+            }
+        }
+    }
+
+    // 处理数据源事件, 返回是否有匹配键第一次同时被两个数据源收到
+    pub fn handle(&mut self, event: SourceEvent) -> bool {
+        self.last_ns = self.last_ns.max(event.timestamp().mono_ns);
+        match event {
+            SourceEvent::Arrival(arrival) => self.record(arrival),
+            // 填充时间线由 Timeline 统计
+            SourceEvent::Batch(_) => false,
+            SourceEvent::Disconnected(source, timestamp) => {
+                self.sources[source].gaps.push((timestamp.mono_ns, None));
+                false
+            }
+            SourceEvent::Connected(source, timestamp) => {
+                let stats = &mut self.sources[source];
+                if stats.connected_once {
+                    stats.reconnects += 1;
+                }
+                stats.connected_once = true;
+                if let Some((_, end @ None)) = stats.gaps.last_mut() {
+                    *end = Some(timestamp.mono_ns);
+                }
+                false
+            }
+        }
+    }
+
+    // 记录一次到达, 返回该键是否第一次同时被两个数据源收到
+    // 延迟在所有未断线的数据源都已收到, 或该 slot 被淘汰时统一计算, 此前只记录到达时间
+    pub fn record(&mut self, arrival: Arrival) -> bool {
+        let Arrival { source, key, slot, timestamp, entry, .. } = arrival;
+        let n = self.sources.len();
+        let evicted = self.seen.advance(slot);
+        self.account_evicted(evicted);
+        // 已被淘汰的 slot 在淘汰时已计为漏收
+        let Some(keys) = self.seen.get_or_insert(slot) else {
+            return false;
+        };
+        let recording = self.recording;
+        let state = keys.entry(key).or_insert_with(|| KeyArrivals {
+            arrivals: vec![None; n],
+            entries: Vec::new(),
+            recording,
+            scored: false,
+        });
+        // 只记录首次到达时间, 同一签名在 shred 端可能重复出现
+        if state.arrivals[source].is_some() {
+            if recording {
+                self.sources[source].duplicates += 1;
+            }
+            return false;
+        }
+        state.arrivals[source] = Some(timestamp);
+        // entry 内容在 slot 淘汰时统一核对, 那时才能区分序号错位和真正的不一致
+        if let Some(entry) = entry {
+            state.entries.resize(n, None);
+            state.entries[source] = Some(entry);
+        }
+        // 计分时该数据源处于断线状态, 之后才到达
+        if state.scored {
+            if state.recording {
+                self.sources[source].excluded += 1;
+            }
+            return false;
+        }
+
+        let delivered = state.arrivals.iter().filter(|ts| ts.is_some()).count();
+        let sources = &self.sources;
+        let ready = state.arrivals.iter().zip(sources).all(|(ts, stats)| ts.is_some() || stats.disconnected());
+        if ready {
+            state.scored = true;
+            let (arrivals, recording) = (state.arrivals.clone(), state.recording);
+            self.score(&key, slot, &arrivals, recording);
+        }
+        delivered == 2
+    }
+
+    // 计算一个匹配键的延迟: 最先到达者计为首先接收, 其余数据源按各自的到达时间计算落后延迟;
+    // 落后一方在此期间断线重连时, 延迟来自重连而不是数据源本身, 计为排除
+    fn score(&mut self, key: &MatchKey, slot: u64, arrivals: &[Option<Timestamp>], recording: bool) {
+        let delivered: Vec<(usize, Timestamp)> =
+            arrivals.iter().enumerate().filter_map(|(i, ts)| ts.map(|ts| (i, ts))).collect();
+        if delivered.len() < 2 || !recording {
+            return;
+        }
+        let first_wall = delivered.iter().map(|(_, ts)| ts.wall_ns).min().unwrap();
+        if !self.window.contains(slot, first_wall) {
+            return;
+        }
+
+        // 延迟一律按单调时钟计算, 同时到达时序号小的数据源算作首先接收
+        let (winner, winner_ts) = *delivered.iter().min_by_key(|(i, ts)| (ts.mono_ns, *i)).unwrap();
+        let mut matched = vec![(winner, winner_ts, 0)];
+        for &(source, ts) in &delivered {
+            if source == winner {
+                continue;
+            }
+            if self.sources[source].in_gap(winner_ts.mono_ns, ts.mono_ns) {
+                self.sources[source].excluded += 1;
+                continue;
+            }
+            matched.push((source, ts, ts.mono_ns - winner_ts.mono_ns));
+        }
+        if matched.len() < 2 {
+            return;
+        }
+
+        self.total += 1;
+        self.sources[winner].first += 1;
+        for &(source, ts, lag) in &matched {
+            self.sources[source].samples += 1;
+            if source != winner {
+                self.sources[source].lag.record_us(lag / 1000);
+            }
+            self.write_sample(key, slot, source, ts, lag);
+        }
+        if let Some(leaders) = &mut self.leaders {
+            let lags: Vec<(usize, u64)> = matched[1..].iter().map(|&(source, _, lag)| (source, lag)).collect();
+            leaders.record(slot, winner, &lags);
+        }
+        for (i, &(a, a_ts, _)) in matched.iter().enumerate() {
+            for &(b, b_ts, _) in &matched[i + 1..] {
+                let diff = a_ts.mono_ns as i128 - b_ts.mono_ns as i128;
+                self.pair_sum[a][b] += diff;
+                self.pair_sum[b][a] -= diff;
+                self.pair_count[a][b] += 1;
+                self.pair_count[b][a] += 1;
+            }
+        }
+    }
+
+    // 后到达者报告的 entry 与最先到达者不一致时计入后到达者
+    // 哈希不同但其中一个哈希在该 slot 的其他序号上出现过, 说明是序号错位而不是内容不一致
+    fn check_entries(
+        &mut self,
+        arrivals: &[Option<Timestamp>],
+        entries: &[Option<EntryInfo>],
+        positions: &HashMap<Hash, Vec<u64>>,
+    ) {
+        let Some(first) = (0..entries.len())
+            .filter(|&i| entries[i].is_some())
+            .min_by_key(|&i| arrivals[i].map(|ts| ts.mono_ns))
+        else {
+            return;
+        };
+        let reference = entries[first].unwrap();
+        let elsewhere = |entry: &EntryInfo| {
+            positions.get(&entry.hash).is_some_and(|indices| indices.iter().any(|&i| i != entry.index))
+        };
+        for (source, entry) in entries.iter().enumerate() {
+            let Some(entry) = entry.filter(|_| source != first) else {
+                continue;
+            };
+            let stats = &mut self.sources[source];
+            stats.entries_checked += 1;
+            if entry.hash != reference.hash && (elsewhere(&entry) || elsewhere(&reference)) {
+                stats.index_drifts += 1;
+                continue;
+            }
+            if entry.hash != reference.hash {
+                stats.hash_mismatches += 1;
+            }
+            if entry.transactions != reference.transactions {
+                stats.tx_count_mismatches += 1;
+            }
+        }
+    }
+
+    // 淘汰的匹配键中, 尚未计分的先计算延迟; 有数据源收到而其他数据源始终未收到的, 计为后者漏收,
+    // 只有一个数据源收到时计入其 only; 相邻两个被淘汰的 slot 之间缺失的 slot 计为空洞
+    fn account_evicted(&mut self, evicted: Vec<(u64, Arrivals)>) {
+        for (slot, keys) in evicted {
+            for (key, state) in keys.iter().filter(|(_, state)| !state.scored) {
+                self.score(key, slot, &state.arrivals, state.recording);
+            }
+
+            let previous = self.last_evicted.replace(slot);
+            if !self.recording {
+                continue;
+            }
+            if let (Some(previous), Some(_)) = (previous, self.holes) {
+                let in_window = |s: u64| self.window.from_slot.is_none_or(|from| s >= from) && self.window.to_slot.is_none_or(|to| s <= to);
+                let missing = (previous + 1..slot).filter(|s| in_window(*s)).count() as u64;
+                self.holes = self.holes.map(|holes| holes + missing);
+            }
+
+            // 该 slot 内各哈希被报告过的序号
+            let mut positions: HashMap<Hash, Vec<u64>> = HashMap::new();
+            for entry in keys.values().flat_map(|state| &state.entries).flatten() {
+                positions.entry(entry.hash).or_default().push(entry.index);
+            }
+
+            for KeyArrivals { arrivals, entries, .. } in keys.values() {
+                let Some(first_wall) = arrivals.iter().flatten().map(|ts| ts.wall_ns).min() else {
+                    continue;
+                };
+                if !self.window.contains(slot, first_wall) {
+                    continue;
+                }
+                self.check_entries(arrivals, entries, &positions);
+                let delivered = arrivals.iter().filter(|a| a.is_some()).count();
+                for (stats, arrival) in self.sources.iter_mut().zip(arrivals) {
+                    match arrival {
+                        None => stats.missed += 1,
+                        Some(_) if delivered == 1 && arrivals.len() > 1 => stats.only += 1,
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+    }
+
+    // 运行结束时淘汰除最近几个 slot 以外的所有匹配键, 使漏收统计完整;
+    // 最近几个 slot 不判定漏收, 但已有多个数据源收到的匹配键仍计算延迟
+    pub fn finish(&mut self) {
+        let before = self.seen.tip().saturating_sub(FINISH_GRACE_SLOTS);
+        let evicted = self.seen.evict_before(before);
+        self.account_evicted(evicted);
+        for (slot, keys) in self.seen.evict_before(u64::MAX) {
+            for (key, state) in keys.iter().filter(|(_, state)| !state.scored) {
+                self.score(key, slot, &state.arrivals, state.recording);
+            }
+        }
+    }
+
+    fn first_percent(&self, stats: &SourceStats) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        stats.first as f64 / self.total as f64 * 100.0
+    }
+
+    // 总体平均延迟: 首先接收时计为 0
+    fn overall_avg(stats: &SourceStats) -> f64 {
+        if stats.samples == 0 {
+            return 0.0;
+        }
+        stats.lag.sum_ms() / stats.samples as f64
+    }
+
+    fn pair_lag_ms(&self, i: usize, j: usize) -> Option<f64> {
+        if i == j || self.pair_count[i][j] == 0 {
+            return None;
+        }
+        Some(self.pair_sum[i][j] as f64 / self.pair_count[i][j] as f64 / 1_000_000.0)
+    }
+
+    fn labels(&self) -> Vec<String> {
+        self.sources.iter().map(|stats| stats.label.clone()).collect()
+    }
+
+    pub fn lag_histogram(&self, source: usize) -> &LatencyHistogram {
+        &self.sources[source].lag
+    }
+
+    pub fn summary(&self) -> Summary {
+        let n = self.sources.len();
+        Summary {
+            total: self.total,
+            slot_holes: self.holes,
+            sources: self
+                .sources
+                .iter()
+                .map(|stats| SourceSummary {
+                    label: stats.label.clone(),
+                    first: stats.first,
+                    first_percent: self.first_percent(stats),
+                    samples: stats.samples,
+                    lag_mean_ms: stats.lag.mean_ms(),
+                    overall_avg_ms: Self::overall_avg(stats),
+                    lag_percentiles_ms: PERCENTILES
+                        .iter()
+                        .map(|(name, q)| (name.to_string(), stats.lag.quantile_ms(*q)))
+                        .collect(),
+                    lag_max_ms: stats.lag.max_ms(),
+                    reconnects: stats.reconnects,
+                    downtime_ms: stats.downtime_ms(self.last_ns),
+                    excluded: stats.excluded,
+                    missed: stats.missed,
+                    only: stats.only,
+                    duplicates: stats.duplicates,
+                    entries_checked: stats.entries_checked,
+                    hash_mismatches: stats.hash_mismatches,
+                    tx_count_mismatches: stats.tx_count_mismatches,
+                    index_drifts: stats.index_drifts,
+                })
+                .collect(),
+            pair_lag_ms: (0..n).map(|i| (0..n).map(|j| self.pair_lag_ms(i, j)).collect()).collect(),
+            leaders: self.leaders.as_ref().map(|leaders| leaders.summary(&self.labels())),
+        }
+    }
+
+    pub fn print_stats(&self) {
+        let width = self.sources.iter().map(|s| s.label.len()).max().unwrap_or(0).max(6);
+
+        println!("[{}] INFO: ===== 端点性能对比 ({} 个样本) =====",
+            Local::now().format("%H:%M:%S%.3f"), self.total);
+        for stats in &self.sources {
+            println!("[{}] INFO: {:<width$} : 首先接收 {:6.2}%, 落后时平均延迟 {:6.2}ms, 总体平均延迟 {:6.2}ms",
+                Local::now().format("%H:%M:%S%.3f"),
+                stats.label,
+                self.first_percent(stats),
+                stats.lag.mean_ms(),
+                Self::overall_avg(stats),
+                width = width
+            );
+        }
+        self.print_completeness(width);
+        self.print_entry_consistency(width);
+
+        println!("[{}] INFO: ===== 落后时延迟分位数 =====", Local::now().format("%H:%M:%S%.3f"));
+        for stats in &self.sources {
+            println!("[{}] INFO: {:<width$} : {}",
+                Local::now().format("%H:%M:%S%.3f"), stats.label, stats.lag.summary(), width = width);
+        }
+
+        for stats in &self.sources {
+            println!("[{}] INFO: ===== {} 落后延迟直方图 ({} 个样本) =====",
+                Local::now().format("%H:%M:%S%.3f"), stats.label, stats.lag.count());
+            for line in stats.lag.ascii() {
+                println!("  {}", line);
+            }
+        }
+
+        self.print_pair_matrix(width);
+        self.print_ranking(width);
+        self.print_connection(width);
+        if let Some(leaders) = &self.leaders {
+            leaders.print_stats(&self.labels());
+        }
+    }
+
+    // 送达完整性: 对一个数据源来说, 漏掉数据比慢几毫秒更严重
+    fn print_completeness(&self, width: usize) {
+        println!("[{}] INFO: ===== 送达完整性 =====", Local::now().format("%H:%M:%S%.3f"));
+        for stats in &self.sources {
+            println!("[{}] INFO: {:<width$} : 漏收 {} 个, 仅该端点收到 {} 个, 重复 {} 次",
+                Local::now().format("%H:%M:%S%.3f"),
+                stats.label,
+                stats.missed,
+                stats.only,
+                stats.duplicates,
+                width = width
+            );
+        }
+        if let Some(holes) = self.holes {
+            println!("[{}] INFO: 所有端点都未收到的 slot: {} 个", Local::now().format("%H:%M:%S%.3f"), holes);
+        }
+    }
+
+    // entry 一致性: 只在按 entry 对比时有数据
+    fn print_entry_consistency(&self, width: usize) {
+        if self.sources.iter().all(|stats| stats.entries_checked == 0) {
+            return;
+        }
+        println!("[{}] INFO: ===== Entry 一致性 =====", Local::now().format("%H:%M:%S%.3f"));
+        for stats in &self.sources {
+            println!("[{}] INFO: {:<width$} : 核对 {} 个, PoH 哈希不一致 {} 个, 交易数不一致 {} 个, 序号错位 {} 个",
+                Local::now().format("%H:%M:%S%.3f"),
+                stats.label,
+                stats.entries_checked,
+                stats.hash_mismatches,
+                stats.tx_count_mismatches,
+                stats.index_drifts,
+                width = width
+            );
+        }
+    }
+
+    // 断线重连情况, 断线期间的样本不计入延迟统计
+    fn print_connection(&self, width: usize) {
+        println!("[{}] INFO: ===== 连接稳定性 =====", Local::now().format("%H:%M:%S%.3f"));
+        for stats in &self.sources {
+            println!("[{}] INFO: {:<width$} : 重连 {} 次, 断线总时长 {:.0}ms, 排除样本 {} 个",
+                Local::now().format("%H:%M:%S%.3f"),
+                stats.label,
+                stats.reconnects,
+                stats.downtime_ms(self.last_ns),
+                stats.excluded,
+                width = width
+            );
+        }
+    }
+
+    // 两两平均延迟差矩阵: 行减列, 正数表示行比列慢
+    fn print_pair_matrix(&self, width: usize) {
+        println!("[{}] INFO: ===== 两两平均延迟差 (行 - 列, ms) =====", Local::now().format("%H:%M:%S%.3f"));
+        let mut header = format!("  {:<width$}", "", width = width);
+        for stats in &self.sources {
+            header.push_str(&format!(" {:>width$}", stats.label, width = width));
+        }
+        println!("{}", header);
+
+        for (i, row) in self.sources.iter().enumerate() {
+            let mut line = format!("  {:<width$}", row.label, width = width);
+            for j in 0..self.sources.len() {
+                let cell = match self.pair_lag_ms(i, j) {
+                    Some(lag) => format!("{:+.2}", lag),
+                    None => "-".to_string(),
+                };
+                line.push_str(&format!(" {:>width$}", cell, width = width));
+            }
+            println!("{}", line);
+        }
+    }
+
+    // 按总体平均延迟从小到大排名
+    fn print_ranking(&self, width: usize) {
+        let mut ranked: Vec<&SourceStats> = self.sources.iter().collect();
+        ranked.sort_by(|a, b| Self::overall_avg(a).total_cmp(&Self::overall_avg(b)));
+
+        println!("[{}] INFO: ===== 排名 (按总体平均延迟) =====", Local::now().format("%H:%M:%S%.3f"));
+        for (rank, stats) in ranked.iter().enumerate() {
+            println!("[{}] INFO: #{} {:<width$} : 总体平均延迟 {:6.2}ms, 首先接收 {:6.2}%",
+                Local::now().format("%H:%M:%S%.3f"),
+                rank + 1,
+                stats.label,
+                Self::overall_avg(stats),
+                self.first_percent(stats),
+                width = width
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: usize = 0;
+    const B: usize = 1;
+    const C: usize = 2;
+
+    fn timestamp(ms: u64) -> Timestamp {
+        Timestamp { mono_ns: ms * 1_000_000, wall_ns: ms * 1_000_000 }
+    }
+
+    fn arrive(comparator: &mut Comparator, source: usize, slot: u64, ms: u64) -> bool {
+        comparator.handle(SourceEvent::arrival(source, MatchKey::Slot(slot), slot, timestamp(ms)))
+    }
+
+    // 直方图按有效位数记录, 均值只近似相等
+    fn assert_lag(stats: &SourceSummary, lag_ms: f64) {
+        assert!((stats.lag_mean_ms - lag_ms).abs() < 0.1, "{} 的落后延迟 {}ms, 预期 {}ms", stats.label, stats.lag_mean_ms, lag_ms);
+    }
+
+    fn comparator() -> Comparator {
+        Comparator::new(vec!["a".to_string(), "b".to_string(), "c".to_string()], 10)
+    }
+
+    #[test]
+    fn three_sources_are_scored_once_all_have_reported() {
+        let mut comparator = comparator();
+        assert!(!arrive(&mut comparator, A, 1, 10));
+        assert!(arrive(&mut comparator, B, 1, 15));
+        // 第三个数据源到达之前不计分
+        assert_eq!(comparator.summary().total, 0);
+        assert!(!arrive(&mut comparator, C, 1, 30));
+
+        let summary = comparator.summary();
+        assert_eq!(summary.total, 1);
+        let (a, b, c) = (&summary.sources[A], &summary.sources[B], &summary.sources[C]);
+        assert_eq!((a.first, b.first, c.first), (1, 0, 0));
+        assert_eq!((a.samples, b.samples, c.samples), (1, 1, 1));
+        assert_lag(b, 5.0);
+        assert_lag(c, 20.0);
+        assert_eq!(summary.pair_lag_ms[B][A], Some(5.0));
+        assert_eq!(summary.pair_lag_ms[A][C], Some(-20.0));
+        assert_eq!(summary.pair_lag_ms[C][B], Some(15.0));
+    }
+
+    #[test]
+    fn excluded_second_arrival_does_not_drop_the_sample() {
+        let mut comparator = comparator();
+        // b 在 20ms 断线, 30ms 重连, 40ms 才收到; c 正常收到
+        arrive(&mut comparator, A, 1, 10);
+        comparator.handle(SourceEvent::Disconnected(B, timestamp(20)));
+        comparator.handle(SourceEvent::Connected(B, timestamp(30)));
+        arrive(&mut comparator, B, 1, 40);
+        arrive(&mut comparator, C, 1, 50);
+
+        let summary = comparator.summary();
+        assert_eq!(summary.total, 1);
+        assert_eq!(summary.sources[A].first, 1);
+        assert_eq!((summary.sources[B].samples, summary.sources[B].excluded), (0, 1));
+        assert_eq!(summary.sources[C].samples, 1);
+        assert_lag(&summary.sources[C], 40.0);
+    }
+
+    #[test]
+    fn disconnected_source_does_not_delay_scoring() {
+        let mut comparator = comparator();
+        comparator.handle(SourceEvent::Disconnected(C, timestamp(5)));
+        arrive(&mut comparator, A, 1, 10);
+        arrive(&mut comparator, B, 1, 12);
+        assert_eq!(comparator.summary().total, 1);
+
+        // 重连后才到达的数据源计为排除
+        comparator.handle(SourceEvent::Connected(C, timestamp(20)));
+        arrive(&mut comparator, C, 1, 25);
+        let summary = comparator.summary();
+        assert_eq!(summary.total, 1);
+        assert_eq!((summary.sources[C].samples, summary.sources[C].excluded), (0, 1));
+    }
+
+    #[test]
+    fn lag_percentiles_count_only_trailing_arrivals() {
+        let mut comparator = Comparator::new(vec!["a".to_string(), "b".to_string()], 10);
+        // a 在前四个 slot 领先 2、4、6、8ms, b 在第五个 slot 领先 10ms
+        for (slot, lag) in [(1, 2), (2, 4), (3, 6), (4, 8)] {
+            arrive(&mut comparator, A, slot, slot * 400);
+            arrive(&mut comparator, B, slot, slot * 400 + lag);
+        }
+        arrive(&mut comparator, B, 5, 2000);
+        arrive(&mut comparator, A, 5, 2010);
+
+        let summary = comparator.summary();
+        let (a, b) = (&summary.sources[A], &summary.sources[B]);
+        assert_eq!((a.first, b.first, a.samples, b.samples), (4, 1, 5, 5));
+        assert_eq!((a.first_percent, b.first_percent), (80.0, 20.0));
+        // 落后延迟只统计落后的到达, 总体平均把领先计为 0
+        assert_lag(b, 5.0);
+        assert!((b.overall_avg_ms - 4.0).abs() < 0.1, "{}", b.overall_avg_ms);
+        assert_lag(a, 10.0);
+        assert!((a.overall_avg_ms - 2.0).abs() < 0.1, "{}", a.overall_avg_ms);
+        let percentiles: Vec<&str> = b.lag_percentiles_ms.keys().map(String::as_str).collect();
+        assert_eq!(percentiles, ["p50", "p90", "p99", "p99.9"]);
+        assert!((b.lag_percentiles_ms["p50"] - 4.0).abs() < 0.01);
+        assert!((b.lag_percentiles_ms["p99"] - 8.0).abs() < 0.01);
+        assert!((b.lag_max_ms - 8.0).abs() < 0.01);
+        assert_eq!(comparator.lag_histogram(B).count(), 4);
+    }
+
+    #[test]
+    fn window_bounds_slots_inclusively_and_end_time_exclusively() {
+        assert!(Window::default().contains(0, 0));
+        assert!(!Window::default().is_past(u64::MAX, u64::MAX));
+
+        let window = Window { from_slot: Some(10), to_slot: Some(20), start_ns: Some(1000), end_ns: Some(2000) };
+        assert!(window.contains(10, 1000) && window.contains(20, 1999));
+        assert!(!window.contains(9, 1500) && !window.contains(21, 1500));
+        assert!(!window.contains(15, 999) && !window.contains(15, 2000));
+        // 窗口开始之前不算已过
+        assert!(!window.is_past(9, 999) && !window.is_past(20, 1999));
+        assert!(window.is_past(21, 1500) && window.is_past(15, 2000));
+    }
+
+    #[test]
+    fn samples_outside_the_window_are_not_scored() {
+        let mut comparator = Comparator::new(vec!["a".to_string(), "b".to_string()], 10);
+        comparator.set_window(Window { from_slot: Some(2), to_slot: None, start_ns: None, end_ns: Some(2_000_000_000) });
+        for slot in [1, 2, 3] {
+            arrive(&mut comparator, A, slot, slot * 400);
+            arrive(&mut comparator, B, slot, slot * 400 + 5);
+        }
+        // 最先到达者在结束时间之前收到, 落后一方跨过边界仍计入
+        arrive(&mut comparator, B, 4, 1999);
+        arrive(&mut comparator, A, 4, 2003);
+        // 最先到达者在结束时间之后收到
+        arrive(&mut comparator, A, 5, 2000);
+        arrive(&mut comparator, B, 5, 2010);
+
+        let summary = comparator.summary();
+        assert_eq!(summary.total, 3);
+        assert_eq!((summary.sources[A].first, summary.sources[B].first), (2, 1));
+    }
+
+    #[test]
+    fn keys_first_seen_during_warmup_are_not_scored() {
+        let mut comparator = Comparator::new(vec!["a".to_string(), "b".to_string()], 10);
+        comparator.set_recording(false);
+        arrive(&mut comparator, A, 1, 10);
+        comparator.set_recording(true);
+        // 预热结束后另一端才收到, 仍按首次到达时的状态不计入
+        arrive(&mut comparator, B, 1, 20);
+        arrive(&mut comparator, A, 2, 400);
+        arrive(&mut comparator, B, 2, 410);
+
+        let summary = comparator.summary();
+        assert_eq!(summary.total, 1);
+        assert_eq!(summary.sources[B].samples, 1);
+        assert_lag(&summary.sources[B], 10.0);
+    }
+
+    #[test]
+    fn out_of_order_arrivals_use_their_timestamps() {
+        let mut comparator = comparator();
+        // 通道中的顺序与到达时间相反
+        arrive(&mut comparator, C, 1, 30);
+        arrive(&mut comparator, B, 1, 20);
+        arrive(&mut comparator, A, 1, 10);
+
+        let summary = comparator.summary();
+        assert_eq!(summary.sources[A].first, 1);
+        assert_lag(&summary.sources[B], 10.0);
+        assert_lag(&summary.sources[C], 20.0);
+    }
+
+    #[test]
+    fn partial_keys_are_scored_on_eviction() {
+        let mut comparator = comparator();
+        arrive(&mut comparator, B, 1, 10);
+        arrive(&mut comparator, A, 1, 12);
+        assert_eq!(comparator.summary().total, 0);
+
+        // slot 1 被淘汰时计分, c 计为漏收
+        arrive(&mut comparator, A, 20, 100);
+        let summary = comparator.summary();
+        assert_eq!(summary.total, 1);
+        assert_eq!(summary.sources[B].first, 1);
+        assert_lag(&summary.sources[A], 2.0);
+        assert_eq!(summary.sources[C].missed, 1);
+    }
+
+    #[test]
+    fn holes_are_counted_only_when_enabled() {
+        let mut counted = comparator();
+        counted.set_count_holes(true);
+        let mut uncounted = comparator();
+        for comparator in [&mut counted, &mut uncounted] {
+            for slot in [1, 2, 5] {
+                arrive(comparator, A, slot, slot * 400);
+                arrive(comparator, B, slot, slot * 400 + 1);
+            }
+            // slot 1、2、5 被淘汰, 其间的 3 和 4 没有任何数据源收到
+            arrive(comparator, A, 20, 8000);
+        }
+        assert_eq!(counted.summary().slot_holes, Some(2));
+        assert_eq!(uncounted.summary().slot_holes, None);
+    }
+
+    #[test]
+    fn duplicates_are_counted_outside_warmup() {
+        let mut comparator = comparator();
+        comparator.set_recording(false);
+        arrive(&mut comparator, A, 1, 10);
+        arrive(&mut comparator, A, 1, 11);
+        comparator.set_recording(true);
+        arrive(&mut comparator, A, 2, 20);
+        arrive(&mut comparator, A, 2, 21);
+        arrive(&mut comparator, A, 2, 22);
+        let summary = comparator.summary();
+        assert_eq!((summary.sources[A].duplicates, summary.sources[B].duplicates), (2, 0));
+    }
+
+    #[test]
+    fn evicted_slots_are_accounted_and_ignored_afterwards() {
+        let mut comparator = comparator();
+        arrive(&mut comparator, A, 1, 10);
+        arrive(&mut comparator, B, 2, 410);
+        // slot 1 和 2 落后最新 slot 超过 10 个, 淘汰时各计一次漏收和仅该端收到
+        arrive(&mut comparator, C, 30, 12000);
+        let summary = comparator.summary();
+        assert_eq!((summary.sources[A].only, summary.sources[B].only), (1, 1));
+        assert_eq!((summary.sources[A].missed, summary.sources[B].missed, summary.sources[C].missed), (1, 1, 2));
+
+        // 淘汰后才到达的数据不再参与对比
+        assert!(!arrive(&mut comparator, B, 1, 12001));
+        assert!(!arrive(&mut comparator, C, 1, 12002));
+        let summary = comparator.summary();
+        assert_eq!(summary.total, 0);
+        assert_eq!(summary.sources[B].duplicates + summary.sources[C].duplicates, 0);
+    }
+}
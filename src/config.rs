@@ -0,0 +1,305 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, FixedOffset};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use yellowstone_grpc_proto::geyser::CommitmentLevel;
+
+use crate::auth::{self, AuthConfig};
+use crate::cli::RunArgs;
+use crate::compare::Window;
+use crate::filter::{FilterConfig, TransactionFilter};
+use crate::heartbeat::HeartbeatConfig;
+use crate::leaders::{LeaderSchedule, LeadersConfig};
+use crate::slot_window::DEFAULT_SLOT_DEPTH;
+use crate::source::{self, CompareMode, Source, SourceKind, StreamOptions};
+use crate::subscription::{Subscription, SubscriptionConfig};
+
+// Geyser 订阅的提交级别
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn to_proto(self) -> CommitmentLevel {
+        match self {
+            Commitment::Processed => CommitmentLevel::Processed,
+            Commitment::Confirmed => CommitmentLevel::Confirmed,
+            Commitment::Finalized => CommitmentLevel::Finalized,
+        }
+    }
+}
+
+// 结果输出格式
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+// 配置文件中的数据源
+#[derive(Deserialize)]
+struct SourceEntry {
+    label: String,
+    kind: String,
+    url: String,
+}
+
+// TOML 配置文件, 所有字段可选, 命令行参数优先
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    #[serde(default)]
+    sources: Vec<SourceEntry>,
+    pub duration: Option<u64>,
+    pub warmup: Option<u64>,
+    pub from_slot: Option<u64>,
+    pub to_slot: Option<u64>,
+    // RFC 3339 时间
+    pub start_at: Option<String>,
+    pub end_at: Option<String>,
+    pub commitment: Option<Commitment>,
+    pub mode: Option<CompareMode>,
+    pub output: Option<OutputFormat>,
+    pub channel_size: Option<usize>,
+    pub slot_depth: Option<u64>,
+    pub metrics_listen: Option<SocketAddr>,
+    pub auth_url: Option<String>,
+    pub auth_keypair: Option<PathBuf>,
+    pub filter: Option<FilterConfig>,
+    pub geyser: Option<SubscriptionConfig>,
+    pub heartbeat: Option<HeartbeatConfig>,
+    pub leaders: Option<LeadersConfig>,
+}
+
+impl FileConfig {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取配置文件 {} 失败: {}", path.display(), e))?;
+        let config = toml::from_str(&content)
+            .map_err(|e| format!("解析配置文件 {} 失败: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    pub fn sources(&self) -> Result<Vec<Source>, String> {
+        self.sources
+            .iter()
+            .map(|entry| {
+                let kind = SourceKind::parse(&entry.kind)
+                    .ok_or_else(|| format!("数据源 {} 的类型无效: {}", entry.label, entry.kind))?;
+                Ok(Source { label: entry.label.clone(), kind, url: entry.url.clone() })
+            })
+            .collect()
+    }
+}
+
+// 合并命令行、配置文件和环境变量后的运行参数
+pub struct Config {
+    pub sources: Vec<Source>,
+    pub duration: u64,
+    pub warmup: u64,
+    pub window: Window,
+    pub commitment: Commitment,
+    pub subscription: Subscription,
+    pub mode: CompareMode,
+    pub output: OutputFormat,
+    pub channel_size: usize,
+    pub slot_depth: u64,
+    pub capture: Option<PathBuf>,
+    pub replay: Vec<PathBuf>,
+    pub metrics_listen: Option<SocketAddr>,
+    pub report: Option<PathBuf>,
+    pub samples_csv: Option<PathBuf>,
+    pub timeline: Option<PathBuf>,
+    pub auth: Option<AuthConfig>,
+    // shreds 数据源的心跳参数
+    pub heartbeat: Option<HeartbeatConfig>,
+    // 按 leader 分组统计用的 leader 信息
+    pub leaders: Option<LeaderSchedule>,
+    pub searcher_url: Option<String>,
+}
+
+impl Config {
+    pub fn compare(args: &RunArgs, file: &FileConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::resolve(args, file, 30, 100, Commitment::Processed)
+    }
+
+    pub fn monitor(args: &RunArgs, file: &FileConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::resolve(args, file, 3600, 1000, Commitment::Processed)
+    }
+
+    // 核对以确认的交易为准, 默认 confirmed
+    pub fn verify(args: &RunArgs, file: &FileConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::resolve(args, file, 60, 1000, Commitment::Confirmed)
+    }
+
+    // 优先级: 命令行 > 配置文件 > 环境变量 (.env) > 默认值
+    pub fn resolve(
+        args: &RunArgs,
+        file: &FileConfig,
+        default_duration: u64,
+        default_channel_size: usize,
+        default_commitment: Commitment,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let sources = if !args.sources.is_empty() {
+            args.sources
+                .iter()
+                .map(|item| source::parse_source(item).ok_or_else(|| format!("无效的数据源: {}", item)))
+                .collect::<Result<Vec<_>, _>>()?
+        } else if !file.sources.is_empty() {
+            file.sources()?
+        } else {
+            source::sources_from_env()?
+        };
+
+        // 时间边界转换为 Unix 纳秒
+        let parse_time = |arg: Option<DateTime<FixedOffset>>, file: &Option<String>| match arg {
+            Some(time) => Ok(time.timestamp_nanos_opt()),
+            None => file
+                .as_deref()
+                .map(|s| DateTime::parse_from_rfc3339(s).map_err(|e| format!("无效的时间 {}: {}", s, e)))
+                .transpose()
+                .map(|time| time.and_then(|t| t.timestamp_nanos_opt())),
+        };
+        let window = Window {
+            from_slot: args.from_slot.or(file.from_slot),
+            to_slot: args.to_slot.or(file.to_slot),
+            start_ns: parse_time(args.start_at, &file.start_at)?.map(|ns| ns as u64),
+            end_ns: parse_time(args.end_at, &file.end_at)?.map(|ns| ns as u64),
+        };
+
+        // 命令行指定了交易过滤参数
+        let filter_args = args.vote.is_some()
+            || args.failed.is_some()
+            || !args.account_include.is_empty()
+            || !args.account_exclude.is_empty()
+            || !args.account_required.is_empty();
+        // 账户列表在命令行指定时整体覆盖配置文件
+        let mut filter = file.filter.clone().unwrap_or_default();
+        filter.vote = args.vote.or(filter.vote);
+        filter.failed = args.failed.or(filter.failed);
+        for (arg, field) in [
+            (&args.account_include, &mut filter.account_include),
+            (&args.account_exclude, &mut filter.account_exclude),
+            (&args.account_required, &mut filter.account_required),
+        ] {
+            if !arg.is_empty() {
+                *field = arg.clone();
+            }
+        }
+        let filter = TransactionFilter::from_config(&filter)?;
+        // 没有给出 [filter] 表也没有命令行过滤参数时由订阅决定是否使用默认的过滤条件
+        let filter = (filter_args || file.filter.is_some()).then_some(filter);
+
+        // 命令行指定了交易过滤参数时忽略 [geyser] 中的命名交易过滤条件
+        let commitment = args.commitment.or(file.commitment).unwrap_or(default_commitment);
+        let mut geyser = file.geyser.clone().unwrap_or_default();
+        if filter_args {
+            geyser.transactions.clear();
+        }
+        // 按 entry 对比时需要订阅 Geyser 的 entry 推送; 对比不使用交易推送,
+        // 没有明确配置交易过滤条件时不再订阅默认的交易, 见 Subscription::from_config
+        let mode = args.mode.or(file.mode).unwrap_or_else(CompareMode::from_env);
+        if mode.is_entry() && geyser.entry.is_empty() {
+            geyser.entry.push("entry".to_string());
+        }
+        let subscription = Subscription::from_config(&geyser, commitment.to_proto(), filter)?;
+
+        let auth = auth::resolve(
+            args.auth_url.clone().or(file.auth_url.clone()),
+            args.auth_keypair.as_deref().or(file.auth_keypair.as_deref()),
+        )?;
+
+        // 命令行逐项覆盖 [leaders]
+        let mut leaders = file.leaders.clone().unwrap_or_default();
+        leaders.schedule = args.leader_schedule.clone().or(leaders.schedule);
+        leaders.first_slot = args.leader_first_slot.or(leaders.first_slot);
+        leaders.regions = args.leader_regions.clone().or(leaders.regions);
+        let searcher_url = args.searcher_url.clone().or(leaders.searcher_url.clone());
+        let leaders = LeaderSchedule::resolve(&leaders)?;
+
+        // 设置了窗口终点时默认不限时长, 由窗口决定何时结束
+        let default_duration = if window.to_slot.is_some() || window.end_ns.is_some() { 0 } else { default_duration };
+
+        Ok(Self {
+            sources,
+            duration: args.duration.or(file.duration).unwrap_or(default_duration),
+            warmup: args.warmup.or(file.warmup).unwrap_or(0),
+            window,
+            commitment,
+            subscription,
+            mode,
+            output: args.output.or(file.output).unwrap_or(OutputFormat::Text),
+            channel_size: args.channel_size.or(file.channel_size).unwrap_or(default_channel_size),
+            slot_depth: args.slot_depth.or(file.slot_depth).unwrap_or(DEFAULT_SLOT_DEPTH),
+            capture: args.capture.clone(),
+            replay: args.replay.clone(),
+            metrics_listen: args.metrics_listen.or(file.metrics_listen),
+            report: args.report.clone(),
+            samples_csv: args.samples_csv.clone(),
+            timeline: args.timeline.clone(),
+            auth,
+            heartbeat: file.heartbeat.clone(),
+            leaders,
+            searcher_url,
+        })
+    }
+
+    pub fn stream_options(&self) -> StreamOptions {
+        StreamOptions {
+            mode: self.mode,
+            slot_depth: self.slot_depth,
+            subscription: Arc::new(self.subscription.clone()),
+            auth: self.auth.clone(),
+            heartbeat: self.heartbeat.clone(),
+            timeline: self.timeline.is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(args: RunArgs, file: &str) -> Config {
+        let args = RunArgs {
+            sources: vec!["grpc=geyser:http://127.0.0.1:10000".to_string(), "shred=shredstream:http://127.0.0.1:9999".to_string()],
+            mode: Some(CompareMode::Entry),
+            ..args
+        };
+        let file: FileConfig = toml::from_str(file).unwrap();
+        Config::resolve(&args, &file, 60, 1000, Commitment::Processed).unwrap()
+    }
+
+    fn transactions(config: &Config) -> Vec<&str> {
+        config.subscription.transactions.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn entry_mode_subscribes_only_entries_by_default() {
+        let config = resolve(RunArgs::default(), "");
+        assert_eq!(config.subscription.entry, ["entry"]);
+        assert!(transactions(&config).is_empty());
+
+        // 已配置的 entry 订阅保持不变
+        let config = resolve(RunArgs::default(), "[geyser]\nentry = [\"entries\"]\n");
+        assert_eq!(config.subscription.entry, ["entries"]);
+        assert!(transactions(&config).is_empty());
+    }
+
+    #[test]
+    fn entry_mode_keeps_explicit_transaction_filters() {
+        assert_eq!(transactions(&resolve(RunArgs::default(), "[filter]\nvote = false\n")), ["client"]);
+        assert_eq!(transactions(&resolve(RunArgs { vote: Some(true), ..Default::default() }, "")), ["client"]);
+        let config = resolve(RunArgs::default(), "[geyser.transactions.all]\n");
+        assert_eq!(transactions(&config), ["all"]);
+        assert_eq!(config.subscription.entry, ["entry"]);
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+
+use solana_sdk::hash::Hash;
+use solana_sdk::message::v0::MessageAddressTableLookup;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::clock::Timestamp;
+use crate::slot_window::{SlotWindow, DEFAULT_SLOT_DEPTH};
+
+// ShredstreamProxy 推送的一条 Entry 消息 (或本地还原的一个批次) 解码后的结果: 同一个 slot 中连续的若干 entry
+pub struct SlotBatch {
+    pub slot: u64,
+    // 该消息是本 slot 的第几条消息, 从 0 开始
+    pub batch_index: usize,
+    // 本地还原的批次前面还有批次未还原时为 false, 此时 entry 序号和交易位置从 0 开始, 不是 slot 内的位置
+    pub positioned: bool,
+    pub entries: Vec<DecodedEntry>,
+}
+
+pub struct DecodedEntry {
+    // 在 slot 内的序号: Shredstream 消息按之前收到的消息中的 entry 数累加, 中途丢消息时会偏小;
+    // 本地还原的批次按之前各批次的 entry 数确定
+    pub index: u64,
+    // 第一笔交易在 slot 内的位置, 与 Geyser 的交易序号对应, 与 entry 序号的确定方式相同
+    pub starting_transaction_index: u64,
+    pub num_hashes: u64,
+    // PoH 哈希
+    pub hash: Hash,
+    pub transactions: Vec<DecodedTransaction>,
+}
+
+pub struct DecodedTransaction {
+    pub signatures: Vec<Signature>,
+    // 消息中的静态账户, v0 交易通过地址查找表加载的账户不在其中
+    pub account_keys: Vec<Pubkey>,
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+    pub instructions: Vec<DecodedInstruction>,
+    pub transaction: VersionedTransaction,
+}
+
+pub struct DecodedInstruction {
+    pub program_id: Pubkey,
+    // 指令引用的账户, 来自地址查找表的账户无法在本地解析, 为 None
+    pub accounts: Vec<Option<Pubkey>>,
+    pub data: Vec<u8>,
+}
+
+impl DecodedTransaction {
+    pub fn from_versioned(transaction: VersionedTransaction) -> Self {
+        let message = &transaction.message;
+        let account_keys = message.static_account_keys().to_vec();
+        let resolve = |index: u8| account_keys.get(index as usize).copied();
+        let instructions = message
+            .instructions()
+            .iter()
+            .map(|ix| DecodedInstruction {
+                program_id: resolve(ix.program_id_index).unwrap_or_default(),
+                accounts: ix.accounts.iter().map(|index| resolve(*index)).collect(),
+                data: ix.data.clone(),
+            })
+            .collect();
+        Self {
+            signatures: transaction.signatures.clone(),
+            address_table_lookups: message.address_table_lookups().map(<[_]>::to_vec).unwrap_or_default(),
+            account_keys,
+            instructions,
+            transaction,
+        }
+    }
+
+    // 第一个签名即交易签名
+    pub fn signature(&self) -> Option<&Signature> {
+        self.signatures.first()
+    }
+
+    // 与 Geyser 的 is_vote 相同, 按 agave 的 is_simple_vote_transaction 判定:
+    // 旧格式消息, 少于 3 个签名, 只有一条指令且调用投票程序
+    pub fn is_vote(&self) -> bool {
+        matches!(self.transaction.message, VersionedMessage::Legacy(_))
+            && self.signatures.len() < 3
+            && matches!(self.instructions.as_slice(), [ix] if ix.program_id == solana_sdk::vote::program::ID)
+    }
+}
+
+// 把 Shredstream 的 Entry 消息解码为 SlotBatch, 并按 slot 累计 entry 序号
+pub struct EntryParser {
+    // 每个 slot 已收到的消息数、entry 数和交易数
+    slots: SlotWindow<(usize, u64, u64)>,
+    // 本地还原的批次按 shred 序号确定位置
+    shred_slots: SlotWindow<ShredSlot>,
+}
+
+// 一个 slot 内本地还原的批次
+#[derive(Default)]
+struct ShredSlot {
+    // 已还原的批次: 第一个 shred 的序号 -> (最后一个 shred 的序号, entry 数, 交易数)
+    batches: BTreeMap<u32, (u32, u64, u64)>,
+    // 等待前面的批次还原才能确定位置的批次: 第一个 shred 的序号 -> (entries, 到达时间)
+    pending: BTreeMap<u32, (Vec<solana_entry::entry::Entry>, Timestamp)>,
+}
+
+impl ShredSlot {
+    // 从 shred 0 开始首尾相连的批次才能确定位置, 返回 first_shred 之前的批次数、entry 数和交易数
+    fn offsets(&self, first_shred: u32) -> Option<(usize, u64, u64)> {
+        let (mut next_shred, mut offsets) = (0, (0, 0, 0));
+        for (&first, &(last, entries, transactions)) in &self.batches {
+            if first != next_shred {
+                return None;
+            }
+            if first == first_shred {
+                return Some(offsets);
+            }
+            offsets = (offsets.0 + 1, offsets.1 + entries, offsets.2 + transactions);
+            next_shred = last.checked_add(1)?;
+        }
+        None
+    }
+}
+
+impl Default for EntryParser {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLOT_DEPTH)
+    }
+}
+
+impl EntryParser {
+    pub fn new(slot_depth: u64) -> Self {
+        Self { slots: SlotWindow::new(slot_depth), shred_slots: SlotWindow::new(slot_depth) }
+    }
+
+    // entries 为 bincode 编码的 Vec<solana_entry::entry::Entry>; 无法解码时返回错误,
+    // 已淘汰的旧 slot 不再累计, batch_index 和 entry 序号都从 0 开始
+    pub fn parse(&mut self, slot: u64, entries: &[u8]) -> bincode::Result<SlotBatch> {
+        let entries = bincode::deserialize::<Vec<solana_entry::entry::Entry>>(entries)?;
+        self.slots.advance(slot);
+        let mut fallback = (0, 0, 0);
+        let (batches, next_index, next_transaction) = self.slots.get_or_insert(slot).unwrap_or(&mut fallback);
+        let batch = SlotBatch::decode(slot, (*batches, *next_index, *next_transaction), true, entries);
+        *batches += 1;
+        *next_index += batch.entries.len() as u64;
+        *next_transaction += batch.transactions().count() as u64;
+        Ok(batch)
+    }
+
+    // 本地还原的批次, first_shred 和 last_shred 为批次首尾的数据 shred 序号; 返回的批次按 shred 序号排列
+    // 前面还有批次未还原时位置未知: hold 为 true 时先保留, 位置确定后连同到达时间一起返回 (之后的批次也随之返回),
+    // 否则立即返回 positioned 为 false 的批次; 该 slot 淘汰时仍未确定位置的批次丢弃
+    pub fn parse_shreds(
+        &mut self,
+        slot: u64,
+        first_shred: u32,
+        last_shred: u32,
+        entries: &[u8],
+        timestamp: Timestamp,
+        hold: bool,
+    ) -> bincode::Result<Vec<(SlotBatch, Timestamp)>> {
+        let entries = bincode::deserialize::<Vec<solana_entry::entry::Entry>>(entries)?;
+        self.shred_slots.advance(slot);
+        let Some(shred_slot) = self.shred_slots.get_or_insert(slot) else {
+            return Ok(Vec::new());
+        };
+        let transactions = entries.iter().map(|entry| entry.transactions.len() as u64).sum();
+        shred_slot.batches.insert(first_shred, (last_shred, entries.len() as u64, transactions));
+
+        let Some(offsets) = shred_slot.offsets(first_shred) else {
+            if hold {
+                shred_slot.pending.insert(first_shred, (entries, timestamp));
+                return Ok(Vec::new());
+            }
+            let batch_index = shred_slot.batches.range(..first_shred).count();
+            return Ok(vec![(SlotBatch::decode(slot, (batch_index, 0, 0), false, entries), timestamp)]);
+        };
+        let mut batches = vec![(SlotBatch::decode(slot, offsets, true, entries), timestamp)];
+        // 等待中的后续批次可能因此确定了位置, 位置按 shred 序号依次确定, 第一个仍未确定时后面的也未确定
+        while let Some(offsets) = shred_slot.pending.keys().next().and_then(|&first| shred_slot.offsets(first)) {
+            let (_, (entries, timestamp)) = shred_slot.pending.pop_first().unwrap();
+            batches.push((SlotBatch::decode(slot, offsets, true, entries), timestamp));
+        }
+        Ok(batches)
+    }
+}
+
+impl SlotBatch {
+    // offsets: 之前的批次数、entry 数和交易数
+    fn decode(slot: u64, offsets: (usize, u64, u64), positioned: bool, entries: Vec<solana_entry::entry::Entry>) -> Self {
+        let (batch_index, mut next_index, mut next_transaction) = offsets;
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let index = next_index;
+                next_index += 1;
+                let starting_transaction_index = next_transaction;
+                next_transaction += entry.transactions.len() as u64;
+                DecodedEntry {
+                    index,
+                    starting_transaction_index,
+                    num_hashes: entry.num_hashes,
+                    hash: entry.hash,
+                    transactions: entry.transactions.into_iter().map(DecodedTransaction::from_versioned).collect(),
+                }
+            })
+            .collect();
+        Self { slot, batch_index, positioned, entries }
+    }
+
+    pub fn transactions(&self) -> impl Iterator<Item = &DecodedTransaction> {
+        self.entries.iter().flat_map(|entry| &entry.transactions)
+    }
+
+    // 交易及其在 slot 内的位置, 位置未知时为 None
+    pub fn positioned_transactions(&self) -> impl Iterator<Item = (Option<u64>, &DecodedTransaction)> {
+        self.entries.iter().flat_map(|entry| {
+            (entry.starting_transaction_index..)
+                .map(|position| self.positioned.then_some(position))
+                .zip(&entry.transactions)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_entry::entry::{next_entry_mut, Entry};
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::{v0, Message};
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::system_transaction;
+
+    use super::*;
+
+    // signers 个签名者, 不签名; 第一个签名者付费
+    fn transaction(signers: usize, instructions: &[Instruction], v0: bool) -> DecodedTransaction {
+        let payer = Pubkey::new_unique();
+        let extra: Vec<AccountMeta> = (1..signers).map(|_| AccountMeta::new_readonly(Pubkey::new_unique(), true)).collect();
+        let mut instructions = instructions.to_vec();
+        instructions[0].accounts.extend(extra);
+        let message = if v0 {
+            VersionedMessage::V0(v0::Message::try_compile(&payer, &instructions, &[], Hash::default()).unwrap())
+        } else {
+            VersionedMessage::Legacy(Message::new(&instructions, Some(&payer)))
+        };
+        let signatures = vec![Signature::default(); message.header().num_required_signatures as usize];
+        DecodedTransaction::from_versioned(VersionedTransaction { signatures, message })
+    }
+
+    fn instruction(program_id: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(program_id, &[1, 2, 3], vec![AccountMeta::new(Pubkey::new_unique(), false)])
+    }
+
+    fn vote() -> Instruction {
+        instruction(solana_sdk::vote::program::ID)
+    }
+
+    #[test]
+    fn simple_vote_transactions_are_votes() {
+        assert!(transaction(1, &[vote()], false).is_vote());
+        assert!(transaction(2, &[vote()], false).is_vote());
+        let decoded = transaction(1, &[vote()], false);
+        assert_eq!(decoded.instructions[0].program_id, solana_sdk::vote::program::ID);
+        assert_eq!(decoded.instructions[0].data, [1, 2, 3]);
+    }
+
+    #[test]
+    fn other_vote_program_transactions_are_not_votes() {
+        // 签名过多、指令多于一条、v0 消息或调用其他程序
+        assert!(!transaction(3, &[vote()], false).is_vote());
+        assert!(!transaction(1, &[vote(), instruction(Pubkey::new_unique())], false).is_vote());
+        assert!(!transaction(1, &[vote()], true).is_vote());
+        assert!(!transaction(1, &[instruction(Pubkey::new_unique())], false).is_vote());
+    }
+
+    #[test]
+    fn entry_and_transaction_indexes_continue_across_messages() {
+        let mut hash = Hash::new_unique();
+        let mut message = |counts: &[usize]| {
+            let entries: Vec<Entry> = counts
+                .iter()
+                .map(|count| {
+                    let transactions =
+                        (0..*count).map(|_| system_transaction::transfer(&Keypair::new(), &Pubkey::new_unique(), 1, hash)).collect();
+                    next_entry_mut(&mut hash, 1, transactions)
+                })
+                .collect();
+            bincode::serialize(&entries).unwrap()
+        };
+        let (first, second) = (message(&[2, 0]), message(&[3]));
+
+        let mut parser = EntryParser::default();
+        let batch = parser.parse(7, &first).unwrap();
+        assert_eq!((batch.batch_index, batch.entries.len(), batch.transactions().count()), (0, 2, 2));
+        let batch = parser.parse(7, &second).unwrap();
+        assert_eq!(batch.batch_index, 1);
+        assert_eq!((batch.entries[0].index, batch.entries[0].starting_transaction_index), (2, 2));
+        let positions: Vec<Option<u64>> = batch.positioned_transactions().map(|(position, _)| position).collect();
+        assert_eq!(positions, [Some(2), Some(3), Some(4)]);
+        // 其他 slot 从 0 开始
+        assert_eq!(parser.parse(8, &first).unwrap().batch_index, 0);
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use yellowstone_grpc_proto::geyser::SubscribeRequestFilterTransactions;
+
+use crate::entries::DecodedTransaction;
+
+// 配置文件中的 [filter] 表, 字段与 SubscribeRequestFilterTransactions 相同
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterConfig {
+    pub vote: Option<bool>,
+    pub failed: Option<bool>,
+    #[serde(default)]
+    pub account_include: Vec<String>,
+    #[serde(default)]
+    pub account_exclude: Vec<String>,
+    #[serde(default)]
+    pub account_required: Vec<String>,
+}
+
+// 交易过滤条件, Geyser 端作为订阅条件发给服务端, Shredstream 端在客户端对解码后的交易按相同语义过滤:
+//   vote / failed: 为 Some 时只保留投票 (失败) 状态与之相同的交易
+//   account_include: 非空时交易至少包含其中一个账户
+//   account_exclude: 交易不包含其中任何账户
+//   account_required: 交易包含其中所有账户
+// Shredstream 端的限制: 交易尚未执行, 无法判断是否失败, failed = Some(true) 时不匹配任何交易, 否则不过滤;
+// 只能看到消息中的静态账户, 通过地址查找表加载的账户不参与匹配
+#[derive(Clone)]
+pub struct TransactionFilter {
+    pub vote: Option<bool>,
+    pub failed: Option<bool>,
+    pub account_include: HashSet<Pubkey>,
+    pub account_exclude: HashSet<Pubkey>,
+    pub account_required: HashSet<Pubkey>,
+}
+
+// 默认排除投票交易和失败交易
+impl Default for TransactionFilter {
+    fn default() -> Self {
+        Self {
+            vote: Some(false),
+            failed: Some(false),
+            account_include: HashSet::new(),
+            account_exclude: HashSet::new(),
+            account_required: HashSet::new(),
+        }
+    }
+}
+
+impl TransactionFilter {
+    pub fn from_config(config: &FilterConfig) -> Result<Self, String> {
+        let defaults = Self::default();
+        Ok(Self {
+            vote: config.vote.or(defaults.vote),
+            failed: config.failed.or(defaults.failed),
+            account_include: parse_pubkeys(&config.account_include)?,
+            account_exclude: parse_pubkeys(&config.account_exclude)?,
+            account_required: parse_pubkeys(&config.account_required)?,
+        })
+    }
+
+    pub fn to_proto(&self) -> SubscribeRequestFilterTransactions {
+        let strings = |keys: &HashSet<Pubkey>| keys.iter().map(Pubkey::to_string).collect();
+        SubscribeRequestFilterTransactions {
+            vote: self.vote,
+            failed: self.failed,
+            signature: None,
+            account_include: strings(&self.account_include),
+            account_exclude: strings(&self.account_exclude),
+            account_required: strings(&self.account_required),
+        }
+    }
+
+    pub fn matches(&self, transaction: &DecodedTransaction) -> bool {
+        if self.vote.is_some_and(|vote| vote != transaction.is_vote()) {
+            return false;
+        }
+        if self.failed == Some(true) {
+            return false;
+        }
+        let keys = &transaction.account_keys;
+        if !self.account_include.is_empty() && !keys.iter().any(|k| self.account_include.contains(k)) {
+            return false;
+        }
+        if keys.iter().any(|k| self.account_exclude.contains(k)) {
+            return false;
+        }
+        self.account_required.iter().all(|k| keys.contains(k))
+    }
+}
+
+pub(crate) fn parse_pubkeys(keys: &[String]) -> Result<HashSet<Pubkey>, String> {
+    keys.iter()
+        .map(|key| Pubkey::from_str(key).map_err(|e| format!("无效的账户地址 {}: {}", key, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::signature::Signature;
+    use solana_sdk::transaction::VersionedTransaction;
+
+    use super::*;
+
+    // 调用 program_id 的旧格式交易, accounts 为指令引用的账户
+    fn transaction(program_id: Pubkey, accounts: &[Pubkey]) -> DecodedTransaction {
+        let metas = accounts.iter().map(|key| AccountMeta::new(*key, false)).collect();
+        let instruction = Instruction::new_with_bytes(program_id, &[0], metas);
+        let message = Message::new_with_blockhash(&[instruction], Some(&Pubkey::new_unique()), &Hash::default());
+        let message = VersionedMessage::Legacy(message);
+        DecodedTransaction::from_versioned(VersionedTransaction { signatures: vec![Signature::default()], message })
+    }
+
+    fn keys<const N: usize>(keys: [Pubkey; N]) -> HashSet<Pubkey> {
+        keys.into_iter().collect()
+    }
+
+    #[test]
+    fn default_filter_drops_votes() {
+        let filter = TransactionFilter::default();
+        let (vote, other) = (transaction(solana_sdk::vote::program::ID, &[]), transaction(Pubkey::new_unique(), &[]));
+        assert!(!filter.matches(&vote) && filter.matches(&other));
+
+        let votes = TransactionFilter { vote: Some(true), ..Default::default() };
+        assert!(votes.matches(&vote) && !votes.matches(&other));
+        let all = TransactionFilter { vote: None, ..Default::default() };
+        assert!(all.matches(&vote) && all.matches(&other));
+    }
+
+    #[test]
+    fn failed_transactions_cannot_be_matched_before_execution() {
+        let transaction = transaction(Pubkey::new_unique(), &[]);
+        assert!(!TransactionFilter { failed: Some(true), ..Default::default() }.matches(&transaction));
+        assert!(TransactionFilter { failed: None, ..Default::default() }.matches(&transaction));
+    }
+
+    #[test]
+    fn accounts_follow_include_exclude_and_required_rules() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let program = Pubkey::new_unique();
+        let with_a = transaction(program, &[a]);
+        let with_ab = transaction(program, &[a, b]);
+        let with_c = transaction(program, &[c]);
+
+        let include = TransactionFilter { account_include: keys([a, b]), ..Default::default() };
+        assert!(include.matches(&with_a) && include.matches(&with_ab) && !include.matches(&with_c));
+        // 调用的程序也是交易的账户
+        assert!(TransactionFilter { account_include: keys([program]), ..Default::default() }.matches(&with_c));
+
+        let exclude = TransactionFilter { account_exclude: keys([b]), ..Default::default() };
+        assert!(exclude.matches(&with_a) && !exclude.matches(&with_ab) && exclude.matches(&with_c));
+
+        let required = TransactionFilter { account_required: keys([a, b]), ..Default::default() };
+        assert!(!required.matches(&with_a) && required.matches(&with_ab) && !required.matches(&with_c));
+
+        // 三个条件同时满足
+        let combined = TransactionFilter {
+            account_include: keys([a, c]),
+            account_exclude: keys([c]),
+            account_required: keys([b]),
+            ..Default::default()
+        };
+        assert!(!combined.matches(&with_a) && combined.matches(&with_ab) && !combined.matches(&with_c));
+    }
+
+    #[test]
+    fn config_keeps_defaults_and_rejects_invalid_accounts() {
+        let key = Pubkey::new_unique();
+        let config = FilterConfig { vote: Some(true), account_include: vec![key.to_string()], ..Default::default() };
+        let filter = TransactionFilter::from_config(&config).unwrap();
+        assert_eq!((filter.vote, filter.failed), (Some(true), Some(false)));
+        assert_eq!(filter.account_include, keys([key]));
+        let proto = filter.to_proto();
+        assert_eq!((proto.vote, proto.failed, proto.account_include), (Some(true), Some(false), vec![key.to_string()]));
+
+        let config = FilterConfig { account_required: vec!["not-a-key".to_string()], ..Default::default() };
+        let error = TransactionFilter::from_config(&config).err().unwrap();
+        assert!(error.starts_with("无效的账户地址 not-a-key"), "{}", error);
+    }
+}
@@ -0,0 +1,85 @@
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient};
+use yellowstone_grpc_proto::geyser::{SubscribeRequest, SubscribeUpdate};
+use yellowstone_grpc_proto::prost::bytes::Buf;
+use yellowstone_grpc_proto::prost::Message as _;
+use yellowstone_grpc_proto::tonic::client::Grpc;
+use yellowstone_grpc_proto::tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder, Streaming};
+use yellowstone_grpc_proto::tonic::codegen::http::uri::PathAndQuery;
+use yellowstone_grpc_proto::tonic::{Request, Status};
+
+use crate::clock::Timestamp;
+
+const SUBSCRIBE_PATH: &str = "/geyser.Geyser/Subscribe";
+
+// 带接收时间的 SubscribeUpdate
+// 时间戳在 tonic 收齐一帧、交给解码器时记录, 不包含 protobuf 解码耗时, 与 Shredstream 端的口径一致
+pub struct TimestampedUpdate {
+    pub timestamp: Timestamp,
+    // 帧的 protobuf 编码长度
+    pub len: usize,
+    pub update: SubscribeUpdate,
+}
+
+// 请求按 prost 编码, 推送在解码之前打时间戳
+#[derive(Clone, Copy, Default)]
+pub struct TimestampedCodec;
+
+pub struct RequestEncoder;
+
+pub struct TimestampedDecoder;
+
+impl Codec for TimestampedCodec {
+    type Encode = SubscribeRequest;
+    type Decode = TimestampedUpdate;
+    type Encoder = RequestEncoder;
+    type Decoder = TimestampedDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        RequestEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        TimestampedDecoder
+    }
+}
+
+impl Encoder for RequestEncoder {
+    type Item = SubscribeRequest;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, buf: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(buf).map_err(|e| Status::internal(format!("编码 SubscribeRequest 失败: {}", e)))
+    }
+}
+
+impl Decoder for TimestampedDecoder {
+    type Item = TimestampedUpdate;
+    type Error = Status;
+
+    fn decode(&mut self, buf: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        let timestamp = Timestamp::now();
+        let len = buf.remaining();
+        let update = SubscribeUpdate::decode(buf).map_err(|e| Status::internal(format!("解码 SubscribeUpdate 失败: {}", e)))?;
+        Ok(Some(TimestampedUpdate { timestamp, len, update }))
+    }
+}
+
+// 订阅 Geyser: 返回发送后续请求 (例如回复 ping) 的通道和带时间戳的推送流
+pub async fn subscribe(
+    url: &str,
+    request: SubscribeRequest,
+) -> Result<(mpsc::UnboundedSender<SubscribeRequest>, Streaming<TimestampedUpdate>), Box<dyn std::error::Error + Send + Sync>> {
+    // 地址解析和 TLS 设置与 yellowstone 客户端相同, 只替换 Subscribe 调用的解码器
+    let builder = GeyserGrpcClient::build_from_shared(url.to_string())?.tls_config(ClientTlsConfig::new().with_native_roots())?;
+    let mut grpc = Grpc::new(builder.endpoint.connect().await?);
+    grpc.ready().await.map_err(|e| format!("Geyser 服务未就绪: {}", e))?;
+
+    let (requests, rx) = mpsc::unbounded_channel();
+    requests.send(request)?;
+    let response = grpc
+        .streaming(Request::new(UnboundedReceiverStream::new(rx)), PathAndQuery::from_static(SUBSCRIBE_PATH), TimestampedCodec)
+        .await?;
+    Ok((requests, response.into_inner()))
+}
@@ -0,0 +1,227 @@
+use std::net::SocketAddr;
+
+use chrono::Local;
+use jito_protos::shared::Socket;
+use jito_protos::shredstream::shredstream_client::ShredstreamClient;
+use jito_protos::shredstream::Heartbeat;
+use jito_protos::tonic::transport::Endpoint;
+use serde::Deserialize;
+use tokio::sync::watch;
+use tokio::time::{Duration, Instant};
+
+use crate::auth::{self, AuthConfig, Authenticator, OptionalBearer};
+use crate::backoff::Backoff;
+use crate::cli::HeartbeatArgs;
+use crate::config::FileConfig;
+
+// shredstream-proxy 默认的 UDP 端口
+const DEFAULT_LISTEN: &str = "0.0.0.0:20000";
+// 服务端返回的有效期过短时, 两次心跳之间的最小间隔
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+type HeartbeatResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+// 配置文件中的 [heartbeat] 表
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HeartbeatConfig {
+    // block engine 的 Shredstream 服务地址
+    pub url: Option<String>,
+    // 本地接收 shred 的 UDP 地址
+    pub listen: Option<SocketAddr>,
+    // 向服务端登记的地址, 服务端把 shred 发往这里, 必须是本机的公网地址
+    pub public_addr: Option<SocketAddr>,
+    #[serde(default)]
+    pub regions: Vec<String>,
+}
+
+// 合并命令行和配置文件后的心跳参数
+pub struct HeartbeatSettings {
+    pub url: String,
+    pub listen: SocketAddr,
+    pub public_addr: SocketAddr,
+    pub regions: Vec<String>,
+    pub auth: Option<AuthConfig>,
+}
+
+impl HeartbeatSettings {
+    // 优先级: 命令行 > 配置文件 > 默认值
+    pub fn resolve(args: &HeartbeatArgs, file: &FileConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let table = file.heartbeat.clone().unwrap_or_default();
+        let url = args.url.clone().or(table.url).ok_or("未指定 Shredstream 服务地址, 请使用 --url 或 [heartbeat] url")?;
+        let listen = match args.listen.or(table.listen) {
+            Some(listen) => listen,
+            None => DEFAULT_LISTEN.parse()?,
+        };
+        let public_addr = registered_addr(listen, args.public_addr.or(table.public_addr))?;
+        let regions = if args.regions.is_empty() { table.regions } else { args.regions.clone() };
+        if regions.is_empty() {
+            return Err("至少需要指定一个区域, 请使用 --region 或 [heartbeat] regions".into());
+        }
+        let auth = auth::resolve(
+            args.auth_url.clone().or(file.auth_url.clone()),
+            args.auth_keypair.as_deref().or(file.auth_keypair.as_deref()),
+        )?;
+        Ok(Self { url, listen, public_addr, regions, auth })
+    }
+}
+
+impl HeartbeatConfig {
+    // 为 shreds 数据源维持心跳, 没有设置 url 时不发送
+    pub fn spawn_for(&self, listen: SocketAddr, auth: Option<Authenticator>) -> Result<Option<HeartbeatClient>, String> {
+        let Some(url) = &self.url else {
+            return Ok(None);
+        };
+        let socket = registered_addr(listen, self.public_addr)?;
+        Ok(Some(HeartbeatClient::spawn(url.clone(), socket, self.regions.clone(), auth)))
+    }
+}
+
+// 向服务端登记的地址, 监听所有网卡时无法推断, 必须另外指定
+pub fn registered_addr(listen: SocketAddr, public_addr: Option<SocketAddr>) -> Result<SocketAddr, String> {
+    match public_addr {
+        Some(addr) => Ok(addr),
+        None if !listen.ip().is_unspecified() => Ok(listen),
+        None => Err(format!("监听地址 {} 不是具体地址, 请使用 --public-addr 或 public_addr 指定登记的地址", listen)),
+    }
+}
+
+// 心跳的当前状态
+#[derive(Clone, Default)]
+pub struct HeartbeatStatus {
+    // 最近一次成功登记的区域
+    pub regions: Vec<String>,
+    // 服务端要求的心跳有效期
+    pub ttl: Option<Duration>,
+    pub last_success: Option<Instant>,
+    // 上次成功之后连续失败的次数
+    pub failures: u32,
+}
+
+impl HeartbeatStatus {
+    // 上次成功的心跳已超过有效期, 服务端可能已停止推送 shred
+    pub fn expired(&self) -> bool {
+        match (self.last_success, self.ttl) {
+            (Some(last), Some(ttl)) => last.elapsed() > ttl,
+            _ => true,
+        }
+    }
+}
+
+// 后台向 Shredstream 服务登记本地 UDP 地址和区域, 在有效期过半时重发
+// 失败时按退避重试, 区域变更后立即重发; 丢弃后任务退出
+pub struct HeartbeatClient {
+    regions: watch::Sender<Vec<String>>,
+    status: watch::Receiver<HeartbeatStatus>,
+}
+
+impl HeartbeatClient {
+    pub fn spawn(url: String, socket: SocketAddr, regions: Vec<String>, auth: Option<Authenticator>) -> Self {
+        let (regions_tx, regions_rx) = watch::channel(regions);
+        let (status_tx, status_rx) = watch::channel(HeartbeatStatus::default());
+        tokio::spawn(maintain(url, socket, regions_rx, auth, status_tx));
+        Self { regions: regions_tx, status: status_rx }
+    }
+
+    // 更换订阅的区域, 立即发送一次心跳
+    pub fn set_regions(&self, regions: Vec<String>) {
+        self.regions.send_replace(regions);
+    }
+
+    pub fn status(&self) -> HeartbeatStatus {
+        self.status.borrow().clone()
+    }
+}
+
+async fn maintain(
+    url: String,
+    socket: SocketAddr,
+    mut regions: watch::Receiver<Vec<String>>,
+    auth: Option<Authenticator>,
+    status: watch::Sender<HeartbeatStatus>,
+) {
+    let mut backoff = Backoff::default();
+    // 已经提示过过期的那次成功心跳, 每次中断只提示一次
+    let mut warned = None;
+    loop {
+        let result = heartbeat_loop(&url, socket, &mut regions, auth.as_ref(), &status, &mut backoff).await;
+        let Err(e) = result else {
+            return;
+        };
+        status.send_modify(|status| status.failures += 1);
+        let delay = backoff.next_delay();
+        println!("[{}] WARN: 心跳失败: {}, {:.1}秒后第 {} 次重试",
+            Local::now().format("%H:%M:%S%.3f"), e, delay.as_secs_f64(), backoff.attempt());
+        let current = status.borrow().clone();
+        if current.last_success.is_some() && current.expired() && warned != current.last_success {
+            warned = current.last_success;
+            println!("[{}] WARN: 心跳已超过有效期, 服务端可能已停止推送 shred", Local::now().format("%H:%M:%S%.3f"));
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            // 区域变更时立即重试
+            changed = regions.changed() => if changed.is_err() {
+                return;
+            },
+        }
+    }
+}
+
+// 连接并不断发送心跳, 只在出错或 HeartbeatClient 被丢弃时返回
+async fn heartbeat_loop(
+    url: &str,
+    socket: SocketAddr,
+    regions: &mut watch::Receiver<Vec<String>>,
+    auth: Option<&Authenticator>,
+    status: &watch::Sender<HeartbeatStatus>,
+    backoff: &mut Backoff,
+) -> HeartbeatResult<()> {
+    if let Some(auth) = auth {
+        auth.ready().await?;
+    }
+    let channel = Endpoint::from_shared(url.to_string())?.connect().await?;
+    let mut client = ShredstreamClient::with_interceptor(channel, OptionalBearer::new(auth));
+
+    loop {
+        let current = regions.borrow_and_update().clone();
+        let response = client
+            .send_heartbeat(Heartbeat {
+                socket: Some(Socket { ip: socket.ip().to_string(), port: socket.port() as i64 }),
+                regions: current.clone(),
+            })
+            .await?
+            .into_inner();
+        let ttl = Duration::from_millis(response.ttl_ms as u64);
+        backoff.reset();
+
+        let previous = status.send_replace(HeartbeatStatus {
+            regions: current.clone(),
+            ttl: Some(ttl),
+            last_success: Some(Instant::now()),
+            failures: 0,
+        });
+        if previous.last_success.is_none() {
+            println!("[{}] INFO: 心跳登记成功, 地址 {}, 区域 {:?}, 有效期 {}ms",
+                Local::now().format("%H:%M:%S%.3f"), socket, current, ttl.as_millis());
+        } else {
+            if previous.failures > 0 {
+                println!("[{}] INFO: 心跳在失败 {} 次后恢复", Local::now().format("%H:%M:%S%.3f"), previous.failures);
+            }
+            if previous.regions != current {
+                println!("[{}] INFO: 心跳区域变更: {:?} -> {:?}",
+                    Local::now().format("%H:%M:%S%.3f"), previous.regions, current);
+            }
+            if previous.ttl != Some(ttl) {
+                println!("[{}] INFO: 心跳有效期变更为 {}ms", Local::now().format("%H:%M:%S%.3f"), ttl.as_millis());
+            }
+        }
+
+        // 有效期过半时重发, 区域变更时立即重发
+        tokio::select! {
+            _ = tokio::time::sleep((ttl / 2).max(MIN_INTERVAL)) => {}
+            changed = regions.changed() => if changed.is_err() {
+                return Ok(());
+            },
+        }
+    }
+}
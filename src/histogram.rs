@@ -0,0 +1,184 @@
+use hdrhistogram::Histogram;
+
+// 延迟直方图, 内部以微秒为单位记录, 展示时换算为毫秒
+pub struct LatencyHistogram {
+    hist: Histogram<u64>,
+}
+
+// 上限 1 小时, 3 位有效数字
+const MAX_LATENCY_US: u64 = 3_600_000_000;
+const SIGNIFICANT_FIGURES: u8 = 3;
+const BAR_WIDTH: u64 = 40;
+
+// 报告中展示的分位数
+pub const PERCENTILES: [(&str, f64); 4] = [
+    ("p50", 0.50),
+    ("p90", 0.90),
+    ("p99", 0.99),
+    ("p99.9", 0.999),
+];
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            hist: Histogram::new_with_bounds(1, MAX_LATENCY_US, SIGNIFICANT_FIGURES).unwrap(),
+        }
+    }
+
+    pub fn record_us(&mut self, lag_us: u64) {
+        self.hist.saturating_record(lag_us);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.hist.len()
+    }
+
+    pub fn mean_ms(&self) -> f64 {
+        if self.hist.is_empty() {
+            return 0.0;
+        }
+        self.hist.mean() / 1000.0
+    }
+
+    pub fn sum_ms(&self) -> f64 {
+        self.mean_ms() * self.count() as f64
+    }
+
+    pub fn quantile_ms(&self, quantile: f64) -> f64 {
+        if self.hist.is_empty() {
+            return 0.0;
+        }
+        self.hist.value_at_quantile(quantile) as f64 / 1000.0
+    }
+
+    // 不超过 upper_us 的样本数, 用于导出累积分桶
+    pub fn count_le_us(&self, upper_us: u64) -> u64 {
+        if self.hist.is_empty() {
+            return 0;
+        }
+        self.hist.count_between(0, upper_us)
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        if self.hist.is_empty() {
+            return 0.0;
+        }
+        self.hist.max() as f64 / 1000.0
+    }
+
+    // 单行分位数摘要, 例如 "p50 1.20ms p90 3.40ms ... max 12.00ms"
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = PERCENTILES
+            .iter()
+            .map(|(name, q)| format!("{} {:.2}ms", name, self.quantile_ms(*q)))
+            .collect();
+        parts.push(format!("max {:.2}ms", self.max_ms()));
+        parts.join(", ")
+    }
+
+    // 按 2 的幂划分毫秒区间的 ASCII 直方图, 便于观察双峰分布
+    pub fn ascii(&self) -> Vec<String> {
+        if self.hist.is_empty() {
+            return vec!["(无样本)".to_string()];
+        }
+
+        let buckets: Vec<(u64, u64)> = self
+            .hist
+            .iter_log(1000, 2.0)
+            .map(|v| (v.value_iterated_to(), v.count_since_last_iteration()))
+            .collect();
+        let peak = buckets.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+
+        let mut lower_ms = 0.0;
+        let mut lines = Vec::with_capacity(buckets.len());
+        for (upper_us, count) in buckets {
+            let upper_ms = (upper_us + 1) as f64 / 1000.0;
+            let bar = "█".repeat((count * BAR_WIDTH / peak) as usize);
+            lines.push(format!(
+                "{:>9.1} - {:>9.1}ms | {:<width$} {}",
+                lower_ms,
+                upper_ms,
+                bar,
+                count,
+                width = BAR_WIDTH as usize
+            ));
+            lower_ms = upper_ms;
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 3 位有效数字, 换算后的毫秒值只近似相等
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() <= expected * 0.001 + 0.001, "{} 与预期 {} 不符", actual, expected);
+    }
+
+    // 1ms 到 100ms 各一个样本
+    fn uniform() -> LatencyHistogram {
+        let mut hist = LatencyHistogram::new();
+        (1..=100).for_each(|ms| hist.record_us(ms * 1000));
+        hist
+    }
+
+    #[test]
+    fn empty_histogram_reports_zero() {
+        let hist = LatencyHistogram::new();
+        assert_eq!(hist.count(), 0);
+        assert_eq!((hist.mean_ms(), hist.sum_ms(), hist.quantile_ms(0.5), hist.max_ms()), (0.0, 0.0, 0.0, 0.0));
+        assert_eq!(hist.count_le_us(1_000_000), 0);
+        assert_eq!(hist.summary(), "p50 0.00ms, p90 0.00ms, p99 0.00ms, p99.9 0.00ms, max 0.00ms");
+        assert_eq!(hist.ascii(), ["(无样本)"]);
+    }
+
+    #[test]
+    fn percentiles_follow_recorded_lags() {
+        let hist = uniform();
+        assert_eq!(hist.count(), 100);
+        assert_close(hist.mean_ms(), 50.5);
+        assert_close(hist.sum_ms(), 5050.0);
+        assert_close(hist.quantile_ms(0.5), 50.0);
+        assert_close(hist.quantile_ms(0.9), 90.0);
+        assert_close(hist.quantile_ms(0.99), 99.0);
+        assert_close(hist.max_ms(), 100.0);
+        assert_eq!(hist.count_le_us(10_000), 10);
+        assert!(hist.summary().starts_with("p50 50.0"), "{}", hist.summary());
+    }
+
+    #[test]
+    fn lags_beyond_the_upper_bound_are_clamped() {
+        let mut hist = LatencyHistogram::new();
+        hist.record_us(MAX_LATENCY_US * 10);
+        assert_eq!(hist.count(), 1);
+        assert_close(hist.max_ms(), MAX_LATENCY_US as f64 / 1000.0);
+    }
+
+    #[test]
+    fn ascii_buckets_double_in_width() {
+        // 双峰分布: 1ms 附近 10 个样本, 100ms 附近 5 个
+        let mut hist = LatencyHistogram::new();
+        (0..10).for_each(|_| hist.record_us(900));
+        (0..5).for_each(|_| hist.record_us(100_000));
+        let lines = hist.ascii();
+
+        // 区间上界依次为 1、2、4 ... 128ms
+        assert_eq!(lines.len(), 8);
+        assert!(lines[0].starts_with("      0.0 -       1.0ms | "), "{}", lines[0]);
+        assert!(lines[7].starts_with("     64.0 -     128.0ms | "), "{}", lines[7]);
+        let counts: Vec<u64> = lines.iter().map(|line| line.rsplit(' ').next().unwrap().parse().unwrap()).collect();
+        assert_eq!(counts, [10, 0, 0, 0, 0, 0, 0, 5]);
+        // 样本最多的区间占满宽度, 其余按比例缩放
+        assert_eq!(lines[0].matches('█').count(), BAR_WIDTH as usize);
+        assert_eq!(lines[7].matches('█').count(), BAR_WIDTH as usize / 2);
+        assert_eq!(lines[1].matches('█').count(), 0);
+    }
+}
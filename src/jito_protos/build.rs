@@ -7,10 +7,16 @@ fn main() {
         std::env::set_var(PROTOC_ENVAR, protobuf_src::protoc());
     }
 
+    // 同时生成客户端和服务端代码, 服务端用于本地模拟测试
     configure()
+        .build_client(true)
+        .build_server(true)
         .compile(
             &[
                 "protos/auth.proto",
+                "protos/bundle.proto",
+                "protos/packet.proto",
+                "protos/searcher.proto",
                 "protos/shared.proto",
                 "protos/shredstream.proto",
             ],
@@ -6,6 +6,23 @@ pub mod auth {
     tonic::include_proto!("auth");
 }
 
+pub mod packet {
+    tonic::include_proto!("packet");
+}
+
+pub mod bundle {
+    tonic::include_proto!("bundle");
+}
+
+pub mod searcher {
+    tonic::include_proto!("searcher");
+}
+
 pub mod shredstream {
     tonic::include_proto!("shredstream");
 }
+
+// 重新导出与生成代码匹配的 prost / tonic 版本, 供上层编解码和实现服务端使用
+pub use prost;
+pub use prost_types;
+pub use tonic;
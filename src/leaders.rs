@@ -0,0 +1,421 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// 按 leader 分组时最多输出的行数, 完整结果见结果文档
+const LEADER_ROWS: usize = 20;
+// 没有 leader 信息的 slot, 以及不在 Jito 区域列表中的 leader
+const UNKNOWN_LEADER: &str = "未知";
+const NON_JITO: &str = "非 Jito";
+
+// 配置文件中的 [leaders] 表
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LeadersConfig {
+    // getLeaderSchedule 的响应或 solana leader-schedule --output json 的输出
+    pub schedule: Option<PathBuf>,
+    // getLeaderSchedule 返回的是 epoch 内的偏移, 需要加上 epoch 的第一个 slot
+    pub first_slot: Option<u64>,
+    // Jito searcher GetConnectedLeadersRegioned 的响应 (JSON)
+    pub regions: Option<PathBuf>,
+    // Jito SearcherService 地址, 运行时查询各区域连接的 leader
+    pub searcher_url: Option<String>,
+}
+
+// 离线的 leader 信息: slot -> leader, leader -> 连接的 Jito 区域 (按名称排序)
+#[derive(Clone, Default)]
+pub struct LeaderSchedule {
+    leaders: HashMap<u64, String>,
+    regions: HashMap<String, Vec<String>>,
+}
+
+// getLeaderSchedule 的响应, 可以带或不带 JSON-RPC 外层
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScheduleFile {
+    Rpc { result: HashMap<String, Vec<u64>> },
+    Cli {
+        #[serde(rename = "leaderScheduleEntries")]
+        entries: Vec<CliScheduleEntry>,
+    },
+    Result(HashMap<String, Vec<u64>>),
+}
+
+#[derive(Deserialize)]
+struct CliScheduleEntry {
+    slot: u64,
+    leader: String,
+}
+
+// grpcurl 输出使用 camelCase, uint64 输出为字符串
+#[derive(Deserialize)]
+struct RegionedLeaders {
+    #[serde(rename = "connectedValidators", alias = "connected_validators")]
+    regions: HashMap<String, ConnectedLeaders>,
+}
+
+#[derive(Deserialize)]
+struct ConnectedLeaders {
+    #[serde(default, rename = "connectedValidators", alias = "connected_validators")]
+    validators: HashMap<String, SlotList>,
+}
+
+#[derive(Default, Deserialize)]
+struct SlotList {
+    #[serde(default)]
+    slots: Vec<Value>,
+}
+
+impl LeaderSchedule {
+    // 按配置加载, 既没有 leader 表也没有区域列表时返回 None
+    pub fn resolve(config: &LeadersConfig) -> Result<Option<Self>, String> {
+        if config.schedule.is_none() && config.regions.is_none() {
+            return Ok(None);
+        }
+        let mut schedule = Self::default();
+        if let Some(path) = &config.schedule {
+            schedule.load_schedule(path, config.first_slot)?;
+        }
+        if let Some(path) = &config.regions {
+            schedule.load_regions(path)?;
+        }
+        Ok(Some(schedule))
+    }
+
+    pub fn load_schedule(&mut self, path: &Path, first_slot: Option<u64>) -> Result<(), String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("读取 leader 表 {} 失败: {}", path.display(), e))?;
+        let file: ScheduleFile =
+            serde_json::from_str(&content).map_err(|e| format!("解析 leader 表 {} 失败: {}", path.display(), e))?;
+        match file {
+            ScheduleFile::Cli { entries } => {
+                self.leaders.extend(entries.into_iter().map(|entry| (entry.slot, entry.leader)));
+            }
+            ScheduleFile::Rpc { result: offsets } | ScheduleFile::Result(offsets) => {
+                let first_slot = first_slot.ok_or_else(|| {
+                    format!("{} 中是 epoch 内的 slot 偏移, 请用 --leader-first-slot 或 [leaders] first_slot 指定 epoch 的第一个 slot", path.display())
+                })?;
+                for (leader, offsets) in offsets {
+                    self.leaders.extend(offsets.into_iter().map(|offset| (first_slot + offset, leader.clone())));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // 区域列表中的 slot 同样用于补充 leader 表
+    pub fn load_regions(&mut self, path: &Path) -> Result<(), String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("读取区域列表 {} 失败: {}", path.display(), e))?;
+        let file: RegionedLeaders =
+            serde_json::from_str(&content).map_err(|e| format!("解析区域列表 {} 失败: {}", path.display(), e))?;
+        for (region, connected) in file.regions {
+            for (leader, slots) in connected.validators {
+                self.add_connected(&leader, &region, slots.slots.iter().filter_map(parse_slot));
+            }
+        }
+        Ok(())
+    }
+
+    // 记录 leader 连接的 Jito 区域, 其 slot 用于补充 leader 表; 同一个 leader 可以连接多个区域
+    pub fn add_connected(&mut self, leader: &str, region: &str, slots: impl IntoIterator<Item = u64>) {
+        for slot in slots {
+            self.leaders.entry(slot).or_insert_with(|| leader.to_string());
+        }
+        let regions = self.regions.entry(leader.to_string()).or_default();
+        if let Err(position) = regions.binary_search_by(|r| r.as_str().cmp(region)) {
+            regions.insert(position, region.to_string());
+        }
+    }
+
+    pub fn leader(&self, slot: u64) -> Option<&str> {
+        self.leaders.get(&slot).map(String::as_str)
+    }
+
+    // leader 连接的所有区域; 没有区域数据时为空, 有区域数据但 leader 不在其中时为非 Jito
+    pub fn regions(&self, leader: &str) -> Vec<&str> {
+        if self.regions.is_empty() {
+            return Vec::new();
+        }
+        match self.regions.get(leader) {
+            Some(regions) => regions.iter().map(String::as_str).collect(),
+            None => vec![NON_JITO],
+        }
+    }
+
+    pub fn slots(&self) -> usize {
+        self.leaders.len()
+    }
+
+    pub fn has_regions(&self) -> bool {
+        !self.regions.is_empty()
+    }
+}
+
+fn parse_slot(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+// 某个分组中单个数据源的统计
+#[derive(Clone, Default)]
+struct GroupSourceStats {
+    first: u64,
+    samples: u64,
+    lagged: u64,
+    lag_sum_ns: u128,
+}
+
+#[derive(Default)]
+struct GroupStats {
+    total: u64,
+    sources: Vec<GroupSourceStats>,
+}
+
+impl GroupStats {
+    fn source(&mut self, source: usize, n: usize) -> &mut GroupSourceStats {
+        if self.sources.len() < n {
+            self.sources.resize(n, GroupSourceStats::default());
+        }
+        &mut self.sources[source]
+    }
+}
+
+#[derive(Serialize)]
+pub struct GroupSourceSummary {
+    pub label: String,
+    pub first: u64,
+    pub first_percent: f64,
+    pub samples: u64,
+    pub lag_mean_ms: f64,
+    pub overall_avg_ms: f64,
+}
+
+#[derive(Serialize)]
+pub struct GroupSummary {
+    // leader 身份或区域名
+    pub name: String,
+    // 按 leader 分组时为该 leader 的 Jito 区域
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    pub total: u64,
+    pub sources: Vec<GroupSourceSummary>,
+}
+
+#[derive(Serialize)]
+pub struct LeaderSummary {
+    pub by_leader: Vec<GroupSummary>,
+    // 只在提供区域列表时有数据
+    pub by_region: Vec<GroupSummary>,
+}
+
+// 按 slot 的 leader 和 leader 所在的 Jito 区域分组统计首先接收比例和延迟, 口径与总体统计相同
+pub struct LeaderStats {
+    schedule: LeaderSchedule,
+    sources: usize,
+    leaders: HashMap<String, GroupStats>,
+    regions: BTreeMap<String, GroupStats>,
+}
+
+impl LeaderStats {
+    pub fn new(schedule: LeaderSchedule, sources: usize) -> Self {
+        Self { schedule, sources, leaders: HashMap::new(), regions: BTreeMap::new() }
+    }
+
+    pub fn leader(&self, slot: u64) -> Option<&str> {
+        self.schedule.leader(slot)
+    }
+
+    // 一个匹配键的计分结果: 首先接收的数据源, 以及其余数据源的落后延迟
+    pub fn record(&mut self, slot: u64, winner: usize, lags: &[(usize, u64)]) {
+        self.update(slot, |group, n| {
+            group.total += 1;
+            let stats = group.source(winner, n);
+            stats.first += 1;
+            stats.samples += 1;
+            for &(source, lag_ns) in lags {
+                let stats = group.source(source, n);
+                stats.samples += 1;
+                stats.lagged += 1;
+                stats.lag_sum_ns += lag_ns as u128;
+            }
+        });
+    }
+
+    fn update(&mut self, slot: u64, apply: impl Fn(&mut GroupStats, usize)) {
+        let n = self.sources;
+        let leader = self.schedule.leader(slot);
+        apply(self.leaders.entry(leader.unwrap_or(UNKNOWN_LEADER).to_string()).or_default(), n);
+        // 没有区域数据时不按区域分组; 连接了多个区域的 leader 在每个区域中各计一次
+        let regions = match leader {
+            Some(leader) => self.schedule.regions(leader),
+            None if self.schedule.has_regions() => vec![UNKNOWN_LEADER],
+            None => Vec::new(),
+        };
+        for region in regions {
+            apply(self.regions.entry(region.to_string()).or_default(), n);
+        }
+    }
+
+    // 按样本数从多到少排列
+    pub fn summary(&self, labels: &[String]) -> LeaderSummary {
+        let mut by_leader: Vec<GroupSummary> = self
+            .leaders
+            .iter()
+            .map(|(leader, group)| {
+                let regions = self.schedule.regions(leader);
+                let region = (leader != UNKNOWN_LEADER && !regions.is_empty()).then(|| regions.join("/"));
+                group_summary(leader, region, group, labels)
+            })
+            .collect();
+        by_leader.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+        let mut by_region: Vec<GroupSummary> =
+            self.regions.iter().map(|(region, group)| group_summary(region, None, group, labels)).collect();
+        by_region.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+        LeaderSummary { by_leader, by_region }
+    }
+
+    pub fn print_stats(&self, labels: &[String]) {
+        let summary = self.summary(labels);
+        println!("[{}] INFO: ===== 按 leader 分组 ({} 个 leader, 首先接收比例 / 落后时平均延迟) =====",
+            Local::now().format("%H:%M:%S%.3f"), summary.by_leader.len());
+        for group in summary.by_leader.iter().take(LEADER_ROWS) {
+            let name = match &group.region {
+                Some(region) => format!("{} ({})", group.name, region),
+                None => group.name.clone(),
+            };
+            print_group(&name, group);
+        }
+        if summary.by_leader.len() > LEADER_ROWS {
+            println!("[{}] INFO: 其余 {} 个 leader 省略, 完整结果见 --report",
+                Local::now().format("%H:%M:%S%.3f"), summary.by_leader.len() - LEADER_ROWS);
+        }
+
+        if summary.by_region.is_empty() {
+            return;
+        }
+        println!("[{}] INFO: ===== 按 Jito 区域分组 (首先接收比例 / 落后时平均延迟) =====", Local::now().format("%H:%M:%S%.3f"));
+        for group in &summary.by_region {
+            print_group(&group.name, group);
+        }
+    }
+}
+
+fn group_summary(name: &str, region: Option<String>, group: &GroupStats, labels: &[String]) -> GroupSummary {
+    GroupSummary {
+        name: name.to_string(),
+        region,
+        total: group.total,
+        sources: labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                let stats = group.sources.get(i).cloned().unwrap_or_default();
+                let lag_ms = stats.lag_sum_ns as f64 / 1_000_000.0;
+                GroupSourceSummary {
+                    label: label.clone(),
+                    first: stats.first,
+                    first_percent: match group.total {
+                        0 => 0.0,
+                        total => stats.first as f64 / total as f64 * 100.0,
+                    },
+                    samples: stats.samples,
+                    lag_mean_ms: match stats.lagged {
+                        0 => 0.0,
+                        lagged => lag_ms / lagged as f64,
+                    },
+                    overall_avg_ms: match stats.samples {
+                        0 => 0.0,
+                        samples => lag_ms / samples as f64,
+                    },
+                }
+            })
+            .collect(),
+    }
+}
+
+fn print_group(name: &str, group: &GroupSummary) {
+    let sources: Vec<String> = group
+        .sources
+        .iter()
+        .map(|s| format!("{} {:.1}% / {:.2}ms", s.label, s.first_percent, s.lag_mean_ms))
+        .collect();
+    println!("[{}] INFO: {} 样本 {} : {}", Local::now().format("%H:%M:%S%.3f"), name, group.total, sources.join(", "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 写入临时文件后加载, 返回加载结果
+    fn load(name: &str, content: &str, load: impl FnOnce(&Path) -> Result<(), String>) -> Result<(), String> {
+        let path = std::env::temp_dir().join(format!("{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        let result = load(&path);
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn rpc_schedules_are_offsets_from_the_first_slot() {
+        for (name, content) in [
+            ("schedule-rpc", r#"{"jsonrpc":"2.0","result":{"A":[0,1],"B":[2]},"id":1}"#),
+            ("schedule-result", r#"{"A":[0,1],"B":[2]}"#),
+        ] {
+            let mut schedule = LeaderSchedule::default();
+            load(name, content, |path| schedule.load_schedule(path, Some(1000))).unwrap();
+            assert_eq!(schedule.slots(), 3);
+            assert_eq!((schedule.leader(1000), schedule.leader(1001), schedule.leader(1002)), (Some("A"), Some("A"), Some("B")));
+            assert_eq!(schedule.leader(2), None);
+
+            // 没有 epoch 的第一个 slot 时无法换算
+            let error = load(name, content, |path| LeaderSchedule::default().load_schedule(path, None)).unwrap_err();
+            assert!(error.contains("epoch 内的 slot 偏移"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn cli_schedules_use_absolute_slots() {
+        let content = r#"{"epoch":1,"leaderScheduleEntries":[{"slot":432000,"leader":"A"},{"slot":432001,"leader":"B"}]}"#;
+        let mut schedule = LeaderSchedule::default();
+        load("schedule-cli", content, |path| schedule.load_schedule(path, Some(5))).unwrap();
+        assert_eq!((schedule.leader(432000), schedule.leader(432001)), (Some("A"), Some("B")));
+
+        let error = load("schedule-bad", r#"[432000]"#, |path| schedule.load_schedule(path, None)).unwrap_err();
+        assert!(error.starts_with("解析 leader 表"), "{}", error);
+    }
+
+    #[test]
+    fn regions_fill_missing_leaders_and_mark_non_jito() {
+        // grpcurl 输出 camelCase 和字符串 slot, 也接受 snake_case
+        let content = r#"{"connectedValidators":{
+            "ny":{"connectedValidators":{"A":{"slots":["10","11"]},"B":{"slots":[12]}}},
+            "amsterdam":{"connected_validators":{"A":{"slots":[]}}},
+            "tokyo":{}
+        }}"#;
+        let mut schedule = LeaderSchedule::default();
+        // 没有区域数据时不区分是否运行 Jito
+        assert!(schedule.regions("A").is_empty() && !schedule.has_regions());
+        schedule.leaders.insert(12, "C".to_string());
+        load("regions", content, |path| schedule.load_regions(path)).unwrap();
+
+        assert!(schedule.has_regions());
+        assert_eq!(schedule.regions("A"), ["amsterdam", "ny"]);
+        assert_eq!(schedule.regions("B"), ["ny"]);
+        assert_eq!(schedule.regions("C"), [NON_JITO]);
+        // 区域列表只补充 leader 表, 已有的 slot 不覆盖
+        assert_eq!((schedule.leader(10), schedule.leader(11), schedule.leader(12)), (Some("A"), Some("A"), Some("C")));
+    }
+
+    #[test]
+    fn resolve_loads_nothing_without_files() {
+        assert!(LeaderSchedule::resolve(&LeadersConfig::default()).unwrap().is_none());
+        let config = LeadersConfig { schedule: Some(PathBuf::from("/nonexistent/schedule.json")), ..Default::default() };
+        let error = LeaderSchedule::resolve(&config).err().unwrap();
+        assert!(error.starts_with("读取 leader 表 /nonexistent/schedule.json 失败"), "{}", error);
+    }
+}
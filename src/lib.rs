@@ -0,0 +1,24 @@
+pub mod auth;
+pub mod backoff;
+pub mod capture;
+pub mod cli;
+pub mod clock;
+pub mod commands;
+pub mod compare;
+pub mod config;
+pub mod entries;
+pub mod filter;
+pub mod geyser;
+pub mod heartbeat;
+pub mod histogram;
+pub mod leaders;
+pub mod metrics;
+pub mod mock;
+pub mod report;
+pub mod searcher;
+pub mod shred;
+pub mod slot_window;
+pub mod source;
+pub mod subscription;
+pub mod timeline;
+pub mod verify;
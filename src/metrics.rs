@@ -0,0 +1,201 @@
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::Local;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::compare::Comparator;
+use crate::source::SourceEvent;
+
+// Prometheus 延迟直方图的分桶上限 (秒)
+const LAG_BUCKETS: [f64; 12] = [0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.4, 0.8, 1.6, 3.2];
+
+// 订阅任务直接累加的收包计数
+#[derive(Default)]
+struct Received {
+    messages: AtomicU64,
+    bytes: AtomicU64,
+}
+
+struct State {
+    latest_slots: Vec<u64>,
+    comparator: Comparator,
+}
+
+// monitor 的指标集合, 订阅任务和 HTTP 服务共用
+#[derive(Clone)]
+pub struct Metrics {
+    // 已按 Prometheus 文本格式转义的数据源标签
+    labels: Arc<Vec<String>>,
+    received: Arc<Vec<Received>>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Metrics {
+    pub fn new(labels: Vec<String>, slot_depth: u64) -> Self {
+        let n = labels.len();
+        // monitor 按 slot 对比, 每个 slot 都应到达
+        let mut comparator = Comparator::new(labels.clone(), slot_depth);
+        comparator.set_count_holes(true);
+        Self {
+            received: Arc::new((0..n).map(|_| Received::default()).collect()),
+            state: Arc::new(Mutex::new(State { latest_slots: vec![0; n], comparator })),
+            labels: Arc::new(labels.iter().map(|label| escape_label(label)).collect()),
+        }
+    }
+
+    // 记录一条原始消息, 在对比器处理之前调用
+    pub fn received(&self, source: usize, bytes: usize) {
+        let received = &self.received[source];
+        received.messages.fetch_add(1, Ordering::Relaxed);
+        received.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn observe(&self, event: SourceEvent) {
+        let mut state = self.state.lock().unwrap();
+        if let SourceEvent::Arrival(arrival) = &event {
+            let latest = &mut state.latest_slots[arrival.source];
+            *latest = (*latest).max(arrival.slot);
+        }
+        state.comparator.handle(event);
+    }
+
+    // Prometheus 文本格式
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let summary = state.comparator.summary();
+        let tip = state.latest_slots.iter().copied().max().unwrap_or(0);
+        let mut out = String::new();
+
+        header(&mut out, "shred_vs_grpc_latest_slot", "gauge", "最新收到的 slot");
+        for (label, slot) in self.labels.iter().zip(&state.latest_slots) {
+            let _ = writeln!(out, "shred_vs_grpc_latest_slot{{source=\"{}\"}} {}", label, slot);
+        }
+
+        header(&mut out, "shred_vs_grpc_slot_gap", "gauge", "落后于最快数据源的 slot 数");
+        for (label, slot) in self.labels.iter().zip(&state.latest_slots) {
+            let _ = writeln!(out, "shred_vs_grpc_slot_gap{{source=\"{}\"}} {}", label, tip - slot);
+        }
+
+        header(&mut out, "shred_vs_grpc_first_arrivals_total", "counter", "首先收到 slot 的次数");
+        for (label, source) in self.labels.iter().zip(&summary.sources) {
+            let _ = writeln!(out, "shred_vs_grpc_first_arrivals_total{{source=\"{}\"}} {}", label, source.first);
+        }
+
+        header(&mut out, "shred_vs_grpc_reconnects_total", "counter", "重连次数");
+        for (label, source) in self.labels.iter().zip(&summary.sources) {
+            let _ = writeln!(out, "shred_vs_grpc_reconnects_total{{source=\"{}\"}} {}", label, source.reconnects);
+        }
+
+        header(&mut out, "shred_vs_grpc_missed_total", "counter", "其他数据源收到而该数据源未收到的 slot 数");
+        for (label, source) in self.labels.iter().zip(&summary.sources) {
+            let _ = writeln!(out, "shred_vs_grpc_missed_total{{source=\"{}\"}} {}", label, source.missed);
+        }
+
+        header(&mut out, "shred_vs_grpc_only_total", "counter", "只有该数据源收到的 slot 数");
+        for (label, source) in self.labels.iter().zip(&summary.sources) {
+            let _ = writeln!(out, "shred_vs_grpc_only_total{{source=\"{}\"}} {}", label, source.only);
+        }
+
+        header(&mut out, "shred_vs_grpc_duplicates_total", "counter", "重复到达的次数");
+        for (label, source) in self.labels.iter().zip(&summary.sources) {
+            let _ = writeln!(out, "shred_vs_grpc_duplicates_total{{source=\"{}\"}} {}", label, source.duplicates);
+        }
+
+        if let Some(holes) = summary.slot_holes {
+            header(&mut out, "shred_vs_grpc_slot_holes_total", "counter", "所有数据源都未收到的 slot 数");
+            let _ = writeln!(out, "shred_vs_grpc_slot_holes_total {}", holes);
+        }
+
+        header(&mut out, "shred_vs_grpc_messages_received_total", "counter", "收到的原始消息数");
+        for (label, received) in self.labels.iter().zip(self.received.iter()) {
+            let _ = writeln!(out, "shred_vs_grpc_messages_received_total{{source=\"{}\"}} {}",
+                label, received.messages.load(Ordering::Relaxed));
+        }
+
+        header(&mut out, "shred_vs_grpc_bytes_received_total", "counter", "收到的原始消息字节数");
+        for (label, received) in self.labels.iter().zip(self.received.iter()) {
+            let _ = writeln!(out, "shred_vs_grpc_bytes_received_total{{source=\"{}\"}} {}",
+                label, received.bytes.load(Ordering::Relaxed));
+        }
+
+        header(&mut out, "shred_vs_grpc_lag_seconds", "histogram", "落后于最先到达者的延迟");
+        for (index, label) in self.labels.iter().enumerate() {
+            let lag = state.comparator.lag_histogram(index);
+            for upper in LAG_BUCKETS {
+                let _ = writeln!(out, "shred_vs_grpc_lag_seconds_bucket{{source=\"{}\",le=\"{}\"}} {}",
+                    label, upper, lag.count_le_us((upper * 1_000_000.0) as u64));
+            }
+            let _ = writeln!(out, "shred_vs_grpc_lag_seconds_bucket{{source=\"{}\",le=\"+Inf\"}} {}", label, lag.count());
+            let _ = writeln!(out, "shred_vs_grpc_lag_seconds_sum{{source=\"{}\"}} {}", label, lag.sum_ms() / 1000.0);
+            let _ = writeln!(out, "shred_vs_grpc_lag_seconds_count{{source=\"{}\"}} {}", label, lag.count());
+        }
+        out
+    }
+}
+
+// 标签值中的反斜杠、双引号和换行需要转义
+fn escape_label(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+// 极简 HTTP 服务: GET /metrics 返回指标, 其他路径返回 404
+pub async fn serve(addr: SocketAddr, metrics: Metrics) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    println!("[{}] INFO: 指标导出地址 http://{}/metrics", Local::now().format("%H:%M:%S%.3f"), listener.local_addr()?);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let _ = handle(stream, &metrics).await;
+        });
+    }
+}
+
+async fn handle(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    // 只需要请求行, 读到头部结束或缓冲区满为止
+    let mut buf = vec![0u8; 4096];
+    let mut len = 0;
+    while len < buf.len() {
+        let n = stream.read(&mut buf[len..]).await?;
+        if n == 0 {
+            break;
+        }
+        len += n;
+        if buf[..len].windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+    }
+
+    let request = String::from_utf8_lossy(&buf[..len]);
+    let mut parts = request.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", "not found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
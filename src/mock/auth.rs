@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use chrono::Local;
+use jito_protos::auth::auth_service_server::{AuthService, AuthServiceServer};
+use jito_protos::auth::{
+    GenerateAuthChallengeRequest, GenerateAuthChallengeResponse, GenerateAuthTokensRequest,
+    GenerateAuthTokensResponse, RefreshAccessTokenRequest, RefreshAccessTokenResponse, Role, Token,
+};
+use jito_protos::prost_types::Timestamp;
+use jito_protos::tonic::metadata::MetadataMap;
+use jito_protos::tonic::{self, Request, Response, Status};
+use rand::Rng;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::time::Duration;
+
+// 模拟 block engine 签发的令牌, 模拟 ShredstreamProxy、心跳和 SearcherService 用它校验请求
+pub struct MockAuthState {
+    access_ttl: Duration,
+    refresh_ttl: Duration,
+    inner: Mutex<Tokens>,
+}
+
+#[derive(Default)]
+struct Tokens {
+    // 挑战 -> (申请者公钥, 角色), 每个挑战只能使用一次
+    challenges: HashMap<String, (Pubkey, Role)>,
+    access: HashMap<String, (Role, SystemTime)>,
+    refresh: HashMap<String, (Pubkey, Role, SystemTime)>,
+}
+
+impl MockAuthState {
+    pub fn new(access_ttl: Duration, refresh_ttl: Duration) -> Arc<Self> {
+        Arc::new(Self { access_ttl, refresh_ttl, inner: Mutex::new(Tokens::default()) })
+    }
+
+    // 校验 authorization: Bearer <访问令牌>, 令牌必须是为 role 签发的, 失败时返回原因
+    pub fn check(&self, metadata: &MetadataMap, role: Role) -> Result<(), &'static str> {
+        let token = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or("缺少访问令牌")?;
+        match self.inner.lock().unwrap().access.get(token) {
+            Some((_, expires)) if *expires <= SystemTime::now() => Err("访问令牌已过期"),
+            Some((issued, _)) if *issued != role => Err("访问令牌的角色不符"),
+            Some(_) => Ok(()),
+            None => Err("无效的访问令牌"),
+        }
+    }
+
+    fn issue(ttl: Duration) -> (String, SystemTime, Token) {
+        let value: String = (0..32).map(|_| format!("{:02x}", rand::thread_rng().gen::<u8>())).collect();
+        let expires = SystemTime::now() + ttl;
+        let token = Token { value: value.clone(), expires_at_utc: Some(Timestamp::from(expires)) };
+        (value, expires, token)
+    }
+}
+
+pub struct MockAuth {
+    state: Arc<MockAuthState>,
+}
+
+#[tonic::async_trait]
+impl AuthService for MockAuth {
+    async fn generate_auth_challenge(
+        &self,
+        request: Request<GenerateAuthChallengeRequest>,
+    ) -> Result<Response<GenerateAuthChallengeResponse>, Status> {
+        let request = request.into_inner();
+        let role = match Role::try_from(request.role) {
+            Ok(role @ (Role::ShredstreamSubscriber | Role::Searcher)) => role,
+            _ => return Err(Status::permission_denied("只支持 SHREDSTREAM_SUBSCRIBER 和 SEARCHER")),
+        };
+        let pubkey = Pubkey::try_from(request.pubkey.as_slice())
+            .map_err(|_| Status::invalid_argument("无效的公钥"))?;
+        let challenge: String = (0..9).map(|_| rand::thread_rng().sample(rand::distributions::Alphanumeric) as char).collect();
+        self.state.inner.lock().unwrap().challenges.insert(challenge.clone(), (pubkey, role));
+        Ok(Response::new(GenerateAuthChallengeResponse { challenge }))
+    }
+
+    async fn generate_auth_tokens(
+        &self,
+        request: Request<GenerateAuthTokensRequest>,
+    ) -> Result<Response<GenerateAuthTokensResponse>, Status> {
+        let request = request.into_inner();
+        let pubkey = Pubkey::try_from(request.client_pubkey.as_slice())
+            .map_err(|_| Status::invalid_argument("无效的公钥"))?;
+        // 签名内容为 "<公钥>-<挑战>"
+        let challenge = request
+            .challenge
+            .strip_prefix(&format!("{}-", pubkey))
+            .ok_or_else(|| Status::invalid_argument("挑战格式错误"))?;
+        let signature = Signature::try_from(request.signed_challenge.as_slice())
+            .map_err(|_| Status::invalid_argument("无效的签名"))?;
+        if !signature.verify(pubkey.as_ref(), request.challenge.as_bytes()) {
+            return Err(Status::permission_denied("签名校验失败"));
+        }
+
+        let mut tokens = self.state.inner.lock().unwrap();
+        let role = match tokens.challenges.remove(challenge) {
+            Some((issued, role)) if issued == pubkey => role,
+            _ => return Err(Status::permission_denied("挑战不存在或已使用")),
+        };
+        let (access_value, access_expires, access_token) = MockAuthState::issue(self.state.access_ttl);
+        let (refresh_value, refresh_expires, refresh_token) = MockAuthState::issue(self.state.refresh_ttl);
+        tokens.access.insert(access_value, (role, access_expires));
+        tokens.refresh.insert(refresh_value, (pubkey, role, refresh_expires));
+        println!("[{}] INFO: 模拟鉴权服务: 为 {} 签发 {} 令牌",
+            Local::now().format("%H:%M:%S%.3f"), pubkey, role.as_str_name());
+        Ok(Response::new(GenerateAuthTokensResponse {
+            access_token: Some(access_token),
+            refresh_token: Some(refresh_token),
+        }))
+    }
+
+    async fn refresh_access_token(
+        &self,
+        request: Request<RefreshAccessTokenRequest>,
+    ) -> Result<Response<RefreshAccessTokenResponse>, Status> {
+        let request = request.into_inner();
+        let mut tokens = self.state.inner.lock().unwrap();
+        let (pubkey, role) = match tokens.refresh.get(&request.refresh_token) {
+            Some((pubkey, role, expires)) if *expires > SystemTime::now() => (*pubkey, *role),
+            Some(_) => return Err(Status::unauthenticated("刷新令牌已过期")),
+            None => return Err(Status::unauthenticated("无效的刷新令牌")),
+        };
+        let (value, expires, access_token) = MockAuthState::issue(self.state.access_ttl);
+        tokens.access.insert(value, (role, expires));
+        // 清理过期的访问令牌
+        let now = SystemTime::now();
+        tokens.access.retain(|_, (_, expires)| *expires > now);
+        println!("[{}] INFO: 模拟鉴权服务: 为 {} 刷新访问令牌", Local::now().format("%H:%M:%S%.3f"), pubkey);
+        Ok(Response::new(RefreshAccessTokenResponse { access_token: Some(access_token) }))
+    }
+}
+
+// 在 addr 上启动模拟鉴权服务, 直到出错才返回
+pub async fn serve(addr: SocketAddr, state: Arc<MockAuthState>) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(AuthServiceServer::new(MockAuth { state }))
+        .serve(addr)
+        .await
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use solana_entry::entry::{next_entry_mut, Entry};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::system_transaction;
+use tokio::sync::broadcast;
+use rand::Rng;
+use tokio::time::{Duration, Instant};
+
+// 合成数据源参数
+#[derive(Clone)]
+pub struct FeedConfig {
+    pub start_slot: u64,
+    // 每个 slot 的时长, 400ms 对应主网出块速度
+    pub slot_duration: Duration,
+    pub entries_per_slot: usize,
+    pub txs_per_entry: usize,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        Self {
+            start_slot: 1,
+            slot_duration: Duration::from_millis(400),
+            entries_per_slot: 8,
+            txs_per_entry: 4,
+        }
+    }
+}
+
+// 合成的一个 entry, 由所有模拟服务端共享, 各自按自己的延迟发出
+pub struct FeedEntry {
+    pub slot: u64,
+    // 在 slot 内的序号
+    pub index: usize,
+    // 第一笔交易在 slot 内的位置
+    pub starting_transaction_index: u64,
+    // 是否为该 slot 的最后一个 entry
+    pub last_in_slot: bool,
+    pub entry: Entry,
+    // 生成时间, 模拟服务端以此为基准叠加延迟
+    pub produced_at: Instant,
+}
+
+// 启动合成数据源: 每个 slot 内均匀地产生 entries_per_slot 个 entry,
+// 每个 entry 含 txs_per_entry 笔真实签名的转账交易
+pub fn spawn_feed(config: FeedConfig) -> broadcast::Sender<Arc<FeedEntry>> {
+    let (tx, _) = broadcast::channel(1024);
+    let feed = tx.clone();
+    tokio::spawn(async move {
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let mut hash = Hash::new_unique();
+        let mut lamports = 1u64;
+        let entries_per_slot = config.entries_per_slot.max(1);
+        let tick = config.slot_duration / entries_per_slot as u32;
+        let mut next = Instant::now();
+
+        for slot in config.start_slot.. {
+            let mut starting_transaction_index = 0;
+            for index in 0..entries_per_slot {
+                next += tick;
+                tokio::time::sleep_until(next).await;
+
+                let transactions = (0..config.txs_per_entry)
+                    .map(|_| {
+                        lamports += 1;
+                        system_transaction::transfer(&payer, &recipient, lamports, hash)
+                    })
+                    .collect();
+                let entry = next_entry_mut(&mut hash, 1, transactions);
+                let feed_entry = FeedEntry {
+                    slot,
+                    index,
+                    starting_transaction_index,
+                    last_in_slot: index + 1 == entries_per_slot,
+                    entry,
+                    produced_at: Instant::now(),
+                };
+                starting_transaction_index += config.txs_per_entry as u64;
+                // 没有订阅者时发送失败, 忽略即可
+                let _ = feed.send(Arc::new(feed_entry));
+            }
+        }
+    });
+    tx
+}
+
+// 模拟服务端相对合成数据源的发送行为
+#[derive(Clone, Default)]
+pub struct DelayConfig {
+    // 固定延迟
+    pub delay: Duration,
+    // 在固定延迟上再叠加 [0, jitter] 的随机延迟
+    pub jitter: Duration,
+    // 丢弃 entry 的概率, 0.0 ~ 1.0
+    pub drop_rate: f64,
+}
+
+impl DelayConfig {
+    // 等到该 entry 应当发出的时间, 返回 false 表示按丢弃率丢弃
+    // 以生成时间为基准计算发送时间, 避免延迟逐条累加
+    pub async fn wait(&self, feed_entry: &FeedEntry) -> bool {
+        let (drop, jitter_us) = {
+            let mut rng = rand::thread_rng();
+            let jitter_us = match self.jitter.as_micros() as u64 {
+                0 => 0,
+                max => rng.gen_range(0..=max),
+            };
+            (rng.gen_bool(self.drop_rate.clamp(0.0, 1.0)), jitter_us)
+        };
+        if drop {
+            return false;
+        }
+
+        tokio::time::sleep_until(feed_entry.produced_at + self.delay + Duration::from_micros(jitter_us)).await;
+        true
+    }
+}
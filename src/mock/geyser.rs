@@ -0,0 +1,321 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures_util::Stream;
+use solana_sdk::transaction::VersionedTransaction;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::Duration;
+use tokio_stream::wrappers::ReceiverStream;
+use yellowstone_grpc_proto::geyser::geyser_server::{Geyser, GeyserServer};
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::geyser::{
+    GetBlockHeightRequest, GetBlockHeightResponse, GetLatestBlockhashRequest,
+    GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse, GetVersionRequest,
+    GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest,
+    PongResponse, SubscribeRequest,
+    SubscribeRequestFilterTransactions, SubscribeUpdate, SubscribeUpdatePing, SubscribeUpdatePong,
+    SubscribeUpdateEntry, SubscribeUpdateSlot, SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+};
+use yellowstone_grpc_proto::prelude::{Message, Transaction, TransactionStatusMeta};
+use yellowstone_grpc_proto::tonic::{self, Request, Response, Status, Streaming};
+
+use crate::mock::feed::{DelayConfig, FeedEntry};
+
+// 模拟 Yellowstone Geyser 的 Subscribe 接口, 与模拟 Shredstream 共用同一个合成数据源,
+// 或者把每次订阅交给调用方 (例如测试) 逐条控制
+pub struct MockGeyser {
+    updates: Updates,
+}
+
+enum Updates {
+    Feed {
+        feed: broadcast::Sender<Arc<FeedEntry>>,
+        config: DelayConfig,
+        // 服务端主动发送 Ping 的间隔
+        ping_interval: Duration,
+    },
+    Scripted(mpsc::UnboundedSender<ScriptedSession>),
+}
+
+// 交给调用方的一次订阅: 客户端发来的请求流和推送给客户端的更新, 丢弃后服务端结束数据流
+pub struct ScriptedSession {
+    pub requests: Streaming<SubscribeRequest>,
+    pub updates: UpdateSender,
+}
+
+impl ScriptedSession {
+    // 客户端的下一个请求, 请求流结束时为 None
+    pub async fn next_request(&mut self) -> Option<SubscribeRequest> {
+        self.requests.message().await.ok().flatten()
+    }
+
+    pub async fn send(&self, update_oneof: UpdateOneof) -> bool {
+        self.updates.send(Ok(update(vec![], update_oneof))).await.is_ok()
+    }
+
+    pub async fn ping(&self) -> bool {
+        self.send(UpdateOneof::Ping(SubscribeUpdatePing {})).await
+    }
+}
+
+impl MockGeyser {
+    pub fn new(feed: broadcast::Sender<Arc<FeedEntry>>, config: DelayConfig, ping_interval: Duration) -> Self {
+        Self { updates: Updates::Feed { feed, config, ping_interval } }
+    }
+
+    // 不推送合成数据, 每次订阅从返回的通道交给调用方
+    pub fn scripted() -> (Self, mpsc::UnboundedReceiver<ScriptedSession>) {
+        let (sessions, rx) = mpsc::unbounded_channel();
+        (Self { updates: Updates::Scripted(sessions) }, rx)
+    }
+}
+
+type UpdateSender = mpsc::Sender<Result<SubscribeUpdate, Status>>;
+
+fn update(filters: Vec<String>, update_oneof: UpdateOneof) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters,
+        update_oneof: Some(update_oneof),
+        ..Default::default()
+    }
+}
+
+// 按 SubscribeRequestFilterTransactions 的语义判断交易是否匹配
+// 合成交易都是成功的非投票交易
+fn filter_matches(filter: &SubscribeRequestFilterTransactions, signature: &str, account_keys: &[String]) -> bool {
+    if filter.vote == Some(true) || filter.failed == Some(true) {
+        return false;
+    }
+    if let Some(expected) = &filter.signature {
+        if expected != signature {
+            return false;
+        }
+    }
+    if !filter.account_include.is_empty() && !filter.account_include.iter().any(|a| account_keys.contains(a)) {
+        return false;
+    }
+    if filter.account_exclude.iter().any(|a| account_keys.contains(a)) {
+        return false;
+    }
+    filter.account_required.iter().all(|a| account_keys.contains(a))
+}
+
+fn transaction_update(slot: u64, index: u64, transaction: &VersionedTransaction, filters: Vec<String>) -> SubscribeUpdate {
+    let message = transaction.message.clone();
+    let info = SubscribeUpdateTransactionInfo {
+        signature: transaction.signatures[0].as_ref().to_vec(),
+        is_vote: false,
+        transaction: Some(Transaction {
+            signatures: transaction.signatures.iter().map(|s| s.as_ref().to_vec()).collect(),
+            message: Some(Message {
+                account_keys: message.static_account_keys().iter().map(|k| k.to_bytes().to_vec()).collect(),
+                recent_blockhash: message.recent_blockhash().to_bytes().to_vec(),
+                versioned: false,
+                ..Default::default()
+            }),
+        }),
+        meta: Some(TransactionStatusMeta::default()),
+        index,
+    };
+    update(filters, UpdateOneof::Transaction(SubscribeUpdateTransaction { transaction: Some(info), slot }))
+}
+
+// 订阅了 slot 时推送新 slot 的更新
+async fn send_slot(tx: &UpdateSender, request: &SubscribeRequest, slot: u64) -> bool {
+    if request.slots.is_empty() {
+        return true;
+    }
+    let slot_update = SubscribeUpdateSlot { slot, parent: slot.checked_sub(1), ..Default::default() };
+    let filters = request.slots.keys().cloned().collect();
+    tx.send(Ok(update(filters, UpdateOneof::Slot(slot_update)))).await.is_ok()
+}
+
+// 处理一个 entry: 按当前订阅条件生成交易和 entry 更新
+async fn send_entry(tx: &UpdateSender, request: &SubscribeRequest, feed_entry: &FeedEntry) -> bool {
+    for (tx_index, transaction) in (feed_entry.starting_transaction_index..).zip(&feed_entry.entry.transactions) {
+        let signature = transaction.signatures[0].to_string();
+        let account_keys: Vec<String> = transaction
+            .message
+            .static_account_keys()
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        let filters: Vec<String> = request
+            .transactions
+            .iter()
+            .filter(|(_, filter)| filter_matches(filter, &signature, &account_keys))
+            .map(|(name, _)| name.clone())
+            .collect();
+        if filters.is_empty() {
+            continue;
+        }
+        let update = transaction_update(feed_entry.slot, tx_index, transaction, filters);
+        if tx.send(Ok(update)).await.is_err() {
+            return false;
+        }
+    }
+
+    // 与真实的 Geyser 插件一样, entry 在其中的交易之后推送
+    if !request.entry.is_empty() {
+        let entry_update = SubscribeUpdateEntry {
+            slot: feed_entry.slot,
+            index: feed_entry.index as u64,
+            num_hashes: feed_entry.entry.num_hashes,
+            hash: feed_entry.entry.hash.to_bytes().to_vec(),
+            executed_transaction_count: feed_entry.entry.transactions.len() as u64,
+            starting_transaction_index: feed_entry.starting_transaction_index,
+        };
+        let filters = request.entry.keys().cloned().collect();
+        if tx.send(Ok(update(filters, UpdateOneof::Entry(entry_update)))).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+// 按合成数据源推送, 返回推送给客户端的更新
+fn subscribe_feed(
+    mut requests: Streaming<SubscribeRequest>,
+    mut feed: broadcast::Receiver<Arc<FeedEntry>>,
+    config: DelayConfig,
+    ping_interval: Duration,
+) -> mpsc::Receiver<Result<SubscribeUpdate, Status>> {
+    let (tx, rx) = mpsc::channel(1024);
+    let (filter_tx, mut filter_rx) = watch::channel(None::<SubscribeRequest>);
+
+    // 读取客户端请求: ping 立即回复 pong, 其余请求替换当前订阅条件
+    let pong_tx = tx.clone();
+    tokio::spawn(async move {
+        while let Ok(Some(request)) = requests.message().await {
+            match request.ping {
+                Some(ping) => {
+                    let pong = update(vec![], UpdateOneof::Pong(SubscribeUpdatePong { id: ping.id }));
+                    if pong_tx.send(Ok(pong)).await.is_err() {
+                        break;
+                    }
+                }
+                None => {
+                    filter_tx.send_replace(Some(request));
+                }
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        // 等待第一个订阅请求
+        if filter_rx.wait_for(|request| request.is_some()).await.is_err() {
+            return;
+        }
+        let mut ping = tokio::time::interval(ping_interval);
+        // 已推送过 slot 更新的最新 slot
+        let mut last_slot = None;
+        loop {
+            tokio::select! {
+                _ = ping.tick() => {
+                    let ping = update(vec![], UpdateOneof::Ping(SubscribeUpdatePing {}));
+                    if tx.send(Ok(ping)).await.is_err() {
+                        break;
+                    }
+                }
+                received = feed.recv() => {
+                    let feed_entry = match received {
+                        Ok(feed_entry) => feed_entry,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    // slot 更新在收到新 slot 的第一个 entry 时推送, 与该 entry 是否被丢弃或延迟无关
+                    if last_slot.is_none_or(|last| feed_entry.slot > last) {
+                        last_slot = Some(feed_entry.slot);
+                        let request = filter_rx.borrow().clone().unwrap_or_default();
+                        if !send_slot(&tx, &request, feed_entry.slot).await {
+                            break;
+                        }
+                    }
+                    if !config.wait(&feed_entry).await {
+                        continue;
+                    }
+                    let request = filter_rx.borrow().clone().unwrap_or_default();
+                    if !send_entry(&tx, &request, &feed_entry).await {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+#[tonic::async_trait]
+impl Geyser for MockGeyser {
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let requests = request.into_inner();
+        let rx = match &self.updates {
+            Updates::Feed { feed, config, ping_interval } => {
+                subscribe_feed(requests, feed.subscribe(), config.clone(), *ping_interval)
+            }
+            Updates::Scripted(sessions) => {
+                let (updates, rx) = mpsc::channel(16);
+                let _ = sessions.send(ScriptedSession { requests, updates });
+                rx
+            }
+        };
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn ping(&self, request: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        Ok(Response::new(PongResponse { count: request.into_inner().count }))
+    }
+
+    async fn get_latest_blockhash(
+        &self,
+        _request: Request<GetLatestBlockhashRequest>,
+    ) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+
+    async fn get_block_height(
+        &self,
+        _request: Request<GetBlockHeightRequest>,
+    ) -> Result<Response<GetBlockHeightResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+
+    async fn get_slot(&self, _request: Request<GetSlotRequest>) -> Result<Response<GetSlotResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+
+    async fn is_blockhash_valid(
+        &self,
+        _request: Request<IsBlockhashValidRequest>,
+    ) -> Result<Response<IsBlockhashValidResponse>, Status> {
+        Err(Status::unimplemented("mock geyser"))
+    }
+
+    async fn get_version(&self, _request: Request<GetVersionRequest>) -> Result<Response<GetVersionResponse>, Status> {
+        Ok(Response::new(GetVersionResponse { version: "mock".to_string() }))
+    }
+}
+
+// 在 addr 上启动模拟 Geyser 服务, 直到出错才返回
+pub async fn serve(
+    addr: SocketAddr,
+    feed: broadcast::Sender<Arc<FeedEntry>>,
+    config: DelayConfig,
+    ping_interval: Duration,
+) -> Result<(), tonic::transport::Error> {
+    serve_mock(addr, MockGeyser::new(feed, config, ping_interval)).await
+}
+
+pub async fn serve_mock(addr: SocketAddr, geyser: MockGeyser) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(GeyserServer::new(geyser))
+        .serve(addr)
+        .await
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+use chrono::Local;
+use jito_protos::auth::Role;
+use jito_protos::shredstream::shredstream_server::{Shredstream, ShredstreamServer};
+use jito_protos::shredstream::{Heartbeat, HeartbeatResponse};
+use jito_protos::tonic::{self, Request, Response, Status};
+use rand::Rng;
+use tokio::net::UdpSocket;
+use tokio::sync::broadcast;
+use tokio::time::{Duration, Instant};
+
+use crate::mock::auth::MockAuthState;
+use crate::mock::feed::{DelayConfig, FeedEntry};
+use crate::mock::shredder::MockShredder;
+
+// 登记的 UDP 地址 -> (区域, 过期时间)
+type Registrations = Arc<Mutex<HashMap<SocketAddr, (Vec<String>, Instant)>>>;
+
+// 模拟 block engine 的 Shredstream 服务: 收到心跳后在有效期内向登记的地址转发数据包
+pub struct MockHeartbeat {
+    ttl: Duration,
+    registrations: Registrations,
+    auth: Option<Arc<MockAuthState>>,
+}
+
+#[tonic::async_trait]
+impl Shredstream for MockHeartbeat {
+    async fn send_heartbeat(&self, request: Request<Heartbeat>) -> Result<Response<HeartbeatResponse>, Status> {
+        if let Some(auth) = &self.auth {
+            auth.check(request.metadata(), Role::ShredstreamSubscriber).map_err(Status::unauthenticated)?;
+        }
+        let heartbeat = request.into_inner();
+        let socket = heartbeat.socket.ok_or_else(|| Status::invalid_argument("缺少 socket"))?;
+        let ip: IpAddr = socket.ip.parse().map_err(|_| Status::invalid_argument("无效的 IP 地址"))?;
+        let port = u16::try_from(socket.port).map_err(|_| Status::invalid_argument("无效的端口"))?;
+        if heartbeat.regions.is_empty() {
+            return Err(Status::invalid_argument("至少需要一个区域"));
+        }
+
+        let addr = SocketAddr::new(ip, port);
+        let expires = Instant::now() + self.ttl;
+        let previous = self.registrations.lock().unwrap().insert(addr, (heartbeat.regions.clone(), expires));
+        match previous {
+            Some((regions, previous_expires)) if previous_expires > Instant::now() => {
+                if regions != heartbeat.regions {
+                    println!("[{}] INFO: 模拟心跳服务: {} 的区域变更为 {:?}",
+                        Local::now().format("%H:%M:%S%.3f"), addr, heartbeat.regions);
+                }
+            }
+            _ => println!("[{}] INFO: 模拟心跳服务: 登记 {}, 区域 {:?}",
+                Local::now().format("%H:%M:%S%.3f"), addr, heartbeat.regions),
+        }
+        Ok(Response::new(HeartbeatResponse { ttl_ms: self.ttl.as_millis() as u32 }))
+    }
+}
+
+// 把每个 entry 切分为 shred, 按延迟配置发往所有未过期的登记地址
+// 丢弃率作用于单个 shred, 接收端可以用编码 shred 恢复
+async fn forward(feed: broadcast::Sender<Arc<FeedEntry>>, config: DelayConfig, registrations: Registrations) {
+    let socket = match UdpSocket::bind("0.0.0.0:0").await {
+        Ok(socket) => socket,
+        Err(e) => {
+            println!("[{}] ERROR: 模拟心跳服务绑定 UDP 失败: {}", Local::now().format("%H:%M:%S%.3f"), e);
+            return;
+        }
+    };
+    let timing = DelayConfig { drop_rate: 0.0, ..config.clone() };
+    let mut shredder = MockShredder::default();
+    let mut feed = feed.subscribe();
+    loop {
+        let feed_entry = match feed.recv().await {
+            Ok(feed_entry) => feed_entry,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let Ok(entries) = bincode::serialize(&vec![feed_entry.entry.clone()]) else {
+            continue;
+        };
+        let packets = shredder.shred(feed_entry.slot, &entries, feed_entry.last_in_slot);
+        timing.wait(&feed_entry).await;
+
+        // 清理过期的登记
+        let targets: Vec<SocketAddr> = {
+            let mut registrations = registrations.lock().unwrap();
+            let now = Instant::now();
+            registrations.retain(|addr, (_, expires)| {
+                let alive = *expires > now;
+                if !alive {
+                    println!("[{}] INFO: 模拟心跳服务: {} 的登记已过期, 停止转发",
+                        Local::now().format("%H:%M:%S%.3f"), addr);
+                }
+                alive
+            });
+            registrations.keys().copied().collect()
+        };
+        for packet in &packets {
+            if rand::thread_rng().gen_bool(config.drop_rate.clamp(0.0, 1.0)) {
+                continue;
+            }
+            for target in &targets {
+                let _ = socket.send_to(packet, target).await;
+            }
+        }
+    }
+}
+
+// 在 addr 上启动模拟心跳服务, 直到出错才返回
+pub async fn serve(
+    addr: SocketAddr,
+    feed: broadcast::Sender<Arc<FeedEntry>>,
+    config: DelayConfig,
+    ttl: Duration,
+    auth: Option<Arc<MockAuthState>>,
+) -> Result<(), tonic::transport::Error> {
+    let registrations = Registrations::default();
+    tokio::spawn(forward(feed, config, registrations.clone()));
+    tonic::transport::Server::builder()
+        .add_service(ShredstreamServer::new(MockHeartbeat { ttl, registrations, auth }))
+        .serve(addr)
+        .await
+}
@@ -0,0 +1,8 @@
+// 本地模拟服务端, 用于离线测试和演示
+pub mod auth;
+pub mod feed;
+pub mod geyser;
+pub mod heartbeat;
+pub mod searcher;
+pub mod shredder;
+pub mod shredstream;
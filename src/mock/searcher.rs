@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use futures_util::Stream;
+use jito_protos::auth::Role;
+use jito_protos::bundle::BundleResult;
+use jito_protos::searcher::searcher_service_server::{SearcherService, SearcherServiceServer};
+use jito_protos::searcher::{
+    ConnectedLeadersRegionedRequest, ConnectedLeadersRegionedResponse, ConnectedLeadersRequest,
+    ConnectedLeadersResponse, GetRegionsRequest, GetRegionsResponse, GetTipAccountsRequest, GetTipAccountsResponse,
+    NextScheduledLeaderRequest, NextScheduledLeaderResponse, SendBundleRequest, SendBundleResponse, SlotList,
+    SubscribeBundleResultsRequest,
+};
+use jito_protos::tonic::{self, Request, Response, Status};
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::broadcast;
+
+use crate::mock::auth::MockAuthState;
+use crate::mock::feed::FeedEntry;
+
+// 模拟的 leader 轮换: 每个 leader 连续 4 个 slot, 前 4 个运行 Jito, 各连接一个区域
+const LEADERS: u64 = 6;
+const SLOTS_PER_LEADER: u64 = 4;
+const REGIONS: [&str; 4] = ["amsterdam", "frankfurt", "ny", "tokyo"];
+// 模拟服务端当前连接的区域
+const CURRENT_REGION: &str = "ny";
+// 模拟的 epoch 长度, 比主网短, 响应不会太大
+pub const EPOCH_SLOTS: u64 = 8192;
+
+// 第 index 个模拟 leader 的身份
+fn identity(index: u64) -> String {
+    Pubkey::new_from_array([index as u8 + 1; 32]).to_string()
+}
+
+fn leader_index(slot: u64) -> u64 {
+    slot / SLOTS_PER_LEADER % LEADERS
+}
+
+// leader 连接的区域, 不运行 Jito 时为 None
+fn region_of(index: u64) -> Option<&'static str> {
+    REGIONS.get(index as usize).copied()
+}
+
+// slot 所在 epoch 的所有 slot
+fn epoch_slots(slot: u64) -> std::ops::Range<u64> {
+    let first = slot / EPOCH_SLOTS * EPOCH_SLOTS;
+    first..first + EPOCH_SLOTS
+}
+
+// 第一个 epoch 的 getLeaderSchedule 响应, slot 为 epoch 内的偏移, 用于离线测试按 leader 分组
+pub fn write_leader_schedule(path: &Path) -> std::io::Result<()> {
+    let mut schedule: HashMap<String, Vec<u64>> = HashMap::new();
+    for slot in epoch_slots(0) {
+        schedule.entry(identity(leader_index(slot))).or_default().push(slot);
+    }
+    let response = serde_json::json!({ "jsonrpc": "2.0", "result": schedule, "id": 1 });
+    std::fs::write(path, serde_json::to_vec(&response)?)
+}
+
+// 模拟 block engine 的 SearcherService, 只实现查询 leader 和区域的接口
+pub struct MockSearcher {
+    // 合成数据源的最新 slot
+    current_slot: Arc<AtomicU64>,
+    auth: Option<Arc<MockAuthState>>,
+}
+
+impl MockSearcher {
+    // 未指定区域时只查当前连接的区域
+    fn requested(regions: Vec<String>) -> Vec<String> {
+        if regions.is_empty() {
+            vec![CURRENT_REGION.to_string()]
+        } else {
+            regions
+        }
+    }
+
+    // 某个区域连接的 leader 及其在当前 epoch 的 slot
+    fn connected(&self, region: &str) -> ConnectedLeadersResponse {
+        let mut connected_validators: HashMap<String, SlotList> = HashMap::new();
+        for slot in epoch_slots(self.current_slot.load(Ordering::Relaxed)) {
+            let index = leader_index(slot);
+            if region_of(index) == Some(region) {
+                connected_validators.entry(identity(index)).or_default().slots.push(slot);
+            }
+        }
+        ConnectedLeadersResponse { connected_validators }
+    }
+}
+
+#[tonic::async_trait]
+impl SearcherService for MockSearcher {
+    type SubscribeBundleResultsStream = Pin<Box<dyn Stream<Item = Result<BundleResult, Status>> + Send>>;
+
+    async fn subscribe_bundle_results(
+        &self,
+        _request: Request<SubscribeBundleResultsRequest>,
+    ) -> Result<Response<Self::SubscribeBundleResultsStream>, Status> {
+        Err(Status::unimplemented("模拟服务端不支持 bundle"))
+    }
+
+    async fn send_bundle(&self, _request: Request<SendBundleRequest>) -> Result<Response<SendBundleResponse>, Status> {
+        Err(Status::unimplemented("模拟服务端不支持 bundle"))
+    }
+
+    async fn get_next_scheduled_leader(
+        &self,
+        request: Request<NextScheduledLeaderRequest>,
+    ) -> Result<Response<NextScheduledLeaderResponse>, Status> {
+        if let Some(auth) = &self.auth {
+            auth.check(request.metadata(), Role::Searcher).map_err(Status::unauthenticated)?;
+        }
+        let regions = Self::requested(request.into_inner().regions);
+        let current_slot = self.current_slot.load(Ordering::Relaxed);
+        let next = (current_slot..current_slot + LEADERS * SLOTS_PER_LEADER).find_map(|slot| {
+            let region = region_of(leader_index(slot)).filter(|region| regions.iter().any(|r| r == region))?;
+            Some((slot, region))
+        });
+        let Some((slot, region)) = next else {
+            return Err(Status::not_found("所选区域没有连接的 leader"));
+        };
+        Ok(Response::new(NextScheduledLeaderResponse {
+            current_slot,
+            next_leader_slot: slot,
+            next_leader_identity: identity(leader_index(slot)),
+            next_leader_region: region.to_string(),
+        }))
+    }
+
+    async fn get_connected_leaders(
+        &self,
+        request: Request<ConnectedLeadersRequest>,
+    ) -> Result<Response<ConnectedLeadersResponse>, Status> {
+        if let Some(auth) = &self.auth {
+            auth.check(request.metadata(), Role::Searcher).map_err(Status::unauthenticated)?;
+        }
+        Ok(Response::new(self.connected(CURRENT_REGION)))
+    }
+
+    async fn get_connected_leaders_regioned(
+        &self,
+        request: Request<ConnectedLeadersRegionedRequest>,
+    ) -> Result<Response<ConnectedLeadersRegionedResponse>, Status> {
+        if let Some(auth) = &self.auth {
+            auth.check(request.metadata(), Role::Searcher).map_err(Status::unauthenticated)?;
+        }
+        let connected_validators = Self::requested(request.into_inner().regions)
+            .into_iter()
+            .filter(|region| REGIONS.contains(&region.as_str()))
+            .map(|region| {
+                let connected = self.connected(&region);
+                (region, connected)
+            })
+            .collect();
+        Ok(Response::new(ConnectedLeadersRegionedResponse { connected_validators }))
+    }
+
+    async fn get_tip_accounts(
+        &self,
+        _request: Request<GetTipAccountsRequest>,
+    ) -> Result<Response<GetTipAccountsResponse>, Status> {
+        Err(Status::unimplemented("模拟服务端不支持小费账户"))
+    }
+
+    async fn get_regions(&self, request: Request<GetRegionsRequest>) -> Result<Response<GetRegionsResponse>, Status> {
+        if let Some(auth) = &self.auth {
+            auth.check(request.metadata(), Role::Searcher).map_err(Status::unauthenticated)?;
+        }
+        Ok(Response::new(GetRegionsResponse {
+            current_region: CURRENT_REGION.to_string(),
+            available_regions: REGIONS.iter().map(|region| region.to_string()).collect(),
+        }))
+    }
+}
+
+// 在 addr 上启动模拟 SearcherService, 当前 slot 跟随合成数据源, 直到出错才返回
+pub async fn serve(
+    addr: SocketAddr,
+    feed: broadcast::Sender<Arc<FeedEntry>>,
+    auth: Option<Arc<MockAuthState>>,
+) -> Result<(), tonic::transport::Error> {
+    let current_slot = Arc::new(AtomicU64::new(0));
+    let tracker = current_slot.clone();
+    let mut feed = feed.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match feed.recv().await {
+                Ok(feed_entry) => tracker.fetch_max(feed_entry.slot, Ordering::Relaxed),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+        }
+    });
+    tonic::transport::Server::builder()
+        .add_service(SearcherServiceServer::new(MockSearcher { current_slot, auth }))
+        .serve(addr)
+        .await
+}
@@ -0,0 +1,102 @@
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+use crate::shred::{
+    ShredVariant, CODING_SHRED_PAYLOAD, DATA_COMPLETE_SHRED, DATA_SHRED_PAYLOAD, LAST_SHRED_IN_SLOT,
+    SIZE_OF_CODING_SHRED_HEADERS, SIZE_OF_DATA_SHRED_HEADERS, SIZE_OF_SIGNATURE,
+};
+use crate::slot_window::{SlotWindow, DEFAULT_SLOT_DEPTH};
+
+// 每个 FEC 集合的数据 shred 数, 编码 shred 数与之相同, 与主网一致
+const DATA_SHREDS_PER_FEC_SET: usize = 32;
+// 集合大小为 64 个 shred 时的 merkle 证明层数, 按此切分数据可以放进任意大小的集合
+const MAX_PROOF_SIZE: u8 = 6;
+
+// 把合成 entry 批次切分为 chained merkle 格式的数据 shred 和编码 shred
+// 签名、merkle 根和证明都填 0, 接收端不校验
+pub struct MockShredder {
+    // 每个 slot 下一个数据 shred 和编码 shred 的序号
+    next: SlotWindow<(u32, u32)>,
+}
+
+impl Default for MockShredder {
+    fn default() -> Self {
+        Self { next: SlotWindow::new(DEFAULT_SLOT_DEPTH) }
+    }
+}
+
+impl MockShredder {
+    // data 为 bincode 编码的 Vec<Entry>, 返回可以直接发送的 shred 数据包
+    pub fn shred(&mut self, slot: u64, data: &[u8], last_in_slot: bool) -> Vec<Vec<u8>> {
+        self.next.advance(slot);
+        let Some((next_data, next_code)) = self.next.get_or_insert(slot) else {
+            return Vec::new();
+        };
+        let capacity = variant(MAX_PROOF_SIZE).data_capacity().unwrap_or_default();
+        let chunks: Vec<&[u8]> = data.chunks(capacity).collect();
+        let total = chunks.len();
+
+        let mut packets = Vec::new();
+        for (set_number, set) in chunks.chunks(DATA_SHREDS_PER_FEC_SET).enumerate() {
+            let num = set.len();
+            // 2 * num 个叶子的 merkle 树的层数
+            let variant = variant((2 * num).next_power_of_two().trailing_zeros() as u8);
+            let fec_set_index = *next_data;
+
+            let data_shreds: Vec<Vec<u8>> = set
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| {
+                    let flags = match (set_number * DATA_SHREDS_PER_FEC_SET + i + 1 == total, last_in_slot) {
+                        (true, true) => LAST_SHRED_IN_SLOT,
+                        (true, false) => DATA_COMPLETE_SHRED,
+                        (false, _) => 0,
+                    };
+                    let mut payload = vec![0u8; DATA_SHRED_PAYLOAD];
+                    write_common_header(&mut payload, variant.to_byte(true), slot, fec_set_index + i as u32, fec_set_index);
+                    payload[83..85].copy_from_slice(&1u16.to_le_bytes());
+                    payload[85] = flags;
+                    payload[86..88].copy_from_slice(&((SIZE_OF_DATA_SHRED_HEADERS + chunk.len()) as u16).to_le_bytes());
+                    payload[SIZE_OF_DATA_SHRED_HEADERS..SIZE_OF_DATA_SHRED_HEADERS + chunk.len()].copy_from_slice(chunk);
+                    payload
+                })
+                .collect();
+            *next_data += num as u32;
+
+            let shard_len = variant.erasure_shard_len().unwrap_or_default();
+            let data_shards: Vec<&[u8]> = data_shreds
+                .iter()
+                .map(|payload| &payload[SIZE_OF_SIGNATURE..SIZE_OF_SIGNATURE + shard_len])
+                .collect();
+            let mut parity = vec![vec![0u8; shard_len]; num];
+            if ReedSolomon::new(num, num).and_then(|rs| rs.encode_sep(&data_shards, &mut parity)).is_err() {
+                continue;
+            }
+            packets.extend(data_shreds);
+
+            for (position, shard) in parity.iter().enumerate() {
+                let mut payload = vec![0u8; CODING_SHRED_PAYLOAD];
+                write_common_header(&mut payload, variant.to_byte(false), slot, *next_code + position as u32, fec_set_index);
+                payload[83..85].copy_from_slice(&(num as u16).to_le_bytes());
+                payload[85..87].copy_from_slice(&(num as u16).to_le_bytes());
+                payload[87..89].copy_from_slice(&(position as u16).to_le_bytes());
+                payload[SIZE_OF_CODING_SHRED_HEADERS..SIZE_OF_CODING_SHRED_HEADERS + shard_len].copy_from_slice(shard);
+                packets.push(payload);
+            }
+            *next_code += num as u32;
+        }
+        packets
+    }
+}
+
+fn variant(proof_size: u8) -> ShredVariant {
+    ShredVariant::Merkle { proof_size, chained: true, resigned: false }
+}
+
+// 签名之后的公共头部: variant, slot, index, version, fec_set_index
+fn write_common_header(payload: &mut [u8], variant: u8, slot: u64, index: u32, fec_set_index: u32) {
+    payload[64] = variant;
+    payload[65..73].copy_from_slice(&slot.to_le_bytes());
+    payload[73..77].copy_from_slice(&index.to_le_bytes());
+    payload[77..79].copy_from_slice(&0u16.to_le_bytes());
+    payload[79..83].copy_from_slice(&fec_set_index.to_le_bytes());
+}
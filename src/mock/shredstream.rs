@@ -0,0 +1,80 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures_util::Stream;
+use jito_protos::auth::Role;
+use jito_protos::shredstream::shredstream_proxy_server::{ShredstreamProxy, ShredstreamProxyServer};
+use jito_protos::shredstream::{Entry, SubscribeEntriesRequest};
+use jito_protos::tonic::{self, Request, Response, Status};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::mock::auth::MockAuthState;
+use crate::mock::feed::{DelayConfig, FeedEntry};
+
+pub struct MockShredstream {
+    feed: broadcast::Sender<Arc<FeedEntry>>,
+    config: DelayConfig,
+    // 设置后要求请求携带模拟鉴权服务签发的访问令牌
+    auth: Option<Arc<MockAuthState>>,
+}
+
+impl MockShredstream {
+    pub fn new(feed: broadcast::Sender<Arc<FeedEntry>>, config: DelayConfig, auth: Option<Arc<MockAuthState>>) -> Self {
+        Self { feed, config, auth }
+    }
+}
+
+#[tonic::async_trait]
+impl ShredstreamProxy for MockShredstream {
+    type SubscribeEntriesStream = Pin<Box<dyn Stream<Item = Result<Entry, Status>> + Send>>;
+
+    async fn subscribe_entries(
+        &self,
+        request: Request<SubscribeEntriesRequest>,
+    ) -> Result<Response<Self::SubscribeEntriesStream>, Status> {
+        if let Some(auth) = &self.auth {
+            auth.check(request.metadata(), Role::ShredstreamSubscriber).map_err(Status::unauthenticated)?;
+        }
+        let mut feed = self.feed.subscribe();
+        let config = self.config.clone();
+        let (tx, rx) = mpsc::channel(1024);
+
+        tokio::spawn(async move {
+            loop {
+                let feed_entry = match feed.recv().await {
+                    Ok(feed_entry) => feed_entry,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if !config.wait(&feed_entry).await {
+                    continue;
+                }
+
+                let entries = match bincode::serialize(&vec![feed_entry.entry.clone()]) {
+                    Ok(entries) => entries,
+                    Err(_) => continue,
+                };
+                if tx.send(Ok(Entry { slot: feed_entry.slot, entries })).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+// 在 addr 上启动模拟 ShredstreamProxy 服务, 直到出错才返回
+pub async fn serve(
+    addr: SocketAddr,
+    feed: broadcast::Sender<Arc<FeedEntry>>,
+    config: DelayConfig,
+    auth: Option<Arc<MockAuthState>>,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(ShredstreamProxyServer::new(MockShredstream::new(feed, config, auth)))
+        .serve(addr)
+        .await
+}
@@ -0,0 +1,193 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::clock::{ns_to_ms, Timestamp};
+use crate::compare::{Summary, Window};
+use crate::config::{Commitment, Config};
+use crate::source::{CompareMode, MatchKey, Source};
+use crate::timeline::TimelineSummary;
+
+// 结果文档中的端点信息
+#[derive(Serialize)]
+pub struct Endpoint {
+    pub label: String,
+    pub kind: String,
+    pub url: String,
+}
+
+// 本次运行的元数据
+#[derive(Serialize)]
+pub struct RunInfo {
+    pub version: &'static str,
+    pub started_at: String,
+    pub finished_at: String,
+    // 配置的统计时长和预热时长 (秒)
+    pub duration_secs: u64,
+    pub warmup_secs: u64,
+    // 统计窗口, 时间为 Unix 纳秒
+    pub window: Window,
+    // 实际运行时长 (秒), 回放时按抓包中的接收时间计算
+    pub elapsed_secs: f64,
+    pub mode: CompareMode,
+    pub commitment: Commitment,
+    pub replay: Vec<PathBuf>,
+    pub endpoints: Vec<Endpoint>,
+}
+
+// 完整的结果文档, 用于 --output json 和 --report
+#[derive(Serialize)]
+pub struct Report {
+    pub run: RunInfo,
+    #[serde(flatten)]
+    pub summary: Summary,
+    // 只在指定 --timeline 时输出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeline: Option<TimelineSummary>,
+}
+
+impl Report {
+    pub fn new(
+        config: &Config,
+        sources: &[Source],
+        started_at: DateTime<Local>,
+        elapsed_secs: f64,
+        summary: Summary,
+        timeline: Option<TimelineSummary>,
+    ) -> Self {
+        Self {
+            run: RunInfo {
+                version: env!("CARGO_PKG_VERSION"),
+                started_at: started_at.to_rfc3339(),
+                finished_at: Local::now().to_rfc3339(),
+                duration_secs: config.duration,
+                warmup_secs: config.warmup,
+                window: config.window,
+                elapsed_secs,
+                mode: config.mode,
+                commitment: config.commitment,
+                replay: config.replay.clone(),
+                endpoints: sources
+                    .iter()
+                    .map(|s| Endpoint { label: s.label.clone(), kind: s.kind.name().to_string(), url: s.url.clone() })
+                    .collect(),
+            },
+            summary,
+            timeline,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_json()?.as_bytes())?;
+        file.write_all(b"\n")
+    }
+}
+
+// CSV 字段: 含逗号、引号或换行时加引号, 引号写两次
+pub fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+// 逐样本 CSV: 每个计入统计的到达一行, 首先到达者的 lag 为 0
+// 表头: slot, 非 slot 模式下的匹配键 (signature、entry 序号或 entry-hash), source, recv_ts (Unix 毫秒, 精确到纳秒),
+// lag (毫秒, 按单调时钟计算), 提供 leader 信息时追加 leader 列, 未知时为空
+// source 含逗号、引号或换行时按 CSV 规则加引号
+pub struct SampleWriter {
+    writer: BufWriter<File>,
+    // 是否在 slot 之后写匹配键列
+    key_column: bool,
+    leaders: bool,
+}
+
+impl SampleWriter {
+    pub fn create(path: &Path, mode: CompareMode, leaders: bool) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let key_column = mode != CompareMode::Slot;
+        write!(writer, "slot")?;
+        if key_column {
+            write!(writer, ",{}", mode.name())?;
+        }
+        writeln!(writer, ",source,recv_ts,lag{}", if leaders { ",leader" } else { "" })?;
+        Ok(Self { writer, key_column, leaders })
+    }
+
+    pub fn write(
+        &mut self,
+        key: &MatchKey,
+        slot: u64,
+        source: &str,
+        recv_ts: Timestamp,
+        lag_ns: u64,
+        leader: Option<&str>,
+    ) -> io::Result<()> {
+        write!(self.writer, "{}", slot)?;
+        if self.key_column {
+            // slot 已单独成列, entry 模式只写 slot 内的序号
+            match key {
+                MatchKey::Entry(_, index) => write!(self.writer, ",{}", index)?,
+                key => write!(self.writer, ",{}", key)?,
+            }
+        }
+        write!(
+            self.writer,
+            ",{},{}.{:06},{:.6}",
+            csv_field(source),
+            recv_ts.wall_ns / 1_000_000,
+            recv_ts.wall_ns % 1_000_000,
+            ns_to_ms(lag_ns)
+        )?;
+        if self.leaders {
+            write!(self.writer, ",{}", leader.unwrap_or_default())?;
+        }
+        writeln!(self.writer)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("shred-ny"), "shred-ny");
+        assert!(matches!(csv_field("shred-ny"), Cow::Borrowed(_)));
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+    #[test]
+    fn sample_rows_quote_the_source_label() {
+        let path = std::env::temp_dir().join(format!("samples-{}.csv", std::process::id()));
+        let mut samples = SampleWriter::create(&path, CompareMode::Entry, true).unwrap();
+        let timestamp = Timestamp { mono_ns: 0, wall_ns: 1_700_000_000_123_456_789 };
+        samples.write(&MatchKey::Entry(5, 3), 5, "grpc,ny", timestamp, 1_500_000, Some("leader")).unwrap();
+        samples.write(&MatchKey::Entry(5, 4), 5, "shred", timestamp, 0, None).unwrap();
+        samples.flush().unwrap();
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines, [
+            "slot,entry,source,recv_ts,lag,leader",
+            "5,3,\"grpc,ny\",1700000000123.456789,1.500000,leader",
+            "5,4,shred,1700000000123.456789,0.000000,",
+        ]);
+    }
+}
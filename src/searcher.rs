@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use jito_protos::searcher::searcher_service_client::SearcherServiceClient;
+use jito_protos::searcher::{
+    ConnectedLeadersRegionedRequest, GetRegionsRequest, GetRegionsResponse, NextScheduledLeaderRequest,
+    NextScheduledLeaderResponse,
+};
+use jito_protos::tonic::codegen::InterceptedService;
+use jito_protos::tonic::transport::{Channel, Endpoint};
+
+use crate::auth::{Authenticator, OptionalBearer};
+use crate::leaders::LeaderSchedule;
+
+type SearcherResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+// 区域 -> leader 身份 -> 该 leader 在当前 epoch 的 slot
+pub type ConnectedLeaders = HashMap<String, HashMap<String, Vec<u64>>>;
+
+// Jito block engine 的 SearcherService 客户端, 只封装查询 leader 和区域的接口
+pub struct SearcherClient {
+    client: SearcherServiceClient<InterceptedService<Channel, OptionalBearer>>,
+}
+
+impl SearcherClient {
+    // 配置了鉴权时先等待第一次鉴权成功
+    pub async fn connect(url: &str, auth: Option<&Authenticator>) -> SearcherResult<Self> {
+        if let Some(auth) = auth {
+            auth.ready().await?;
+        }
+        let channel = Endpoint::from_shared(url.to_string())?.connect().await?;
+        Ok(Self { client: SearcherServiceClient::with_interceptor(channel, OptionalBearer::new(auth)) })
+    }
+
+    // 当前连接的区域和所有在线的区域
+    pub async fn regions(&mut self) -> SearcherResult<GetRegionsResponse> {
+        Ok(self.client.get_regions(GetRegionsRequest {}).await?.into_inner())
+    }
+
+    // 下一个运行 Jito 的 leader, regions 为空时只查当前连接的区域
+    pub async fn next_scheduled_leader(&mut self, regions: Vec<String>) -> SearcherResult<NextScheduledLeaderResponse> {
+        Ok(self.client.get_next_scheduled_leader(NextScheduledLeaderRequest { regions }).await?.into_inner())
+    }
+
+    // 各区域连接的 leader, regions 为空时只查当前连接的区域
+    pub async fn connected_leaders_regioned(&mut self, regions: Vec<String>) -> SearcherResult<ConnectedLeaders> {
+        let response = self
+            .client
+            .get_connected_leaders_regioned(ConnectedLeadersRegionedRequest { regions })
+            .await?
+            .into_inner();
+        Ok(response
+            .connected_validators
+            .into_iter()
+            .map(|(region, leaders)| {
+                let leaders = leaders.connected_validators.into_iter().map(|(leader, slots)| (leader, slots.slots)).collect();
+                (region, leaders)
+            })
+            .collect())
+    }
+}
+
+// 查询所有在线区域连接的 leader 并合并到 leader 信息中, 返回 (区域数, leader 数)
+pub async fn load_connected_leaders(
+    url: &str,
+    auth: Option<&Authenticator>,
+    schedule: &mut LeaderSchedule,
+) -> SearcherResult<(usize, usize)> {
+    let mut client = SearcherClient::connect(url, auth).await?;
+    let regions = client.regions().await?.available_regions;
+    let connected = client.connected_leaders_regioned(regions).await?;
+    let leaders = connected.values().map(HashMap::len).sum();
+    for (region, leaders) in &connected {
+        for (leader, slots) in leaders {
+            schedule.add_connected(leader, region, slots.iter().copied());
+        }
+    }
+    Ok((connected.len(), leaders))
+}
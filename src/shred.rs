@@ -1,47 +1,537 @@
-use jito_protos::shredstream::{
-    shredstream_proxy_client::ShredstreamProxyClient, SubscribeEntriesRequest,
-};
-use dotenvy::dotenv;
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::collections::HashSet;
-
-
-#[tokio::main]
-async fn main() -> Result<(), std::io::Error> {
-    dotenv().ok();
-    let url = std::env::var("SHRED_URL").expect("SHRED_URL must be set");   
-    let mut client = ShredstreamProxyClient::connect(url)
-        .await
-        .unwrap();
-    let mut stream = client
-        .subscribe_entries(SubscribeEntriesRequest {})
-        .await
-        .unwrap()
-        .into_inner();
-
-    let mut processed_slots = HashSet::new();
-    while let Some(slot_entry) = stream.message().await.unwrap() {
-        let _entries =
-            match bincode::deserialize::<Vec<solana_entry::entry::Entry>>(&slot_entry.entries) {
-                Ok(e) => e,
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+
+use chrono::Local;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use tokio::time::{Duration, Instant};
+
+use crate::slot_window::SlotWindow;
+
+// shred 布局, 与 solana-ledger 的 shred 格式一致
+pub const SIZE_OF_SIGNATURE: usize = 64;
+const SIZE_OF_COMMON_SHRED_HEADER: usize = 83;
+pub const SIZE_OF_DATA_SHRED_HEADERS: usize = 88;
+pub const SIZE_OF_CODING_SHRED_HEADERS: usize = 89;
+pub const SIZE_OF_MERKLE_ROOT: usize = 32;
+pub const SIZE_OF_MERKLE_PROOF_ENTRY: usize = 20;
+// 数据 shred 和编码 shred 的完整长度, 数据包末尾可能还带有 nonce 或填充
+pub const DATA_SHRED_PAYLOAD: usize = 1203;
+pub const CODING_SHRED_PAYLOAD: usize = 1228;
+// 旧格式的数据 shred 与编码 shred 等长, 纠删码分片为编码 shred 去掉头部后的部分
+const LEGACY_DATA_SHRED_PAYLOAD: usize = CODING_SHRED_PAYLOAD;
+const LEGACY_ERASURE_SHARD: usize = CODING_SHRED_PAYLOAD - SIZE_OF_CODING_SHRED_HEADERS;
+
+// 数据 shred 的 flags
+pub const DATA_COMPLETE_SHRED: u8 = 0b0100_0000;
+pub const LAST_SHRED_IN_SLOT: u8 = 0b1100_0000;
+
+const LEGACY_CODE: u8 = 0b1010_0101;
+const LEGACY_DATA: u8 = 0b0101_1010;
+
+// 一个 FEC 集合最多 256 个分片 (GF(2^8))
+const MAX_FEC_SHARDS: usize = 256;
+
+// 还原 shred 保留的 slot 数: 一个 slot 的 shred 通常在几秒内到齐, 迟到更久的 shred 即使还原出批次也没有对比意义,
+// 不跟随匹配键的 slot_depth, 避免缺失分片的集合长时间占用内存
+pub const DESHRED_SLOT_DEPTH: u64 = 32;
+// slot 超过最新 slot 这么多的 shred 视为无效数据包丢弃, 避免一个错误的 slot 把窗口推到远处
+const MAX_SLOTS_AHEAD: u64 = 1000;
+// 连续这么多个 shred 都远超最新 slot 时认为 slot 确实发生了跳跃 (例如长时间中断后恢复), 接受新的 slot
+const FAR_FUTURE_STREAK: u32 = 64;
+// 还原统计有变化时输出日志的最小间隔, 避免恢复失败时逐个 FEC 集合刷屏
+const STATS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShredVariant {
+    Legacy,
+    // proof_size: merkle 证明的层数; chained: 带有上一个 FEC 集合的 merkle 根; resigned: 末尾带有转发节点的签名
+    Merkle { proof_size: u8, chained: bool, resigned: bool },
+}
+
+impl ShredVariant {
+    // 解析 shred_variant 字节, 返回 (格式, 是否为数据 shred)
+    pub fn parse(byte: u8) -> Option<(Self, bool)> {
+        let merkle = |chained, resigned| ShredVariant::Merkle { proof_size: byte & 0x0f, chained, resigned };
+        match byte {
+            LEGACY_CODE => Some((ShredVariant::Legacy, false)),
+            LEGACY_DATA => Some((ShredVariant::Legacy, true)),
+            _ => match byte & 0xf0 {
+                0x40 => Some((merkle(false, false), false)),
+                0x60 => Some((merkle(true, false), false)),
+                0x70 => Some((merkle(true, true), false)),
+                0x80 => Some((merkle(false, false), true)),
+                0x90 => Some((merkle(true, false), true)),
+                0xb0 => Some((merkle(true, true), true)),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn to_byte(self, data: bool) -> u8 {
+        match self {
+            ShredVariant::Legacy if data => LEGACY_DATA,
+            ShredVariant::Legacy => LEGACY_CODE,
+            ShredVariant::Merkle { proof_size, chained, resigned } => {
+                let high = match (data, chained, resigned) {
+                    (false, false, _) => 0x40,
+                    (false, true, false) => 0x60,
+                    (false, true, true) => 0x70,
+                    (true, false, _) => 0x80,
+                    (true, true, false) => 0x90,
+                    (true, true, true) => 0xb0,
+                };
+                high | (proof_size & 0x0f)
+            }
+        }
+    }
+
+    // merkle 根、证明和转发签名占用的字节数, 不参与纠删码
+    fn trailer(self) -> usize {
+        match self {
+            ShredVariant::Legacy => 0,
+            ShredVariant::Merkle { proof_size, resigned, .. } => {
+                proof_size as usize * SIZE_OF_MERKLE_PROOF_ENTRY + if resigned { SIZE_OF_SIGNATURE } else { 0 }
+            }
+        }
+    }
+
+    fn chained_root(self) -> usize {
+        match self {
+            ShredVariant::Merkle { chained: true, .. } => SIZE_OF_MERKLE_ROOT,
+            _ => 0,
+        }
+    }
+
+    // 纠删码分片的长度, 数据 shred 和编码 shred 相同
+    pub fn erasure_shard_len(self) -> Option<usize> {
+        match self {
+            ShredVariant::Legacy => Some(LEGACY_ERASURE_SHARD),
+            ShredVariant::Merkle { .. } => LEGACY_ERASURE_SHARD.checked_sub(self.trailer()),
+        }
+    }
+
+    // 数据 shred 可以携带的数据长度
+    pub fn data_capacity(self) -> Option<usize> {
+        match self {
+            ShredVariant::Legacy => Some(LEGACY_ERASURE_SHARD - SIZE_OF_DATA_SHRED_HEADERS),
+            ShredVariant::Merkle { .. } => DATA_SHRED_PAYLOAD
+                .checked_sub(SIZE_OF_DATA_SHRED_HEADERS + self.chained_root() + self.trailer()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShredType {
+    Data { parent_offset: u16, flags: u8, size: u16 },
+    // position: 在本 FEC 集合编码 shred 中的序号
+    Code { num_data: u16, num_coding: u16, position: u16 },
+}
+
+// 解析后的单个 shred, 不校验签名和 merkle 证明
+pub struct Shred {
+    pub variant: ShredVariant,
+    pub slot: u64,
+    pub index: u32,
+    pub version: u16,
+    pub fec_set_index: u32,
+    pub shred_type: ShredType,
+    payload: Vec<u8>,
+}
+
+impl Shred {
+    pub fn parse(packet: &[u8]) -> Result<Self, String> {
+        if packet.len() < SIZE_OF_COMMON_SHRED_HEADER {
+            return Err(format!("数据包长度 {} 不足以包含 shred 头部", packet.len()));
+        }
+        let (variant, data) = ShredVariant::parse(packet[SIZE_OF_SIGNATURE])
+            .ok_or_else(|| format!("未知的 shred 类型 {:#04x}", packet[SIZE_OF_SIGNATURE]))?;
+        let payload_len = match (variant, data) {
+            (ShredVariant::Legacy, true) => LEGACY_DATA_SHRED_PAYLOAD,
+            (ShredVariant::Merkle { .. }, true) => DATA_SHRED_PAYLOAD,
+            (_, false) => CODING_SHRED_PAYLOAD,
+        };
+        let capacity = variant.data_capacity().ok_or("merkle 证明层数过大")?;
+
+        // 编码 shred 必须完整; 旧格式的数据 shred 可能省略末尾的填充
+        let mut payload = packet.to_vec();
+        if payload.len() < payload_len && !(data && variant == ShredVariant::Legacy) {
+            return Err(format!("shred 长度 {} 小于 {}", payload.len(), payload_len));
+        }
+        payload.resize(payload_len, 0);
+
+        let u16_at = |offset: usize| u16::from_le_bytes([payload[offset], payload[offset + 1]]);
+        let u32_at = |offset: usize| u32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap());
+        let shred_type = if data {
+            let size = u16_at(86);
+            if (size as usize) < SIZE_OF_DATA_SHRED_HEADERS || size as usize > SIZE_OF_DATA_SHRED_HEADERS + capacity {
+                return Err(format!("数据 shred 的长度字段 {} 无效", size));
+            }
+            ShredType::Data { parent_offset: u16_at(83), flags: payload[85], size }
+        } else {
+            let (num_data, num_coding, position) = (u16_at(83), u16_at(85), u16_at(87));
+            if num_data == 0 || num_coding == 0 || position >= num_coding
+                || num_data as usize + num_coding as usize > MAX_FEC_SHARDS
+            {
+                return Err(format!("编码 shred 的头部无效: {} 个数据, {} 个编码, 序号 {}", num_data, num_coding, position));
+            }
+            ShredType::Code { num_data, num_coding, position }
+        };
+
+        Ok(Self {
+            variant,
+            slot: u64::from_le_bytes(payload[65..73].try_into().unwrap()),
+            index: u32_at(73),
+            version: u16_at(77),
+            fec_set_index: u32_at(79),
+            shred_type,
+            payload,
+        })
+    }
+
+    pub fn is_data(&self) -> bool {
+        matches!(self.shred_type, ShredType::Data { .. })
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.payload[..SIZE_OF_SIGNATURE]
+    }
+
+    // 数据 shred 携带的数据, 一个 entry 批次的各 shred 数据依次拼接即为 bincode 编码的 Vec<Entry>
+    pub fn data(&self) -> &[u8] {
+        match self.shred_type {
+            ShredType::Data { size, .. } => &self.payload[SIZE_OF_DATA_SHRED_HEADERS..size as usize],
+            ShredType::Code { .. } => &[],
+        }
+    }
+
+    // 参与纠删码的部分: 旧格式的数据 shred 从头开始, merkle 数据 shred 跳过签名, 编码 shred 跳过头部
+    pub fn erasure_shard(&self) -> &[u8] {
+        let len = self.variant.erasure_shard_len().unwrap_or(0);
+        let start = match (self.variant, self.is_data()) {
+            (ShredVariant::Legacy, true) => 0,
+            (ShredVariant::Merkle { .. }, true) => SIZE_OF_SIGNATURE,
+            (_, false) => SIZE_OF_CODING_SHRED_HEADERS,
+        };
+        &self.payload[start..start + len]
+    }
+
+    // 由恢复出的纠删码分片还原数据 shred, signature 取自同一 FEC 集合的编码 shred
+    fn from_recovered(variant: ShredVariant, signature: &[u8], shard: &[u8]) -> Result<Self, String> {
+        // merkle 证明和转发签名不在分片中, 用 0 补齐, 不影响取出数据
+        let mut packet = match variant {
+            ShredVariant::Legacy => shard.to_vec(),
+            ShredVariant::Merkle { .. } => [signature, shard].concat(),
+        };
+        packet.resize(packet.len().max(DATA_SHRED_PAYLOAD), 0);
+        let shred = Self::parse(&packet)?;
+        if !shred.is_data() {
+            return Err("恢复出的分片不是数据 shred".to_string());
+        }
+        Ok(shred)
+    }
+}
+
+// 一个 FEC 集合收到的编码 shred, 数据 shred 存放在所属 slot 中
+#[derive(Default)]
+struct FecSet {
+    code: HashMap<u16, Shred>,
+    // 所有数据 shred 都已收到或恢复后为集合的数据 shred 序号范围, 此时编码 shred 已释放, 之后到达的也不再保存
+    completed: Option<Range<u32>>,
+}
+
+#[derive(Default)]
+struct SlotShreds {
+    data: HashMap<u32, Shred>,
+    // 所属 FEC 集合已完成、所属批次已输出而释放的数据 shred, 之后重复到达时直接丢弃
+    released: HashSet<u32>,
+    // 带有 DATA_COMPLETE_SHRED 标记的数据 shred, 即各 entry 批次的最后一个 shred
+    batch_ends: BTreeSet<u32>,
+    // 已输出的 entry 批次, 以最后一个 shred 的序号标识
+    emitted: HashSet<u32>,
+    fec_sets: HashMap<u32, FecSet>,
+}
+
+// 还原出的一个 entry 批次
+pub struct ShredBatch {
+    pub slot: u64,
+    // 批次第一个和最后一个数据 shred 的序号, 用于确定批次在 slot 内的位置
+    pub first_shred: u32,
+    pub last_shred: u32,
+    // bincode 编码的 Vec<Entry>, 与 ShredstreamProxy 推送的 Entry 消息的 entries 字段相同
+    pub entries: Vec<u8>,
+}
+
+// 还原过程的累计统计
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct DeshredStats {
+    // 用编码 shred 恢复出的数据 shred 数
+    pub recovered: u64,
+    // Reed-Solomon 恢复失败的次数
+    pub recovery_failures: u64,
+    // 序号越界 (index + 1 或 fec_set_index + num_data 超出 u32) 而丢弃的 shred 数
+    pub invalid: u64,
+    // slot 远超最新 slot 而丢弃的 shred 数
+    pub far_future: u64,
+}
+
+// 把 UDP 收到的 shred 还原为 entry 批次: 按 slot 收集数据 shred, 缺失时用编码 shred 做 Reed-Solomon 恢复,
+// 一个批次的数据 shred 全部到齐后拼接输出, 与 ShredstreamProxy 推送的 Entry 消息格式相同
+// 批次按完成顺序输出, 前面的批次缺失时后面的批次不等待, 批次在 slot 内的位置由 EntryParser 按首尾 shred 序号确定
+pub struct Deshredder {
+    slots: SlotWindow<SlotShreds>,
+    stats: DeshredStats,
+    // 连续远超最新 slot 的 shred 数
+    far_future_streak: u32,
+    // 上次输出日志的时间和当时的统计, 以及之后最近一次恢复失败的原因
+    logged_at: Option<Instant>,
+    logged: DeshredStats,
+    last_failure: Option<String>,
+}
+
+impl Default for Deshredder {
+    fn default() -> Self {
+        Self::new(DESHRED_SLOT_DEPTH)
+    }
+}
+
+impl Deshredder {
+    pub fn new(slot_depth: u64) -> Self {
+        Self {
+            slots: SlotWindow::new(slot_depth),
+            stats: DeshredStats::default(),
+            far_future_streak: 0,
+            logged_at: None,
+            logged: DeshredStats::default(),
+            last_failure: None,
+        }
+    }
+
+    pub fn stats(&self) -> DeshredStats {
+        self.stats
+    }
+
+    // 当前保存的数据 shred 和编码 shred 数
+    pub fn buffered(&self) -> usize {
+        self.slots
+            .values()
+            .map(|shreds| shreds.data.len() + shreds.fec_sets.values().map(|set| set.code.len()).sum::<usize>())
+            .sum()
+    }
+
+    // 加入一个 shred, 返回因此完整的 entry 批次
+    pub fn insert(&mut self, shred: Shred) -> Vec<ShredBatch> {
+        let batches = self.insert_shred(shred);
+        self.log_stats();
+        batches
+    }
+
+    fn insert_shred(&mut self, shred: Shred) -> Vec<ShredBatch> {
+        let slot = shred.slot;
+        // 后面按 index + 1 查找下一个批次, 按 fec_set_index + num_data 枚举集合内的数据 shred
+        let in_range = match shred.shred_type {
+            ShredType::Data { .. } => shred.index.checked_add(1).is_some(),
+            ShredType::Code { num_data, .. } => shred.fec_set_index.checked_add(num_data as u32).is_some(),
+        };
+        if !in_range {
+            self.stats.invalid += 1;
+            return Vec::new();
+        }
+        // 在推进窗口之前检查, 远超最新 slot 的 shred 不能淘汰现有的 slot
+        let tip = self.slots.tip();
+        if tip > 0 && slot > tip.saturating_add(MAX_SLOTS_AHEAD) {
+            self.far_future_streak += 1;
+            if self.far_future_streak < FAR_FUTURE_STREAK {
+                self.stats.far_future += 1;
+                return Vec::new();
+            }
+        }
+        self.far_future_streak = 0;
+
+        self.slots.advance(slot);
+        let Some(shreds) = self.slots.get_or_insert(slot) else {
+            return Vec::new();
+        };
+        let fec_set_index = shred.fec_set_index;
+
+        let mut inserted = Vec::new();
+        match shred.shred_type {
+            ShredType::Data { .. } => {
+                if shreds.data.contains_key(&shred.index) || shreds.released.contains(&shred.index) {
+                    return Vec::new();
+                }
+                inserted.push(shred.index);
+                shreds.insert_data(shred);
+            }
+            ShredType::Code { position, .. } => {
+                let fec_set = shreds.fec_sets.entry(fec_set_index).or_default();
+                if fec_set.completed.is_some() {
+                    return Vec::new();
+                }
+                fec_set.code.entry(position).or_insert(shred);
+            }
+        }
+        // 已完成的集合不再需要恢复
+        let pending = shreds.fec_sets.get(&fec_set_index).is_some_and(|fec_set| fec_set.completed.is_none());
+        if pending {
+            match shreds.recover(fec_set_index) {
+                Ok(recovered) => {
+                    self.stats.recovered += recovered.len() as u64;
+                    inserted.extend(recovered);
+                }
                 Err(e) => {
-                    println!("Deserialization failed with err: {e}");
-                    continue;
+                    self.stats.recovery_failures += 1;
+                    self.last_failure = Some(format!("slot {} FEC 集合 {}: {}", slot, fec_set_index, e));
                 }
+            }
+        }
+
+        let batches: Vec<ShredBatch> = inserted
+            .into_iter()
+            .flat_map(|index| shreds.complete_batches(index))
+            .map(|(first_shred, last_shred, entries)| ShredBatch { slot, first_shred, last_shred, entries })
+            .collect();
+        // 释放新输出批次中的数据 shred, 以及刚完成的集合中所属批次已输出的数据 shred
+        let completed = shreds.fec_sets.get(&fec_set_index).and_then(|fec_set| fec_set.completed.clone());
+        let candidates: Vec<u32> =
+            batches.iter().flat_map(|batch| batch.first_shred..=batch.last_shred).chain(completed.into_iter().flatten()).collect();
+        shreds.release(candidates);
+        batches
+    }
+
+    // 统计有变化时输出累计值, 两次输出至少间隔 STATS_LOG_INTERVAL; 有新的失败或丢弃时为 WARN
+    fn log_stats(&mut self) {
+        if self.stats == self.logged || self.logged_at.is_some_and(|at| at.elapsed() < STATS_LOG_INTERVAL) {
+            return;
+        }
+        let (stats, logged) = (self.stats, self.logged);
+        let level = if (stats.recovery_failures, stats.invalid, stats.far_future)
+            == (logged.recovery_failures, logged.invalid, logged.far_future)
+        {
+            "INFO"
+        } else {
+            "WARN"
+        };
+        println!("[{}] {}: shred 还原: 累计恢复 {} 个数据 shred, 恢复失败 {} 次, 丢弃序号越界的 shred {} 个, 丢弃远超最新 slot 的 shred {} 个{}",
+            Local::now().format("%H:%M:%S%.3f"),
+            level,
+            stats.recovered,
+            stats.recovery_failures,
+            stats.invalid,
+            stats.far_future,
+            self.last_failure.take().map(|e| format!(", 最近一次恢复失败: {}", e)).unwrap_or_default()
+        );
+        self.logged = stats;
+        self.logged_at = Some(Instant::now());
+    }
+}
+
+impl SlotShreds {
+    fn insert_data(&mut self, shred: Shred) {
+        if let ShredType::Data { flags, .. } = shred.shred_type {
+            if flags & DATA_COMPLETE_SHRED != 0 {
+                self.batch_ends.insert(shred.index);
+            }
+        }
+        self.data.insert(shred.index, shred);
+    }
+
+    // 所属 FEC 集合已完成且所属批次已输出的数据 shred 不再需要, 释放其数据
+    fn release(&mut self, indices: impl IntoIterator<Item = u32>) {
+        for index in indices {
+            let Some(shred) = self.data.get(&index) else {
+                continue;
             };
-        
-        if !processed_slots.contains(&slot_entry.slot) {
-            processed_slots.insert(slot_entry.slot);
-            let timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis();
-            println!(
-                "Slot: {}, Timestamp: {}",
-                slot_entry.slot,
-                timestamp
-            );
-        }
-    }
-    Ok(())
-}
\ No newline at end of file
+            let fec_set_done = self.fec_sets.get(&shred.fec_set_index).is_some_and(|fec_set| fec_set.completed.is_some());
+            let batch_done = self.batch_ends.range(index..).next().is_some_and(|end| self.emitted.contains(end));
+            if fec_set_done && batch_done {
+                self.data.remove(&index);
+                self.released.insert(index);
+            }
+        }
+    }
+
+    // 标记集合已完成并释放编码 shred
+    fn complete_fec_set(&mut self, fec_set_index: u32, data_indices: Range<u32>) {
+        let fec_set = self.fec_sets.get_mut(&fec_set_index).unwrap();
+        fec_set.code = HashMap::new();
+        fec_set.completed = Some(data_indices);
+    }
+
+    // 编码 shred 和已有数据 shred 足够时恢复缺失的数据 shred, 返回恢复出的序号
+    fn recover(&mut self, fec_set_index: u32) -> Result<Vec<u32>, String> {
+        let Some(fec_set) = self.fec_sets.get(&fec_set_index) else {
+            return Ok(Vec::new());
+        };
+        // 只有编码 shred 带有集合大小
+        let Some(code) = fec_set.code.values().next() else {
+            return Ok(Vec::new());
+        };
+        let ShredType::Code { num_data, num_coding, .. } = code.shred_type else {
+            return Ok(Vec::new());
+        };
+        let end = fec_set_index.checked_add(num_data as u32).ok_or("FEC 集合的数据 shred 序号超出范围")?;
+        let (num_data, num_coding) = (num_data as usize, num_coding as usize);
+        let data_indices = fec_set_index..end;
+        let present = data_indices.clone().filter(|index| self.data.contains_key(index)).count();
+        if present == num_data {
+            self.complete_fec_set(fec_set_index, data_indices);
+            return Ok(Vec::new());
+        }
+        if present + fec_set.code.len() < num_data {
+            return Ok(Vec::new());
+        }
+
+        let (variant, signature) = (code.variant, code.signature().to_vec());
+        let mut shards: Vec<Option<Vec<u8>>> = data_indices
+            .clone()
+            .map(|index| self.data.get(&index).map(|shred| shred.erasure_shard().to_vec()))
+            .chain((0..num_coding as u16).map(|position| fec_set.code.get(&position).map(|shred| shred.erasure_shard().to_vec())))
+            .collect();
+        let shard_len = variant.erasure_shard_len().unwrap_or(0);
+        if shards.iter().flatten().any(|shard| shard.len() != shard_len) {
+            return Err("分片长度不一致".to_string());
+        }
+        ReedSolomon::new(num_data, num_coding)
+            .and_then(|rs| rs.reconstruct_data(&mut shards))
+            .map_err(|e| format!("{:?}", e))?;
+        self.complete_fec_set(fec_set_index, data_indices.clone());
+
+        let mut recovered = Vec::new();
+        for (index, shard) in data_indices.zip(shards) {
+            if self.data.contains_key(&index) {
+                continue;
+            }
+            let shred = Shred::from_recovered(variant, &signature, &shard.ok_or("恢复结果缺少分片")?)?;
+            if shred.index != index || shred.fec_set_index != fec_set_index {
+                return Err(format!("恢复出的 shred 序号 {} 与预期 {} 不符", shred.index, index));
+            }
+            recovered.push(index);
+            self.insert_data(shred);
+        }
+        Ok(recovered)
+    }
+
+    // 检查包含 index 的批次, 以及 index 为批次末尾时的下一个批次, 返回新完整的批次 (首尾 shred 序号和数据)
+    fn complete_batches(&mut self, index: u32) -> Vec<(u32, u32, Vec<u8>)> {
+        let mut completed = Vec::new();
+        completed.extend(self.complete_batch(index));
+        if let Some(next) = index.checked_add(1).filter(|_| self.batch_ends.contains(&index)) {
+            completed.extend(self.complete_batch(next));
+        }
+        completed
+    }
+
+    // 批次从上一个批次末尾的下一个 shred (或 0) 开始, 到第一个带 DATA_COMPLETE_SHRED 标记的 shred 结束
+    fn complete_batch(&mut self, index: u32) -> Option<(u32, u32, Vec<u8>)> {
+        let end = *self.batch_ends.range(index..).next()?;
+        if self.emitted.contains(&end) {
+            return None;
+        }
+        let start = self.batch_ends.range(..index).next_back().map_or(0, |previous| previous + 1);
+        if !(start..=end).all(|i| self.data.contains_key(&i)) {
+            return None;
+        }
+        self.emitted.insert(end);
+        Some((start, end, (start..=end).flat_map(|i| self.data[&i].data()).copied().collect()))
+    }
+}
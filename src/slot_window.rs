@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+// 默认保留的 slot 数, 约 2 分钟, 足以覆盖正常的延迟和短暂的重连
+pub const DEFAULT_SLOT_DEPTH: u64 = 300;
+
+// 按 slot 分组的有界映射: 只保留落后于最新 slot 不超过 depth 的数据,
+// 更早的 slot 在 tip 前进时被淘汰, 长时间运行时内存不再随 slot 数增长
+pub struct SlotWindow<V> {
+    depth: u64,
+    tip: u64,
+    slots: BTreeMap<u64, V>,
+}
+
+impl<V: Default> SlotWindow<V> {
+    pub fn new(depth: u64) -> Self {
+        Self { depth: depth.max(1), tip: 0, slots: BTreeMap::new() }
+    }
+
+    pub fn tip(&self) -> u64 {
+        self.tip
+    }
+
+    // 该 slot 是否已落在窗口之外
+    pub fn is_expired(&self, slot: u64) -> bool {
+        slot.saturating_add(self.depth) <= self.tip
+    }
+
+    // 取得某个 slot 的数据, 不存在时插入默认值; 已落在窗口之外的 slot 返回 None
+    pub fn get_or_insert(&mut self, slot: u64) -> Option<&mut V> {
+        if self.is_expired(slot) {
+            return None;
+        }
+        Some(self.slots.entry(slot).or_default())
+    }
+
+    // 窗口内各 slot 的数据, 按 slot 从小到大排列
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.slots.values()
+    }
+
+    // 推进 tip, 返回被淘汰的 slot, 按 slot 从小到大排列
+    pub fn advance(&mut self, slot: u64) -> Vec<(u64, V)> {
+        if slot <= self.tip {
+            return Vec::new();
+        }
+        self.tip = slot;
+        self.evict_before(self.tip.saturating_sub(self.depth - 1))
+    }
+
+    // 淘汰所有小于 slot 的数据
+    pub fn evict_before(&mut self, slot: u64) -> Vec<(u64, V)> {
+        let kept = self.slots.split_off(&slot);
+        std::mem::replace(&mut self.slots, kept).into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots(evicted: &[(u64, u32)]) -> Vec<u64> {
+        evicted.iter().map(|(slot, _)| *slot).collect()
+    }
+
+    #[test]
+    fn slots_behind_the_depth_are_evicted_in_order() {
+        let mut window = SlotWindow::<u32>::new(3);
+        for slot in [10, 11, 12] {
+            *window.get_or_insert(slot).unwrap() += 1;
+        }
+        assert!(window.advance(12).is_empty());
+        // 旧 slot 不推进 tip
+        assert!(window.advance(11).is_empty());
+        assert_eq!(window.tip(), 12);
+
+        *window.get_or_insert(14).unwrap() += 1;
+        assert_eq!(slots(&window.advance(14)), [10, 11]);
+        assert_eq!(window.values().count(), 2);
+        assert!(window.is_expired(11) && !window.is_expired(12));
+        // 已淘汰的 slot 不再插入
+        assert!(window.get_or_insert(11).is_none());
+        assert_eq!(window.get_or_insert(12).copied(), Some(1));
+    }
+
+    #[test]
+    fn memory_stays_bounded_by_depth() {
+        let mut window = SlotWindow::<u32>::new(DEFAULT_SLOT_DEPTH);
+        let mut evicted = 0;
+        for slot in 0..10_000 {
+            evicted += window.advance(slot).len() as u64;
+            window.get_or_insert(slot).unwrap();
+            assert!(window.values().count() as u64 <= DEFAULT_SLOT_DEPTH);
+        }
+        assert_eq!(window.values().count() as u64, DEFAULT_SLOT_DEPTH);
+        assert_eq!(evicted, 10_000 - DEFAULT_SLOT_DEPTH);
+    }
+
+    #[test]
+    fn evict_before_drains_older_slots() {
+        // 深度至少为 1, 最新 slot 总是保留
+        let mut window = SlotWindow::<u32>::new(0);
+        window.get_or_insert(5).unwrap();
+        assert!(window.advance(5).is_empty());
+        assert_eq!(slots(&window.advance(6)), [5]);
+
+        let mut window = SlotWindow::<u32>::new(100);
+        for slot in [1, 3, 5, 7] {
+            window.advance(slot);
+            window.get_or_insert(slot).unwrap();
+        }
+        assert_eq!(slots(&window.evict_before(5)), [1, 3]);
+        assert_eq!(window.values().count(), 2);
+    }
+}
@@ -0,0 +1,735 @@
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::Local;
+use clap::ValueEnum;
+use jito_protos::prost::Message as _;
+use jito_protos::shredstream::shredstream_proxy_client::ShredstreamProxyClient;
+use jito_protos::shredstream::{Entry, SubscribeEntriesRequest};
+use jito_protos::tonic::transport::Endpoint;
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use yellowstone_grpc_proto::geyser::{
+    subscribe_update::UpdateOneof, SubscribeRequest,
+    SubscribeRequestPing, SubscribeUpdate,
+};
+use yellowstone_grpc_proto::prost::Message as _;
+
+use crate::auth::{AuthConfig, Authenticator};
+use crate::backoff::Backoff;
+use crate::capture::{CaptureWriter, FrameKind};
+use crate::clock::Timestamp;
+use crate::entries::{EntryParser, SlotBatch};
+use crate::geyser::{self, TimestampedUpdate};
+use crate::heartbeat::{HeartbeatClient, HeartbeatConfig};
+use crate::shred::{Deshredder, Shred};
+use crate::subscription::Subscription;
+use crate::metrics::Metrics;
+use crate::slot_window::SlotWindow;
+
+// 对比模式: 按 slot 首次到达对比, 或按每笔交易签名首次到达对比,
+// 或订阅 Geyser 的 entry 推送, 与 Shredstream 解码出的 entry 按 (slot, 序号) 或 PoH 哈希对比
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompareMode {
+    Slot,
+    Signature,
+    Entry,
+    EntryHash,
+}
+
+impl CompareMode {
+    // 从环境变量 COMPARE_MODE 读取, 默认按 slot 对比
+    pub fn from_env() -> Self {
+        match std::env::var("COMPARE_MODE").as_deref() {
+            Ok("signature") => CompareMode::Signature,
+            Ok("entry") => CompareMode::Entry,
+            Ok("entry-hash") => CompareMode::EntryHash,
+            _ => CompareMode::Slot,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompareMode::Slot => "slot",
+            CompareMode::Signature => "signature",
+            CompareMode::Entry => "entry",
+            CompareMode::EntryHash => "entry-hash",
+        }
+    }
+
+    pub fn is_entry(&self) -> bool {
+        matches!(self, CompareMode::Entry | CompareMode::EntryHash)
+    }
+}
+
+// 各端用来匹配同一条数据的键
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchKey {
+    Slot(u64),
+    Signature(Signature),
+    // (slot, entry 在 slot 内的序号)
+    Entry(u64, u64),
+    EntryHash(Hash),
+}
+
+impl fmt::Display for MatchKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchKey::Slot(slot) => write!(f, "{}", slot),
+            MatchKey::Signature(signature) => write!(f, "{}", signature),
+            MatchKey::Entry(slot, index) => write!(f, "{}:{}", slot, index),
+            MatchKey::EntryHash(hash) => write!(f, "{}", hash),
+        }
+    }
+}
+
+// 某个匹配键在某个数据源的到达
+#[derive(Clone, Copy)]
+pub struct Arrival {
+    pub source: usize,
+    pub key: MatchKey,
+    // 该数据所在的 slot, 按 slot 对比时与匹配键相同
+    pub slot: u64,
+    pub timestamp: Timestamp,
+    // 按 entry 对比时该端报告的 entry 内容, 用于检查两边是否一致
+    pub entry: Option<EntryInfo>,
+    // 按签名对比时交易在 slot 内的位置 (包括投票交易)
+    pub position: Option<u64>,
+    // 按签名对比时 Geyser 端交易是否执行失败, Shred 端交易尚未执行, 为 None
+    pub failed: Option<bool>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EntryInfo {
+    pub index: u64,
+    pub hash: Hash,
+    // entry 中的交易数, 包括投票交易
+    pub transactions: u64,
+}
+
+// slot 内容的一批到达: Shredstream 端为一条 Entry 消息, Geyser 端为一笔交易 (按 entry 对比时为一个 entry)
+#[derive(Clone, Copy)]
+pub struct BatchArrival {
+    pub source: usize,
+    pub slot: u64,
+    // Shredstream 端为消息在 slot 内的序号, Geyser 端为交易或 entry 在 slot 内的序号
+    pub index: u64,
+    pub entries: u64,
+    // 按 entry 对比时为所有交易数, 否则只计匹配过滤条件的交易
+    pub transactions: u64,
+    pub timestamp: Timestamp,
+}
+
+// 数据源发往对比器的消息, 第一个字段为数据源序号
+#[derive(Clone)]
+pub enum SourceEvent {
+    // 某个匹配键到达
+    Arrival(Arrival),
+    // slot 内容的一批到达, 只在记录填充时间线时发出
+    Batch(BatchArrival),
+    // 连接断开, 开始重连
+    Disconnected(usize, Timestamp),
+    // 订阅成功 (首次连接或重连)
+    Connected(usize, Timestamp),
+}
+
+impl SourceEvent {
+    pub fn arrival(source: usize, key: MatchKey, slot: u64, timestamp: Timestamp) -> Self {
+        SourceEvent::Arrival(Arrival { source, key, slot, timestamp, entry: None, position: None, failed: None })
+    }
+
+    fn transaction(
+        source: usize,
+        signature: Signature,
+        slot: u64,
+        position: Option<u64>,
+        failed: Option<bool>,
+        timestamp: Timestamp,
+    ) -> Self {
+        let key = MatchKey::Signature(signature);
+        SourceEvent::Arrival(Arrival { source, key, slot, timestamp, entry: None, position, failed })
+    }
+
+    fn entry(source: usize, mode: CompareMode, slot: u64, entry: EntryInfo, timestamp: Timestamp) -> Self {
+        let key = match mode {
+            CompareMode::EntryHash => MatchKey::EntryHash(entry.hash),
+            _ => MatchKey::Entry(slot, entry.index),
+        };
+        SourceEvent::Arrival(Arrival { source, key, slot, timestamp, entry: Some(entry), position: None, failed: None })
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        match self {
+            SourceEvent::Arrival(arrival) => arrival.timestamp,
+            SourceEvent::Batch(batch) => batch.timestamp,
+            SourceEvent::Disconnected(_, ts) | SourceEvent::Connected(_, ts) => *ts,
+        }
+    }
+}
+
+// 订阅参数, 所有数据源共用
+#[derive(Clone)]
+pub struct StreamOptions {
+    pub mode: CompareMode,
+    // 去重用的 slot 记录保留的深度
+    pub slot_depth: u64,
+    // Geyser 端的订阅请求, 其中的交易过滤条件也用于 Shredstream 端的客户端过滤
+    pub subscription: Arc<Subscription>,
+    // 设置后 Shredstream 连接携带 Jito block engine 的访问令牌
+    pub auth: Option<AuthConfig>,
+    // 设置了 url 时 shreds 数据源向 Shredstream 服务发送心跳, 登记本地 UDP 地址
+    pub heartbeat: Option<HeartbeatConfig>,
+    // 为 true 时额外发出每批内容的到达事件, 用于统计 slot 的填充时间线
+    pub timeline: bool,
+}
+
+// 单个 shred 数据包不超过 1232 字节, 留出余量
+const UDP_PACKET_BUFFER: usize = 2048;
+
+type SessionResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+// Shreds: 在本地 UDP 地址直接接收原始 shred, 地址形如 0.0.0.0:20000
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    Geyser,
+    Shredstream,
+    Shreds,
+}
+
+impl SourceKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "geyser" | "grpc" => Some(SourceKind::Geyser),
+            "shredstream" | "shred" => Some(SourceKind::Shredstream),
+            "shreds" | "udp" => Some(SourceKind::Shreds),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SourceKind::Geyser => "geyser",
+            SourceKind::Shredstream => "shredstream",
+            SourceKind::Shreds => "shreds",
+        }
+    }
+
+    // 产出 Shredstream Entry 消息的数据源, 经 ShredstreamProxy 或本地还原
+    pub fn is_shred(&self) -> bool {
+        matches!(self, SourceKind::Shredstream | SourceKind::Shreds)
+    }
+}
+
+#[derive(Clone)]
+pub struct Source {
+    pub label: String,
+    pub kind: SourceKind,
+    pub url: String,
+}
+
+// 从环境变量读取数据源列表
+// SOURCES 格式: label=kind:url, 多个用逗号分隔, kind 为 geyser、shredstream 或 shreds
+// 例如: SOURCES=helius=geyser:http://a:10000,jito=shredstream:http://b:9999
+// 未设置 SOURCES 时回退到 GRPC_URL 和 SHRED_URL
+pub fn sources_from_env() -> Result<Vec<Source>, String> {
+    if let Ok(list) = std::env::var("SOURCES") {
+        return list
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| parse_source(item).ok_or_else(|| format!("SOURCES 中的数据源配置无效: {}", item)))
+            .collect();
+    }
+
+    let mut sources = Vec::new();
+    if let Ok(url) = std::env::var("GRPC_URL") {
+        sources.push(Source { label: "GRPC".to_string(), kind: SourceKind::Geyser, url });
+    }
+    if let Ok(url) = std::env::var("SHRED_URL") {
+        sources.push(Source { label: "SHRED".to_string(), kind: SourceKind::Shredstream, url });
+    }
+    Ok(sources)
+}
+
+pub fn parse_source(item: &str) -> Option<Source> {
+    let (label, rest) = item.split_once('=')?;
+    let (kind, url) = rest.split_once(':')?;
+    Some(Source {
+        label: label.trim().to_string(),
+        kind: SourceKind::parse(kind.trim())?,
+        url: url.trim().to_string(),
+    })
+}
+
+// 把原始消息转换为对比事件, 直连订阅和离线回放共用
+pub struct Decoder {
+    parser: EntryParser,
+    // 本地 UDP 接收的 shred 在这里还原, 回放抓包文件时同样经过还原
+    deshredder: Deshredder,
+    subscription: Arc<Subscription>,
+    // 按 slot 对比时已发出到达事件的 slot, Geyser 端和 Shredstream 端用同样的方式去重
+    emitted_slots: SlotWindow<bool>,
+    timeline: bool,
+}
+
+impl Decoder {
+    pub fn new(options: &StreamOptions) -> Self {
+        Self {
+            parser: EntryParser::new(options.slot_depth),
+            deshredder: Deshredder::default(),
+            subscription: options.subscription.clone(),
+            emitted_slots: SlotWindow::new(options.slot_depth),
+            timeline: options.timeline,
+        }
+    }
+
+    pub fn geyser(&mut self, index: usize, mode: CompareMode, update: &SubscribeUpdate, timestamp: Timestamp) -> Vec<SourceEvent> {
+        let mut events = self.geyser_events(index, mode, update, timestamp);
+        if self.timeline {
+            events.extend(geyser_batch(index, mode, update, timestamp).map(SourceEvent::Batch));
+        }
+        events
+    }
+
+    fn geyser_events(&mut self, index: usize, mode: CompareMode, update: &SubscribeUpdate, timestamp: Timestamp) -> Vec<SourceEvent> {
+        if mode.is_entry() {
+            let Some(UpdateOneof::Entry(entry)) = &update.update_oneof else {
+                return Vec::new();
+            };
+            let Ok(hash) = <[u8; 32]>::try_from(entry.hash.as_slice()) else {
+                return Vec::new();
+            };
+            let info = EntryInfo {
+                index: entry.index,
+                hash: Hash::new_from_array(hash),
+                transactions: entry.executed_transaction_count,
+            };
+            return vec![SourceEvent::entry(index, mode, entry.slot, info, timestamp)];
+        }
+        let Some(UpdateOneof::Transaction(sut)) = &update.update_oneof else {
+            return Vec::new();
+        };
+        match mode {
+            CompareMode::Slot => {
+                if !self.first_in_slot(sut.slot) {
+                    return Vec::new();
+                }
+                vec![SourceEvent::arrival(index, MatchKey::Slot(sut.slot), sut.slot, timestamp)]
+            }
+            CompareMode::Signature => sut
+                .transaction
+                .as_ref()
+                .and_then(|info| {
+                    let signature = Signature::try_from(info.signature.as_slice()).ok()?;
+                    let failed = info.meta.as_ref().map(|meta| meta.err.is_some());
+                    Some(SourceEvent::transaction(index, signature, sut.slot, Some(info.index), failed, timestamp))
+                })
+                .into_iter()
+                .collect(),
+            // 已在前面处理
+            CompareMode::Entry | CompareMode::EntryHash => Vec::new(),
+        }
+    }
+
+    pub fn shred(&mut self, index: usize, mode: CompareMode, slot_entry: &Entry, timestamp: Timestamp) -> Vec<SourceEvent> {
+        let Ok(batch) = self.parser.parse(slot_entry.slot, &slot_entry.entries) else {
+            return Vec::new();
+        };
+        self.batch_events(index, mode, &batch, timestamp)
+    }
+
+    // 本地 UDP 接收的一个 shred, 还原出完整的批次后按 Shredstream 消息处理, 批次位置按 shred 序号确定
+    // 按 entry 对比时前面的批次未还原的批次先保留, 位置确定后以原到达时间发出, 使 entry 序号与 Geyser 一致
+    pub fn shreds(&mut self, index: usize, mode: CompareMode, shred: Shred, timestamp: Timestamp) -> Vec<SourceEvent> {
+        let mut events = Vec::new();
+        for batch in self.deshredder.insert(shred) {
+            let parsed = self.parser.parse_shreds(
+                batch.slot,
+                batch.first_shred,
+                batch.last_shred,
+                &batch.entries,
+                timestamp,
+                mode.is_entry(),
+            );
+            for (batch, timestamp) in parsed.into_iter().flatten() {
+                events.extend(self.batch_events(index, mode, &batch, timestamp));
+            }
+        }
+        events
+    }
+
+    fn batch_events(&mut self, index: usize, mode: CompareMode, batch: &SlotBatch, timestamp: Timestamp) -> Vec<SourceEvent> {
+        let mut events = self.shred_events(index, mode, batch, timestamp);
+        if self.timeline {
+            let transactions = match mode.is_entry() {
+                true => batch.transactions().count(),
+                false => batch.transactions().filter(|transaction| self.subscription.matches(transaction)).count(),
+            };
+            events.push(SourceEvent::Batch(BatchArrival {
+                source: index,
+                slot: batch.slot,
+                index: batch.batch_index as u64,
+                entries: batch.entries.len() as u64,
+                transactions: transactions as u64,
+                timestamp,
+            }));
+        }
+        events
+    }
+
+    // 该 slot 是否第一次到达, 已淘汰的旧 slot 不再发出
+    fn first_in_slot(&mut self, slot: u64) -> bool {
+        self.emitted_slots.advance(slot);
+        match self.emitted_slots.get_or_insert(slot) {
+            Some(emitted) if !*emitted => {
+                *emitted = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn shred_events(&mut self, index: usize, mode: CompareMode, batch: &SlotBatch, timestamp: Timestamp) -> Vec<SourceEvent> {
+        // Geyser 的 entry 推送不经过交易过滤, 这里同样发出所有 entry
+        if mode.is_entry() {
+            return batch
+                .entries
+                .iter()
+                .map(|entry| {
+                    let info = EntryInfo { index: entry.index, hash: entry.hash, transactions: entry.transactions.len() as u64 };
+                    SourceEvent::entry(index, mode, batch.slot, info, timestamp)
+                })
+                .collect();
+        }
+
+        // 与 Geyser 端的订阅条件保持一致, 只保留匹配过滤条件的交易
+        let mut matched = batch
+            .positioned_transactions()
+            .filter(|(_, transaction)| self.subscription.matches(transaction))
+            .peekable();
+        match mode {
+            // 每个 slot 第一条含有匹配交易的消息算作到达, 与 Geyser 端第一笔匹配交易对应
+            CompareMode::Slot => {
+                if matched.peek().is_none() {
+                    return Vec::new();
+                }
+                if !self.first_in_slot(batch.slot) {
+                    return Vec::new();
+                }
+                vec![SourceEvent::arrival(index, MatchKey::Slot(batch.slot), batch.slot, timestamp)]
+            }
+            // 同一批 entries 里的交易使用同一个到达时间
+            CompareMode::Signature => matched
+                .filter_map(|(position, transaction)| Some((position, transaction.signature()?)))
+                .map(|(position, signature)| SourceEvent::transaction(index, *signature, batch.slot, position, None, timestamp))
+                .collect(),
+            // 已在前面处理
+            CompareMode::Entry | CompareMode::EntryHash => Vec::new(),
+        }
+    }
+}
+
+// Geyser 端的一次推送对应的批次: 按 entry 对比时为一个 entry, 否则为一笔交易
+fn geyser_batch(source: usize, mode: CompareMode, update: &SubscribeUpdate, timestamp: Timestamp) -> Option<BatchArrival> {
+    match &update.update_oneof {
+        Some(UpdateOneof::Entry(entry)) if mode.is_entry() => Some(BatchArrival {
+            source,
+            slot: entry.slot,
+            index: entry.index,
+            entries: 1,
+            transactions: entry.executed_transaction_count,
+            timestamp,
+        }),
+        Some(UpdateOneof::Transaction(sut)) if !mode.is_entry() => Some(BatchArrival {
+            source,
+            slot: sut.slot,
+            index: sut.transaction.as_ref()?.index,
+            entries: 0,
+            transactions: 1,
+            timestamp,
+        }),
+        _ => None,
+    }
+}
+
+// 为每个数据源启动对应的订阅任务, capture 不为空时同时把原始消息写入抓包文件,
+// metrics 不为空时统计收到的消息数和字节数
+pub fn spawn_sources(
+    sources: &[Source],
+    options: StreamOptions,
+    capture: Option<CaptureWriter>,
+    metrics: Option<Metrics>,
+    tx: mpsc::Sender<SourceEvent>,
+) {
+    // 所有 Shredstream 连接和心跳共用同一个访问令牌
+    let heartbeat = options.heartbeat.as_ref().is_some_and(|heartbeat| heartbeat.url.is_some());
+    let auth = options
+        .auth
+        .clone()
+        .filter(|_| {
+            sources.iter().any(|s| s.kind == SourceKind::Shredstream || (s.kind == SourceKind::Shreds && heartbeat))
+        })
+        .map(Authenticator::spawn);
+    for (index, source) in sources.iter().enumerate() {
+        println!("[{}] INFO: 正在连接 {} ({}): {}",
+            Local::now().format("%H:%M:%S%.3f"), source.label, source.kind.name(), source.url);
+        tokio::spawn(supervise(index, source.clone(), options.clone(), capture.clone(), metrics.clone(), auth.clone(), tx.clone()));
+    }
+}
+
+// 保持订阅: 会话出错或结束后按指数退避重连, 直到对比器关闭通道
+async fn supervise(
+    index: usize,
+    source: Source,
+    options: StreamOptions,
+    capture: Option<CaptureWriter>,
+    metrics: Option<Metrics>,
+    auth: Option<Authenticator>,
+    tx: mpsc::Sender<SourceEvent>,
+) {
+    let mut backoff = Backoff::default();
+    let mut decoder = Decoder::new(&options);
+    // 心跳在重连期间保持, 任务退出时随之停止
+    let _heartbeat = spawn_heartbeat(&source, &options, auth.clone());
+    loop {
+        let session = Session {
+            index,
+            options: &options,
+            capture: capture.as_ref(),
+            metrics: metrics.as_ref(),
+            auth: auth.as_ref(),
+            tx: &tx,
+            backoff: &mut backoff,
+            decoder: &mut decoder,
+        };
+        let result = match source.kind {
+            SourceKind::Geyser => run_grpc_client(&source.url, session).await,
+            SourceKind::Shredstream => run_shred_client(&source.url, session).await,
+            SourceKind::Shreds => run_udp_client(&source.url, session).await,
+        };
+        if tx.is_closed() {
+            return;
+        }
+
+        let reason = match result {
+            Ok(()) => "数据流已结束".to_string(),
+            Err(e) => e.to_string(),
+        };
+        let timestamp = Timestamp::now();
+        write_capture(capture.as_ref(), index, FrameKind::Disconnected, timestamp, Vec::new);
+        let _ = tx.send(SourceEvent::Disconnected(index, timestamp)).await;
+        let delay = backoff.next_delay();
+        println!("[{}] WARN: {} 连接断开: {}, {:.1}秒后第 {} 次重连",
+            Local::now().format("%H:%M:%S%.3f"),
+            source.label,
+            reason,
+            delay.as_secs_f64(),
+            backoff.attempt()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+// shreds 数据源配置了心跳时向 Shredstream 服务登记本地地址
+fn spawn_heartbeat(source: &Source, options: &StreamOptions, auth: Option<Authenticator>) -> Option<HeartbeatClient> {
+    let heartbeat = options.heartbeat.as_ref().filter(|_| source.kind == SourceKind::Shreds)?;
+    let result = source
+        .url
+        .parse()
+        .map_err(|e| format!("无效的监听地址 {}: {}", source.url, e))
+        .and_then(|listen| heartbeat.spawn_for(listen, auth));
+    match result {
+        Ok(client) => client,
+        Err(e) => {
+            println!("[{}] ERROR: {} 无法发送心跳: {}", Local::now().format("%H:%M:%S%.3f"), source.label, e);
+            None
+        }
+    }
+}
+
+// 单次订阅会话用到的状态
+struct Session<'a> {
+    index: usize,
+    options: &'a StreamOptions,
+    capture: Option<&'a CaptureWriter>,
+    metrics: Option<&'a Metrics>,
+    auth: Option<&'a Authenticator>,
+    tx: &'a mpsc::Sender<SourceEvent>,
+    backoff: &'a mut Backoff,
+    decoder: &'a mut Decoder,
+}
+
+// 写入抓包文件, 失败时只输出警告
+fn write_capture(
+    capture: Option<&CaptureWriter>,
+    index: usize,
+    kind: FrameKind,
+    timestamp: Timestamp,
+    payload: impl FnOnce() -> Vec<u8>,
+) {
+    let Some(capture) = capture else {
+        return;
+    };
+    let result = match kind {
+        FrameKind::Data => capture.write(index, timestamp, &payload()),
+        kind => capture.write_event(index, kind, timestamp),
+    };
+    if let Err(e) = result {
+        println!("[{}] WARN: 写入抓包文件失败: {}", Local::now().format("%H:%M:%S%.3f"), e);
+    }
+}
+
+impl Session<'_> {
+    // 订阅成功, 同时记入抓包文件, 回放时同样发出
+    async fn connected(&self) {
+        let timestamp = Timestamp::now();
+        write_capture(self.capture, self.index, FrameKind::Connected, timestamp, Vec::new);
+        let _ = self.tx.send(SourceEvent::Connected(self.index, timestamp)).await;
+    }
+
+    async fn emit(&self, events: Vec<SourceEvent>) {
+        for event in events {
+            let _ = self.tx.send(event).await;
+        }
+    }
+
+    fn received(&self, bytes: impl FnOnce() -> usize) {
+        if let Some(metrics) = self.metrics {
+            metrics.received(self.index, bytes());
+        }
+    }
+
+    fn capture(&self, timestamp: Timestamp, payload: impl FnOnce() -> Vec<u8>) {
+        write_capture(self.capture, self.index, FrameKind::Data, timestamp, payload);
+    }
+}
+
+async fn run_grpc_client(url: &str, session: Session<'_>) -> SessionResult {
+    let subscribe_request = session.options.subscription.to_request();
+    let (requests, mut stream) = geyser::subscribe(url, subscribe_request).await?;
+    session.connected().await;
+
+    // 回复服务端 Ping 的序号, 每次递增, 服务端据此区分各次回复
+    let mut ping_id = 0;
+    while let Some(message) = stream.message().await? {
+        // 时间戳在 protobuf 解码之前记录, 见 geyser::TimestampedCodec
+        let TimestampedUpdate { timestamp, len, update: msg } = message;
+        session.backoff.reset();
+        session.received(|| len);
+        session.capture(timestamp, || msg.encode_to_vec());
+
+        if let Some(UpdateOneof::Ping(_)) = msg.update_oneof {
+            ping_id += 1;
+            requests.send(SubscribeRequest {
+                ping: Some(SubscribeRequestPing { id: ping_id }),
+                ..Default::default()
+            })?;
+            continue;
+        }
+        let events = session.decoder.geyser(session.index, session.options.mode, &msg, timestamp);
+        session.emit(events).await;
+        if session.tx.is_closed() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+async fn run_shred_client(url: &str, session: Session<'_>) -> SessionResult {
+    let channel = Endpoint::from_shared(url.to_string())?.connect().await?;
+    let mut stream = match session.auth {
+        Some(auth) => {
+            auth.ready().await?;
+            ShredstreamProxyClient::with_interceptor(channel, auth.interceptor())
+                .subscribe_entries(SubscribeEntriesRequest {})
+                .await?
+        }
+        None => ShredstreamProxyClient::new(channel).subscribe_entries(SubscribeEntriesRequest {}).await?,
+    }
+    .into_inner();
+    session.connected().await;
+
+    while let Some(slot_entry) = stream.message().await? {
+        // 在反序列化 entries 之前打时间戳, 避免 bincode 解码耗时计入 Shredstream 的延迟
+        // (外层 protobuf 消息只有 slot 和 entries 字节, 已由 tonic 解码)
+        let timestamp = Timestamp::now();
+        session.backoff.reset();
+        session.received(|| slot_entry.encoded_len());
+        session.capture(timestamp, || slot_entry.encode_to_vec());
+
+        let events = session.decoder.shred(session.index, session.options.mode, &slot_entry, timestamp);
+        session.emit(events).await;
+        if session.tx.is_closed() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// 在本地 UDP 地址接收原始 shred, 交给 Decoder 还原为 entry 批次
+// 批次的到达时间为补齐最后一个 shred 的数据包的接收时间
+async fn run_udp_client(addr: &str, session: Session<'_>) -> SessionResult {
+    let socket = UdpSocket::bind(addr).await.map_err(|e| format!("绑定 UDP 地址 {} 失败: {}", addr, e))?;
+    session.connected().await;
+
+    let mut buffer = vec![0u8; UDP_PACKET_BUFFER];
+    loop {
+        let (len, _) = socket.recv_from(&mut buffer).await?;
+        let timestamp = Timestamp::now();
+        session.received(|| len);
+        // 不是 shred 的数据包直接忽略
+        let Ok(shred) = Shred::parse(&buffer[..len]) else {
+            continue;
+        };
+        session.backoff.reset();
+        // 记录原始数据包, 回放时重新还原
+        session.capture(timestamp, || buffer[..len].to_vec());
+
+        let events = session.decoder.shreds(session.index, session.options.mode, shred, timestamp);
+        session.emit(events).await;
+        if session.tx.is_closed() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yellowstone_grpc_proto::geyser::SubscribeUpdateTransaction;
+
+    use super::*;
+
+    fn slot_update(slot: u64) -> SubscribeUpdate {
+        SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction { slot, ..Default::default() })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn geyser_slot_arrivals_are_deduplicated_by_slot() {
+        let options = StreamOptions {
+            mode: CompareMode::Slot,
+            slot_depth: 300,
+            subscription: Arc::new(Subscription::default()),
+            auth: None,
+            heartbeat: None,
+            timeline: false,
+        };
+        let mut decoder = Decoder::new(&options);
+        // 两个 slot 的交易交替到达, 每个 slot 只发出第一次
+        let slots: Vec<u64> = [10, 11, 10, 11, 12, 10]
+            .into_iter()
+            .flat_map(|slot| decoder.geyser(0, CompareMode::Slot, &slot_update(slot), Timestamp::now()))
+            .map(|event| match event {
+                SourceEvent::Arrival(arrival) => arrival.slot,
+                _ => panic!("按 slot 对比时只应发出到达事件"),
+            })
+            .collect();
+        assert_eq!(slots, vec![10, 11, 12]);
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+use yellowstone_grpc_proto::geyser::{
+    subscribe_request_filter_accounts_filter::Filter as AccountsFilter,
+    subscribe_request_filter_accounts_filter_memcmp::Data as MemcmpData, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter, SubscribeRequestFilterAccountsFilterMemcmp,
+    SubscribeRequestFilterBlocks, SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterEntry,
+    SubscribeRequestFilterSlots,
+};
+
+use crate::entries::DecodedTransaction;
+use crate::filter::{parse_pubkeys, FilterConfig, TransactionFilter};
+
+// 配置文件中的 [geyser] 表, 描述完整的 Geyser 订阅, 各类过滤条件都按名称给出
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubscriptionConfig {
+    // 命名的交易过滤条件; 不设置时, 若给出了 [filter] 表或命令行过滤参数, 或者没有订阅其他任何推送,
+    // 使用它们 (或默认的过滤条件) 作为唯一的交易过滤条件 "client"
+    #[serde(default)]
+    pub transactions: BTreeMap<String, FilterConfig>,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, AccountFilterConfig>,
+    #[serde(default)]
+    pub slots: BTreeMap<String, SlotFilterConfig>,
+    // entry 和 blocks_meta 没有过滤字段, 只需给出名称
+    #[serde(default)]
+    pub entry: Vec<String>,
+    #[serde(default)]
+    pub blocks_meta: Vec<String>,
+    #[serde(default)]
+    pub blocks: BTreeMap<String, BlockFilterConfig>,
+    // 从指定 slot 开始推送, 需要服务端保留了该 slot 之后的数据
+    pub from_slot: Option<u64>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountFilterConfig {
+    #[serde(default)]
+    pub account: Vec<String>,
+    #[serde(default)]
+    pub owner: Vec<String>,
+    // 账户数据长度
+    pub datasize: Option<u64>,
+    #[serde(default)]
+    pub memcmp: Vec<MemcmpConfig>,
+    pub nonempty_txn_signature: Option<bool>,
+}
+
+// 账户数据在 offset 处与 base58 编码的字节相同
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemcmpConfig {
+    pub offset: u64,
+    pub base58: String,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlotFilterConfig {
+    pub filter_by_commitment: Option<bool>,
+    pub interslot_updates: Option<bool>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockFilterConfig {
+    #[serde(default)]
+    pub account_include: Vec<String>,
+    pub include_transactions: Option<bool>,
+    pub include_accounts: Option<bool>,
+    pub include_entries: Option<bool>,
+}
+
+// 解析后的 Geyser 订阅, 所有数据源共用
+// 交易过滤条件同时用于 Shredstream 端的客户端过滤: 交易匹配任意一个命名过滤条件即保留
+#[derive(Clone)]
+pub struct Subscription {
+    pub commitment: CommitmentLevel,
+    pub transactions: Vec<(String, TransactionFilter)>,
+    pub accounts: HashMap<String, SubscribeRequestFilterAccounts>,
+    pub slots: HashMap<String, SubscribeRequestFilterSlots>,
+    pub entry: Vec<String>,
+    pub blocks_meta: Vec<String>,
+    pub blocks: HashMap<String, SubscribeRequestFilterBlocks>,
+    pub from_slot: Option<u64>,
+}
+
+// 默认只订阅交易, 排除投票交易和失败交易, processed 级别
+impl Default for Subscription {
+    fn default() -> Self {
+        Self::new(CommitmentLevel::Processed, TransactionFilter::default())
+    }
+}
+
+impl Subscription {
+    // 只有一个交易过滤条件 "client" 的订阅
+    pub fn new(commitment: CommitmentLevel, filter: TransactionFilter) -> Self {
+        Self {
+            commitment,
+            transactions: vec![("client".to_string(), filter)],
+            accounts: HashMap::new(),
+            slots: HashMap::new(),
+            entry: Vec::new(),
+            blocks_meta: Vec::new(),
+            blocks: HashMap::new(),
+            from_slot: None,
+        }
+    }
+
+    // filter 为 [filter] 表和命令行合并后的过滤条件, 两者都没有给出时为 None; config 中没有命名交易过滤条件时,
+    // 给出了 filter 则以它作为 "client", 否则只在 config 没有订阅其他任何推送时使用默认的 "client", 避免多订阅交易
+    pub fn from_config(
+        config: &SubscriptionConfig,
+        commitment: CommitmentLevel,
+        filter: Option<TransactionFilter>,
+    ) -> Result<Self, String> {
+        let other_updates = !config.accounts.is_empty()
+            || !config.slots.is_empty()
+            || !config.entry.is_empty()
+            || !config.blocks_meta.is_empty()
+            || !config.blocks.is_empty();
+        let mut subscription = Self::new(commitment, filter.clone().unwrap_or_default());
+        if filter.is_none() && other_updates {
+            subscription.transactions.clear();
+        }
+        if !config.transactions.is_empty() {
+            subscription.transactions = config
+                .transactions
+                .iter()
+                .map(|(name, filter)| {
+                    TransactionFilter::from_config(filter)
+                        .map(|filter| (name.clone(), filter))
+                        .map_err(|e| format!("交易过滤条件 {}: {}", name, e))
+                })
+                .collect::<Result<_, _>>()?;
+        }
+
+        for (name, account) in &config.accounts {
+            let strings = |keys: &[String]| -> Result<Vec<String>, String> {
+                parse_pubkeys(keys)
+                    .map(|keys| keys.iter().map(ToString::to_string).collect())
+                    .map_err(|e| format!("账户过滤条件 {}: {}", name, e))
+            };
+            let mut filters: Vec<SubscribeRequestFilterAccountsFilter> = account
+                .memcmp
+                .iter()
+                .map(|memcmp| SubscribeRequestFilterAccountsFilter {
+                    filter: Some(AccountsFilter::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
+                        offset: memcmp.offset,
+                        data: Some(MemcmpData::Base58(memcmp.base58.clone())),
+                    })),
+                })
+                .collect();
+            if let Some(datasize) = account.datasize {
+                filters.push(SubscribeRequestFilterAccountsFilter { filter: Some(AccountsFilter::Datasize(datasize)) });
+            }
+            subscription.accounts.insert(name.clone(), SubscribeRequestFilterAccounts {
+                account: strings(&account.account)?,
+                owner: strings(&account.owner)?,
+                filters,
+                nonempty_txn_signature: account.nonempty_txn_signature,
+            });
+        }
+
+        for (name, slots) in &config.slots {
+            subscription.slots.insert(name.clone(), SubscribeRequestFilterSlots {
+                filter_by_commitment: slots.filter_by_commitment,
+                interslot_updates: slots.interslot_updates,
+            });
+        }
+
+        for (name, blocks) in &config.blocks {
+            let account_include = parse_pubkeys(&blocks.account_include)
+                .map_err(|e| format!("区块过滤条件 {}: {}", name, e))?;
+            subscription.blocks.insert(name.clone(), SubscribeRequestFilterBlocks {
+                account_include: account_include.iter().map(ToString::to_string).collect(),
+                include_transactions: blocks.include_transactions,
+                include_accounts: blocks.include_accounts,
+                include_entries: blocks.include_entries,
+            });
+        }
+
+        subscription.entry = config.entry.clone();
+        subscription.blocks_meta = config.blocks_meta.clone();
+        subscription.from_slot = config.from_slot;
+        Ok(subscription)
+    }
+
+    pub fn to_request(&self) -> SubscribeRequest {
+        SubscribeRequest {
+            accounts: self.accounts.clone(),
+            slots: self.slots.clone(),
+            transactions: self
+                .transactions
+                .iter()
+                .map(|(name, filter)| (name.clone(), filter.to_proto()))
+                .collect(),
+            blocks: self.blocks.clone(),
+            blocks_meta: self.blocks_meta.iter().map(|name| (name.clone(), SubscribeRequestFilterBlocksMeta {})).collect(),
+            entry: self.entry.iter().map(|name| (name.clone(), SubscribeRequestFilterEntry {})).collect(),
+            commitment: Some(self.commitment.into()),
+            from_slot: self.from_slot,
+            ..Default::default()
+        }
+    }
+
+    // Shredstream 端的客户端过滤, 与 Geyser 一样匹配任意一个命名过滤条件即可
+    pub fn matches(&self, transaction: &DecodedTransaction) -> bool {
+        self.transactions.iter().any(|(_, filter)| filter.matches(transaction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAYDIUM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+
+    fn config(toml: &str) -> SubscriptionConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn default_request_subscribes_client_transactions() {
+        let request = Subscription::default().to_request();
+        assert_eq!(request.commitment, Some(CommitmentLevel::Processed as i32));
+        assert_eq!(request.transactions.keys().collect::<Vec<_>>(), ["client"]);
+        let client = &request.transactions["client"];
+        assert_eq!((client.vote, client.failed), (Some(false), Some(false)));
+        assert!(request.accounts.is_empty() && request.slots.is_empty() && request.entry.is_empty());
+        assert_eq!(request.from_slot, None);
+    }
+
+    #[test]
+    fn other_update_kinds_replace_the_default_filter() {
+        let config = config(&format!(
+            "entry = [\"entries\"]\nfrom_slot = 42\n[accounts.pool]\naccount = [\"{}\"]\ndatasize = 752\n[slots.slots]\nfilter_by_commitment = true\n",
+            RAYDIUM
+        ));
+        let request = Subscription::from_config(&config, CommitmentLevel::Confirmed, None).unwrap().to_request();
+        assert!(request.transactions.is_empty());
+        assert_eq!(request.commitment, Some(CommitmentLevel::Confirmed as i32));
+        assert_eq!(request.from_slot, Some(42));
+        assert_eq!(request.entry.keys().collect::<Vec<_>>(), ["entries"]);
+        assert_eq!(request.slots["slots"].filter_by_commitment, Some(true));
+        let pool = &request.accounts["pool"];
+        assert_eq!(pool.account, [RAYDIUM]);
+        assert!(matches!(pool.filters[..], [SubscribeRequestFilterAccountsFilter { filter: Some(AccountsFilter::Datasize(752)) }]));
+
+        // 明确给出的过滤条件仍然订阅
+        let filter = TransactionFilter { vote: None, ..Default::default() };
+        let request = Subscription::from_config(&config, CommitmentLevel::Confirmed, Some(filter)).unwrap().to_request();
+        assert_eq!(request.transactions["client"].vote, None);
+        // 没有其他推送时使用默认的过滤条件
+        let request = Subscription::from_config(&SubscriptionConfig::default(), CommitmentLevel::Processed, None)
+            .unwrap()
+            .to_request();
+        assert_eq!(request.transactions["client"].vote, Some(false));
+    }
+
+    #[test]
+    fn named_transaction_filters_replace_client() {
+        let named = config(&format!("[transactions.raydium]\nvote = true\naccount_required = [\"{}\"]\n[transactions.all]\n", RAYDIUM));
+        let filter = TransactionFilter { failed: None, ..Default::default() };
+        let request = Subscription::from_config(&named, CommitmentLevel::Processed, Some(filter)).unwrap().to_request();
+        let mut names: Vec<&str> = request.transactions.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["all", "raydium"]);
+        let raydium = &request.transactions["raydium"];
+        assert_eq!((raydium.vote, raydium.failed), (Some(true), Some(false)));
+        assert_eq!(raydium.account_required, [RAYDIUM]);
+
+        let error = Subscription::from_config(&config("[transactions.bad]\naccount_include = [\"x\"]\n"), CommitmentLevel::Processed, None);
+        assert!(error.err().unwrap().starts_with("交易过滤条件 bad"));
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use chrono::Local;
+use serde::Serialize;
+
+use crate::clock::ns_to_ms;
+use crate::compare::{Window, FINISH_GRACE_SLOTS};
+use crate::histogram::{LatencyHistogram, PERCENTILES};
+use crate::report::csv_field;
+use crate::slot_window::SlotWindow;
+use crate::source::BatchArrival;
+
+// 统计的填充比例 (%), 0 表示收到第一批
+const FILL_LEVELS: [u32; 4] = [0, 50, 90, 100];
+
+// 某个 slot 在各数据源收到的批次
+#[derive(Default)]
+struct SlotTimeline {
+    batches: Vec<Vec<BatchArrival>>,
+    // 有批次在预热期间或统计窗口之外到达, 整个 slot 不计入统计
+    excluded: bool,
+}
+
+struct SourceStats {
+    label: String,
+    batches: u64,
+    // 每个填充比例一个直方图, 自任一数据源收到该 slot 的第一批起计时
+    fill: Vec<LatencyHistogram>,
+}
+
+// 某个填充比例的耗时
+#[derive(Serialize)]
+pub struct FillSummary {
+    pub percent: u32,
+    // 达到该比例的 slot 数
+    pub reached: u64,
+    pub mean_ms: f64,
+    pub percentiles_ms: BTreeMap<String, f64>,
+}
+
+#[derive(Serialize)]
+pub struct SourceTimeline {
+    pub label: String,
+    // 平均每个 slot 收到的批次数
+    pub batches_per_slot: f64,
+    pub fill: Vec<FillSummary>,
+}
+
+#[derive(Serialize)]
+pub struct TimelineSummary {
+    // 计入统计的 slot 数
+    pub slots: u64,
+    pub sources: Vec<SourceTimeline>,
+}
+
+// slot 填充时间线: 记录每批内容的到达, slot 淘汰时计算各数据源收到 50%/90%/100% 交易的耗时
+// 交易总数取各数据源中收到最多的一个, 数据源首次收到的 slot 可能不完整, 不计入统计
+pub struct Timeline {
+    sources: Vec<SourceStats>,
+    slots: SlotWindow<SlotTimeline>,
+    // 每个数据源收到的第一个 slot
+    first_slots: Vec<Option<u64>>,
+    counted: u64,
+    recording: bool,
+    window: Window,
+    writer: Option<TimelineWriter>,
+}
+
+impl Timeline {
+    pub fn new(labels: Vec<String>, slot_depth: u64) -> Self {
+        let n = labels.len();
+        Self {
+            sources: labels
+                .into_iter()
+                .map(|label| SourceStats {
+                    label,
+                    batches: 0,
+                    fill: FILL_LEVELS.iter().map(|_| LatencyHistogram::new()).collect(),
+                })
+                .collect(),
+            slots: SlotWindow::new(slot_depth),
+            first_slots: vec![None; n],
+            counted: 0,
+            recording: true,
+            window: Window::default(),
+            writer: None,
+        }
+    }
+
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+    }
+
+    pub fn set_window(&mut self, window: Window) {
+        self.window = window;
+    }
+
+    pub fn set_writer(&mut self, writer: TimelineWriter) {
+        self.writer = Some(writer);
+    }
+
+    pub fn record(&mut self, batch: &BatchArrival) {
+        self.first_slots[batch.source].get_or_insert(batch.slot);
+        let evicted = self.slots.advance(batch.slot);
+        self.account_evicted(evicted);
+        let n = self.sources.len();
+        let Some(slot) = self.slots.get_or_insert(batch.slot) else {
+            return;
+        };
+        if !self.recording || !self.window.contains(batch.slot, batch.timestamp.wall_ns) {
+            slot.excluded = true;
+        }
+        slot.batches.resize_with(n, Vec::new);
+        slot.batches[batch.source].push(*batch);
+    }
+
+    // 运行结束时统计除最近几个 slot 以外的所有 slot
+    pub fn finish(&mut self) -> io::Result<()> {
+        let before = self.slots.tip().saturating_sub(FINISH_GRACE_SLOTS);
+        let evicted = self.slots.evict_before(before);
+        self.account_evicted(evicted);
+        match &mut self.writer {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn account_evicted(&mut self, evicted: Vec<(u64, SlotTimeline)>) {
+        for (slot, timeline) in evicted {
+            self.account_slot(slot, timeline);
+        }
+    }
+
+    fn account_slot(&mut self, slot: u64, timeline: SlotTimeline) {
+        let partial = self.first_slots.iter().flatten().any(|&first| slot <= first);
+        if timeline.excluded || partial {
+            return;
+        }
+        let total = timeline
+            .batches
+            .iter()
+            .map(|batches| batches.iter().map(|b| b.transactions).sum::<u64>())
+            .max()
+            .unwrap_or(0);
+        let Some(start) = timeline.batches.iter().flatten().map(|b| b.timestamp.mono_ns).min() else {
+            return;
+        };
+        // 没有匹配交易的 slot 无从计算填充比例
+        if total == 0 {
+            return;
+        }
+        self.counted += 1;
+
+        for (source, batches) in timeline.batches.iter().enumerate() {
+            let stats = &mut self.sources[source];
+            stats.batches += batches.len() as u64;
+            for (level, hist) in FILL_LEVELS.iter().zip(&mut stats.fill) {
+                if let Some(reached_ns) = fill_time(batches, total, *level) {
+                    hist.record_us(reached_ns.saturating_sub(start) / 1_000);
+                }
+            }
+            if let Some(writer) = &mut self.writer {
+                if let Err(e) = writer.write(slot, &stats.label, batches, start, total) {
+                    println!("[{}] WARN: 写入时间线文件失败: {}", Local::now().format("%H:%M:%S%.3f"), e);
+                }
+            }
+        }
+    }
+
+    pub fn summary(&self) -> TimelineSummary {
+        TimelineSummary {
+            slots: self.counted,
+            sources: self
+                .sources
+                .iter()
+                .map(|stats| SourceTimeline {
+                    label: stats.label.clone(),
+                    batches_per_slot: match self.counted {
+                        0 => 0.0,
+                        counted => stats.batches as f64 / counted as f64,
+                    },
+                    fill: FILL_LEVELS
+                        .iter()
+                        .zip(&stats.fill)
+                        .map(|(percent, hist)| FillSummary {
+                            percent: *percent,
+                            reached: hist.count(),
+                            mean_ms: hist.mean_ms(),
+                            percentiles_ms: PERCENTILES
+                                .iter()
+                                .map(|(name, q)| (name.to_string(), hist.quantile_ms(*q)))
+                                .collect(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn print_stats(&self) {
+        let width = self.sources.iter().map(|s| s.label.len()).max().unwrap_or(0).max(6);
+        println!("[{}] INFO: ===== slot 填充时间 ({} 个 slot, 自任一端点收到第一批起, 中位数/p90 ms) =====",
+            Local::now().format("%H:%M:%S%.3f"), self.counted);
+        for stats in &self.sources {
+            let levels: Vec<String> = FILL_LEVELS
+                .iter()
+                .zip(&stats.fill)
+                .map(|(percent, hist)| {
+                    let name = match percent {
+                        0 => "首批".to_string(),
+                        percent => format!("{}%", percent),
+                    };
+                    format!("{} {:.1}/{:.1}", name, hist.quantile_ms(0.5), hist.quantile_ms(0.9))
+                })
+                .collect();
+            let unfilled = self.counted - stats.fill.last().map_or(0, LatencyHistogram::count);
+            println!("[{}] INFO: {:<width$} : {}, 未收齐 {} 个 slot",
+                Local::now().format("%H:%M:%S%.3f"), stats.label, levels.join(", "), unfilled, width = width);
+        }
+    }
+}
+
+// 累计交易数达到 total 的 level% 的时间, 0% 为第一批的到达时间
+fn fill_time(batches: &[BatchArrival], total: u64, level: u32) -> Option<u64> {
+    if level == 0 {
+        return batches.first().map(|b| b.timestamp.mono_ns);
+    }
+    let target = (total * level as u64).div_ceil(100);
+    let mut received = 0;
+    batches.iter().find_map(|b| {
+        received += b.transactions;
+        (received >= target).then_some(b.timestamp.mono_ns)
+    })
+}
+
+// 时间线 CSV: 每个计入统计的 slot 在各数据源收到的每批内容一行
+// 表头: slot, source, index (消息或交易序号), entries, transactions, recv_ts (Unix 毫秒, 精确到纳秒),
+// offset (自任一端点收到该 slot 第一批起的毫秒数), filled (累计收到的交易占总数的百分比)
+// source 含逗号、引号或换行时按 CSV 规则加引号
+pub struct TimelineWriter {
+    writer: BufWriter<File>,
+}
+
+impl TimelineWriter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "slot,source,index,entries,transactions,recv_ts,offset,filled")?;
+        Ok(Self { writer })
+    }
+
+    fn write(&mut self, slot: u64, source: &str, batches: &[BatchArrival], start_ns: u64, total: u64) -> io::Result<()> {
+        let mut received = 0;
+        for batch in batches {
+            received += batch.transactions;
+            writeln!(
+                self.writer,
+                "{},{},{},{},{},{}.{:06},{:.6},{:.2}",
+                slot,
+                csv_field(source),
+                batch.index,
+                batch.entries,
+                batch.transactions,
+                batch.timestamp.wall_ns / 1_000_000,
+                batch.timestamp.wall_ns % 1_000_000,
+                ns_to_ms(batch.timestamp.mono_ns.saturating_sub(start_ns)),
+                received as f64 / total as f64 * 100.0
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::Timestamp;
+
+    use super::*;
+
+    fn batch(source: usize, slot: u64, index: u64, transactions: u64, ms: u64) -> BatchArrival {
+        let timestamp = Timestamp { mono_ns: ms * 1_000_000, wall_ns: 1_700_000_000_000_000_000 + ms * 1_000_123 };
+        BatchArrival { source, slot, index, entries: 1, transactions, timestamp }
+    }
+
+    #[test]
+    fn writer_lists_each_batch_with_offset_and_fill() {
+        let path = std::env::temp_dir().join(format!("timeline-{}.csv", std::process::id()));
+        let mut timeline = Timeline::new(vec!["shred".to_string(), "grpc, \"ny\"".to_string()], 10);
+        timeline.set_writer(TimelineWriter::create(&path).unwrap());
+        // 第一个 slot 不完整, 不计入也不写出
+        timeline.record(&batch(0, 1, 0, 1, 0));
+        timeline.record(&batch(1, 1, 0, 1, 1));
+        // slot 2: shred 分两批收到 4 笔, grpc 逐笔收到其中 3 笔
+        timeline.record(&batch(0, 2, 0, 1, 400));
+        timeline.record(&batch(0, 2, 1, 3, 402));
+        for (index, ms) in [(0, 401), (1, 405), (2, 410)] {
+            timeline.record(&batch(1, 2, index, 1, ms));
+        }
+        timeline.record(&batch(0, 20, 0, 1, 8000));
+        timeline.finish().unwrap();
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines, [
+            "slot,source,index,entries,transactions,recv_ts,offset,filled",
+            "2,shred,0,1,1,1700000000400.049200,0.000000,25.00",
+            "2,shred,1,1,3,1700000000402.049446,2.000000,100.00",
+            "2,\"grpc, \"\"ny\"\"\",0,1,1,1700000000401.049323,1.000000,25.00",
+            "2,\"grpc, \"\"ny\"\"\",1,1,1,1700000000405.049815,5.000000,50.00",
+            "2,\"grpc, \"\"ny\"\"\",2,1,1,1700000000410.050430,10.000000,75.00",
+        ]);
+
+        let summary = timeline.summary();
+        assert_eq!(summary.slots, 1);
+        let grpc = &summary.sources[1];
+        assert_eq!(grpc.batches_per_slot, 3.0);
+        // grpc 没有收齐, 100% 未达到
+        let reached: Vec<u64> = grpc.fill.iter().map(|fill| fill.reached).collect();
+        assert_eq!(reached, [1, 1, 0, 0]);
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Local;
+use serde::Serialize;
+use solana_sdk::signature::Signature;
+
+use crate::compare::Window;
+use crate::slot_window::SlotWindow;
+use crate::source::{Arrival, MatchKey};
+
+// 一个 slot 内两端收到的签名和交易在 slot 内的位置, Geyser 端同时记录执行失败的交易
+#[derive(Default)]
+struct SlotSets {
+    grpc: HashMap<Signature, Option<u64>>,
+    failed: HashSet<Signature>,
+    shred: HashMap<Signature, Option<u64>>,
+    // 最先收到该 slot 数据的系统时间, 用于判断统计窗口
+    first_wall: Option<u64>,
+    // 该 slot 开始时是否在统计, 预热期间开始的 slot 不计入
+    recording: bool,
+}
+
+// 单个 slot 的核对结果
+#[derive(Clone, Serialize)]
+pub struct SlotRecall {
+    pub slot: u64,
+    pub both: u64,
+    pub grpc_only: u64,
+    pub shred_only: u64,
+    // Geyser 端执行失败的交易, Shred 端无法判断是否失败, 不计入以上三项和召回率
+    pub failed: u64,
+    // both / (both + grpc_only), Geyser 端没有交易时为 None
+    pub recall: Option<f64>,
+    // Geyser 端有而 Shred 端没有的交易全部位于 Shred 端收到的最后一笔交易之后, 即 entries 在 slot 末尾被截断
+    pub truncated: bool,
+}
+
+#[derive(Serialize)]
+pub struct VerifySummary {
+    pub slots: u64,
+    pub both: u64,
+    pub grpc_only: u64,
+    pub shred_only: u64,
+    pub failed: u64,
+    pub recall: Option<f64>,
+    // 有 Geyser 端交易未被 Shred 端收到的 slot 数
+    pub incomplete_slots: u64,
+    pub truncated_slots: Vec<u64>,
+}
+
+// 完整的核对结果, 用于 --output json 和 --report
+#[derive(Serialize)]
+pub struct VerifyReport {
+    pub grpc: String,
+    pub shred: String,
+    #[serde(flatten)]
+    pub summary: VerifySummary,
+    pub slot_results: Vec<SlotRecall>,
+}
+
+// 逐 slot 核对 Shredstream 是否送达了 Geyser 推送的每一笔交易
+// slot 落后于最新 slot 超过 slot_depth 后视为两端都已送达完毕, 此时计算该 slot 的结果
+pub struct Verifier {
+    grpc: usize,
+    shred: usize,
+    seen: SlotWindow<SlotSets>,
+    // 两端收到的第一个 slot, 订阅开始时正在进行的 slot 两端都可能不完整, 只核对之后的 slot
+    first_slots: [Option<u64>; 2],
+    recording: bool,
+    window: Window,
+    results: Vec<SlotRecall>,
+}
+
+impl Verifier {
+    // grpc, shred: 两端的数据源序号
+    pub fn new(grpc: usize, shred: usize, slot_depth: u64) -> Self {
+        Self {
+            grpc,
+            shred,
+            seen: SlotWindow::new(slot_depth),
+            first_slots: [None, None],
+            recording: true,
+            window: Window::default(),
+            results: Vec::new(),
+        }
+    }
+
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+    }
+
+    pub fn set_window(&mut self, window: Window) {
+        self.window = window;
+    }
+
+    // 记录一笔交易的到达, 返回因此完成核对的 slot
+    pub fn record(&mut self, arrival: &Arrival) -> Vec<SlotRecall> {
+        let MatchKey::Signature(signature) = arrival.key else {
+            return Vec::new();
+        };
+        let side = match arrival.source {
+            source if source == self.grpc => 0,
+            source if source == self.shred => 1,
+            _ => return Vec::new(),
+        };
+        self.first_slots[side].get_or_insert(arrival.slot);
+
+        let evicted = self.seen.advance(arrival.slot);
+        let finished = self.account(evicted);
+        let recording = self.recording;
+        if let Some(sets) = self.seen.get_or_insert(arrival.slot) {
+            if sets.first_wall.is_none() {
+                sets.recording = recording;
+            }
+            let wall_ns = arrival.timestamp.wall_ns;
+            sets.first_wall = Some(sets.first_wall.map_or(wall_ns, |first| first.min(wall_ns)));
+            if side == 0 {
+                sets.grpc.entry(signature).or_insert(arrival.position);
+                if arrival.failed == Some(true) {
+                    sets.failed.insert(signature);
+                }
+            } else {
+                sets.shred.entry(signature).or_insert(arrival.position);
+            }
+        }
+        finished
+    }
+
+    // 运行结束时核对除最近几个 slot 以外的所有 slot
+    pub fn finish(&mut self, grace_slots: u64) -> Vec<SlotRecall> {
+        let before = self.seen.tip().saturating_sub(grace_slots);
+        let evicted = self.seen.evict_before(before);
+        self.account(evicted)
+    }
+
+    fn account(&mut self, evicted: Vec<(u64, SlotSets)>) -> Vec<SlotRecall> {
+        let [Some(first_grpc), Some(first_shred)] = self.first_slots else {
+            return Vec::new();
+        };
+        let mut finished = Vec::new();
+        for (slot, sets) in evicted {
+            if slot <= first_grpc.max(first_shred) || !sets.recording {
+                continue;
+            }
+            if !sets.first_wall.is_some_and(|wall| self.window.contains(slot, wall)) {
+                continue;
+            }
+            let result = check_slot(slot, &sets);
+            self.results.push(result.clone());
+            finished.push(result);
+        }
+        finished
+    }
+
+    pub fn summary(&self) -> VerifySummary {
+        let both: u64 = self.results.iter().map(|r| r.both).sum();
+        let grpc_only: u64 = self.results.iter().map(|r| r.grpc_only).sum();
+        VerifySummary {
+            slots: self.results.len() as u64,
+            both,
+            grpc_only,
+            shred_only: self.results.iter().map(|r| r.shred_only).sum(),
+            failed: self.results.iter().map(|r| r.failed).sum(),
+            recall: recall(both, grpc_only),
+            incomplete_slots: self.results.iter().filter(|r| r.grpc_only > 0).count() as u64,
+            truncated_slots: self.results.iter().filter(|r| r.truncated).map(|r| r.slot).collect(),
+        }
+    }
+
+    pub fn report(&self, grpc: &str, shred: &str) -> VerifyReport {
+        VerifyReport {
+            grpc: grpc.to_string(),
+            shred: shred.to_string(),
+            summary: self.summary(),
+            slot_results: self.results.clone(),
+        }
+    }
+
+    pub fn print_stats(&self, grpc: &str, shred: &str) {
+        let summary = self.summary();
+        println!("[{}] INFO: ===== 送达完整性核对 ({} 个 slot) =====",
+            Local::now().format("%H:%M:%S%.3f"), summary.slots);
+        println!("[{}] INFO: 两端都收到 {} 笔, 仅 {} 收到 {} 笔, 仅 {} 收到 {} 笔, 召回率 {}",
+            Local::now().format("%H:%M:%S%.3f"),
+            summary.both,
+            grpc,
+            summary.grpc_only,
+            shred,
+            summary.shred_only,
+            format_recall(summary.recall)
+        );
+        println!("[{}] INFO: {} 报告执行失败的交易 {} 笔, 不计入召回率",
+            Local::now().format("%H:%M:%S%.3f"), grpc, summary.failed);
+        println!("[{}] INFO: 有交易未被 {} 收到的 slot: {} 个",
+            Local::now().format("%H:%M:%S%.3f"), shred, summary.incomplete_slots);
+        println!("[{}] INFO: 疑似截断的 slot: {} 个 {:?}",
+            Local::now().format("%H:%M:%S%.3f"), summary.truncated_slots.len(), summary.truncated_slots);
+    }
+}
+
+// Shred 端收到的最后位置取两种估计中较大的一个, 两者都只会偏小:
+// Shred 端自己解码出的位置 (包括 Geyser 没有推送的交易), 中途丢消息时后面的位置整体偏小;
+// 两端都收到的交易在 Geyser 端的位置, Shred 端最后几笔若 Geyser 没有推送则无从得知
+// 失败交易只用于确定 Shred 端收到的最后位置, 不计入两端的交易数
+fn check_slot(slot: u64, sets: &SlotSets) -> SlotRecall {
+    let (delivered, missing): (Vec<_>, Vec<_>) = sets
+        .grpc
+        .iter()
+        .partition(|(signature, _)| sets.shred.contains_key(*signature));
+    let last_shred = delivered
+        .iter()
+        .filter_map(|(_, position)| **position)
+        .chain(sets.shred.values().filter_map(|position| *position))
+        .max();
+    let succeeded = |(signature, _): &(&Signature, &Option<u64>)| !sets.failed.contains(*signature);
+    let both = delivered.iter().filter(|pair| succeeded(pair)).count() as u64;
+    let missing: Vec<Option<u64>> =
+        missing.into_iter().filter(|pair| succeeded(pair)).map(|(_, position)| *position).collect();
+    let truncated = !missing.is_empty()
+        && last_shred.is_some_and(|last| missing.iter().all(|position| position.is_some_and(|p| p > last)));
+    SlotRecall {
+        slot,
+        both,
+        grpc_only: missing.len() as u64,
+        shred_only: sets.shred.len() as u64 - delivered.len() as u64,
+        failed: sets.failed.len() as u64,
+        recall: recall(both, missing.len() as u64),
+        truncated,
+    }
+}
+
+fn recall(both: u64, grpc_only: u64) -> Option<f64> {
+    let total = both + grpc_only;
+    (total > 0).then(|| both as f64 / total as f64)
+}
+
+pub fn format_recall(recall: Option<f64>) -> String {
+    match recall {
+        Some(recall) => format!("{:.2}%", recall * 100.0),
+        None => "-".to_string(),
+    }
+}
@@ -0,0 +1,190 @@
+// 鉴权流程: 挑战 -> 签名换取令牌 -> 刷新访问令牌, 对照模拟鉴权服务
+mod common;
+
+use std::sync::Arc;
+
+use jito_protos::auth::auth_service_client::AuthServiceClient;
+use jito_protos::auth::{
+    GenerateAuthChallengeRequest, GenerateAuthTokensRequest, GenerateAuthTokensResponse, RefreshAccessTokenRequest,
+    Role,
+};
+use jito_protos::tonic::metadata::MetadataMap;
+use jito_protos::tonic::service::Interceptor;
+use jito_protos::tonic::transport::Channel;
+use jito_protos::tonic::{Code, Request};
+use shred_vs_grpc::auth::{AuthConfig, Authenticator, BearerInterceptor};
+use shred_vs_grpc::mock::auth::{self, MockAuthState};
+use solana_sdk::signature::{Keypair, Signer};
+use tokio::time::{sleep, timeout, Duration};
+
+const WAIT: Duration = Duration::from_secs(10);
+
+// 启动模拟鉴权服务, 返回地址和令牌状态
+fn start(access_ttl: Duration, refresh_ttl: Duration) -> (String, Arc<MockAuthState>) {
+    let addr = common::free_tcp_addr();
+    let state = MockAuthState::new(access_ttl, refresh_ttl);
+    tokio::spawn(auth::serve(addr, state.clone()));
+    (format!("http://{}", addr), state)
+}
+
+async fn connect(url: &str) -> AuthServiceClient<Channel> {
+    for _ in 0..50 {
+        if let Ok(client) = AuthServiceClient::connect(url.to_string()).await {
+            return client;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    panic!("模拟鉴权服务未启动");
+}
+
+async fn challenge(client: &mut AuthServiceClient<Channel>, keypair: &Keypair) -> String {
+    let request = GenerateAuthChallengeRequest {
+        role: Role::ShredstreamSubscriber as i32,
+        pubkey: keypair.pubkey().to_bytes().to_vec(),
+    };
+    let challenge = client.generate_auth_challenge(request).await.unwrap().into_inner().challenge;
+    format!("{}-{}", keypair.pubkey(), challenge)
+}
+
+async fn tokens(
+    client: &mut AuthServiceClient<Channel>,
+    keypair: &Keypair,
+    challenge: &str,
+) -> Result<GenerateAuthTokensResponse, Code> {
+    let request = GenerateAuthTokensRequest {
+        challenge: challenge.to_string(),
+        client_pubkey: keypair.pubkey().to_bytes().to_vec(),
+        signed_challenge: keypair.sign_message(challenge.as_bytes()).as_ref().to_vec(),
+    };
+    client.generate_auth_tokens(request).await.map(|response| response.into_inner()).map_err(|status| status.code())
+}
+
+// 拦截器当前携带的 authorization metadata
+fn bearer(interceptor: &mut BearerInterceptor) -> MetadataMap {
+    interceptor.call(Request::new(())).unwrap().metadata().clone()
+}
+
+fn token_value(metadata: &MetadataMap) -> String {
+    metadata.get("authorization").unwrap().to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn signed_challenge_yields_tokens_and_refresh() {
+    let (url, state) = start(Duration::from_secs(60), Duration::from_secs(120));
+    let mut client = connect(&url).await;
+    let keypair = Keypair::new();
+
+    let challenge = challenge(&mut client, &keypair).await;
+    let response = tokens(&mut client, &keypair, &challenge).await.unwrap();
+    let access = response.access_token.unwrap();
+    let refresh = response.refresh_token.unwrap();
+    assert!(access.expires_at_utc.unwrap().seconds < refresh.expires_at_utc.unwrap().seconds);
+
+    let mut metadata = MetadataMap::new();
+    metadata.insert("authorization", format!("Bearer {}", access.value).parse().unwrap());
+    assert_eq!(state.check(&metadata, Role::ShredstreamSubscriber), Ok(()));
+
+    // 挑战只能使用一次
+    assert_eq!(tokens(&mut client, &keypair, &challenge).await.unwrap_err(), Code::PermissionDenied);
+
+    // 用刷新令牌换取新的访问令牌, 新旧令牌都有效
+    let refreshed = client
+        .refresh_access_token(RefreshAccessTokenRequest { refresh_token: refresh.value })
+        .await
+        .unwrap()
+        .into_inner()
+        .access_token
+        .unwrap();
+    assert_ne!(refreshed.value, access.value);
+    metadata.insert("authorization", format!("Bearer {}", refreshed.value).parse().unwrap());
+    assert_eq!(state.check(&metadata, Role::ShredstreamSubscriber), Ok(()));
+}
+
+#[tokio::test]
+async fn rejects_wrong_signature_and_unknown_tokens() {
+    let (url, state) = start(Duration::from_secs(60), Duration::from_secs(120));
+    let mut client = connect(&url).await;
+    let keypair = Keypair::new();
+
+    // 用其他密钥对签名
+    let challenge = challenge(&mut client, &keypair).await;
+    let request = GenerateAuthTokensRequest {
+        challenge: challenge.clone(),
+        client_pubkey: keypair.pubkey().to_bytes().to_vec(),
+        signed_challenge: Keypair::new().sign_message(challenge.as_bytes()).as_ref().to_vec(),
+    };
+    assert_eq!(client.generate_auth_tokens(request).await.unwrap_err().code(), Code::PermissionDenied);
+
+    let status = client
+        .refresh_access_token(RefreshAccessTokenRequest { refresh_token: "unknown".to_string() })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    assert_eq!(state.check(&MetadataMap::new(), Role::ShredstreamSubscriber), Err("缺少访问令牌"));
+    let mut metadata = MetadataMap::new();
+    metadata.insert("authorization", "Bearer unknown".parse().unwrap());
+    assert_eq!(state.check(&metadata, Role::ShredstreamSubscriber), Err("无效的访问令牌"));
+}
+
+#[tokio::test]
+async fn expired_tokens_are_rejected() {
+    let (url, state) = start(Duration::from_millis(200), Duration::from_millis(300));
+    let mut client = connect(&url).await;
+    let keypair = Keypair::new();
+
+    let challenge = challenge(&mut client, &keypair).await;
+    let response = tokens(&mut client, &keypair, &challenge).await.unwrap();
+    sleep(Duration::from_millis(400)).await;
+
+    let mut metadata = MetadataMap::new();
+    metadata.insert("authorization", format!("Bearer {}", response.access_token.unwrap().value).parse().unwrap());
+    assert_eq!(state.check(&metadata, Role::ShredstreamSubscriber), Err("访问令牌已过期"));
+
+    let status = client
+        .refresh_access_token(RefreshAccessTokenRequest { refresh_token: response.refresh_token.unwrap().value })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_eq!(status.message(), "刷新令牌已过期");
+}
+
+#[tokio::test]
+async fn authenticator_refreshes_before_expiry() {
+    // 访问令牌 1 秒后过期, 剩余一半时刷新
+    let (url, state) = start(Duration::from_secs(1), Duration::from_secs(60));
+    let authenticator = Authenticator::spawn(AuthConfig { url, keypair: Arc::new(Keypair::new()), role: Role::ShredstreamSubscriber });
+    timeout(WAIT, authenticator.ready()).await.expect("等待鉴权超时").unwrap();
+
+    let mut interceptor = authenticator.interceptor();
+    let first = bearer(&mut interceptor);
+    assert_eq!(state.check(&first, Role::ShredstreamSubscriber), Ok(()));
+
+    // 每个访问令牌在过期前都已被替换, 拦截器始终携带有效令牌
+    let mut seen = vec![token_value(&first)];
+    for _ in 0..15 {
+        sleep(Duration::from_millis(200)).await;
+        let metadata = bearer(&mut interceptor);
+        assert_eq!(state.check(&metadata, Role::ShredstreamSubscriber), Ok(()));
+        let value = token_value(&metadata);
+        if seen.last() != Some(&value) {
+            seen.push(value);
+        }
+    }
+    assert!(seen.len() >= 3, "3 秒内应当至少刷新两次, 实际令牌: {:?}", seen);
+}
+
+#[tokio::test]
+async fn authenticator_reauthenticates_when_refresh_token_expires_first() {
+    // 刷新令牌比访问令牌先过期, 不能刷新, 只能重新走挑战流程
+    let (url, state) = start(Duration::from_secs(1), Duration::from_millis(500));
+    let authenticator = Authenticator::spawn(AuthConfig { url, keypair: Arc::new(Keypair::new()), role: Role::ShredstreamSubscriber });
+    timeout(WAIT, authenticator.ready()).await.expect("等待鉴权超时").unwrap();
+
+    let mut interceptor = authenticator.interceptor();
+    let first = token_value(&bearer(&mut interceptor));
+    sleep(Duration::from_millis(800)).await;
+    let metadata = bearer(&mut interceptor);
+    assert_ne!(token_value(&metadata), first);
+    assert_eq!(state.check(&metadata, Role::ShredstreamSubscriber), Ok(()));
+}
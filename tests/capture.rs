@@ -0,0 +1,133 @@
+// 抓包文件: 只接受当前格式; 写入的帧经回放还原出与实时解码相同的事件, 包括连接建立和断开
+mod common;
+
+use std::sync::Arc;
+
+use shred_vs_grpc::capture::{self, CaptureReader, CaptureWriter, FrameKind};
+use shred_vs_grpc::clock::Timestamp;
+use shred_vs_grpc::source::{CompareMode, Decoder, Source, SourceEvent, SourceKind, StreamOptions};
+use shred_vs_grpc::subscription::Subscription;
+use solana_entry::entry::next_entry_mut;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::system_transaction;
+use tokio::sync::mpsc;
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::geyser::{SubscribeUpdate, SubscribeUpdateTransaction};
+use jito_protos::prost::Message as _;
+use yellowstone_grpc_proto::prost::Message as _;
+
+fn options() -> StreamOptions {
+    StreamOptions {
+        mode: CompareMode::Slot,
+        slot_depth: 300,
+        subscription: Arc::new(Subscription::default()),
+        auth: None,
+        heartbeat: None,
+        timeline: false,
+    }
+}
+
+fn timestamp(ms: u64) -> Timestamp {
+    Timestamp { mono_ns: ms * 1_000_000, wall_ns: 1_700_000_000_000_000_000 + ms * 1_000_000 }
+}
+
+fn geyser_update(slot: u64) -> Vec<u8> {
+    let transaction = SubscribeUpdateTransaction { slot, ..Default::default() };
+    SubscribeUpdate { update_oneof: Some(UpdateOneof::Transaction(transaction)), ..Default::default() }.encode_to_vec()
+}
+
+// 含一笔转账交易的 Shredstream Entry 消息
+fn shredstream_entry(slot: u64, hash: &mut Hash) -> Vec<u8> {
+    let transaction = system_transaction::transfer(&Keypair::new(), &Pubkey::new_unique(), 1, *hash);
+    let entries = vec![next_entry_mut(hash, 1, vec![transaction])];
+    jito_protos::shredstream::Entry { slot, entries: bincode::serialize(&entries).unwrap() }.encode_to_vec()
+}
+
+// 便于比较的事件描述
+fn describe(event: &SourceEvent) -> String {
+    match event {
+        SourceEvent::Arrival(arrival) => {
+            format!("arrival {} {} {} {}", arrival.source, arrival.key, arrival.slot, arrival.timestamp.mono_ns)
+        }
+        SourceEvent::Batch(batch) => format!("batch {} {} {}", batch.source, batch.slot, batch.timestamp.mono_ns),
+        SourceEvent::Connected(source, ts) => format!("connected {} {}", source, ts.mono_ns),
+        SourceEvent::Disconnected(source, ts) => format!("disconnected {} {}", source, ts.mono_ns),
+    }
+}
+
+#[test]
+fn older_capture_formats_are_rejected() {
+    let path = common::temp_path("capture-old.cap");
+    let header = br#"{"sources":[{"label":"raw","kind":"shreds","url":"0.0.0.0:20000"}]}"#;
+    for magic in [b"SVGCAP01", b"SVGCAP02", b"SVGCAP03"] {
+        let mut file = magic.to_vec();
+        file.extend((header.len() as u32).to_le_bytes());
+        file.extend(header);
+        std::fs::write(&path, file).unwrap();
+
+        let error = CaptureReader::open(&path).err().expect("不应接受旧版抓包");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(error.to_string().ends_with("不是抓包文件"), "{}", error);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn replay_reproduces_captured_events() {
+    let grpc = Source { label: "grpc".to_string(), kind: SourceKind::Geyser, url: "http://127.0.0.1:10000".to_string() };
+    let shred = Source { label: "shred".to_string(), kind: SourceKind::Shredstream, url: "http://127.0.0.1:9999".to_string() };
+    let paths = [common::temp_path("capture-grpc.cap"), common::temp_path("capture-shred.cap")];
+    let writers = [CaptureWriter::create(&paths[0], &[grpc]).unwrap(), CaptureWriter::create(&paths[1], &[shred]).unwrap()];
+
+    // 两个文件各一个数据源, 回放时按接收时间合并; Shredstream 端中途断线重连
+    let mut hash = Hash::new_unique();
+    let frames: Vec<(usize, FrameKind, u64, Vec<u8>)> = vec![
+        (0, FrameKind::Connected, 1, Vec::new()),
+        (1, FrameKind::Connected, 2, Vec::new()),
+        (1, FrameKind::Data, 10, shredstream_entry(100, &mut hash)),
+        (0, FrameKind::Data, 12, geyser_update(100)),
+        (1, FrameKind::Disconnected, 15, Vec::new()),
+        (0, FrameKind::Data, 20, geyser_update(101)),
+        (1, FrameKind::Connected, 25, Vec::new()),
+        (1, FrameKind::Data, 30, shredstream_entry(102, &mut hash)),
+        (0, FrameKind::Data, 31, geyser_update(102)),
+    ];
+    for (file, kind, ms, payload) in &frames {
+        match kind {
+            FrameKind::Data => writers[*file].write(0, timestamp(*ms), payload).unwrap(),
+            kind => writers[*file].write_event(0, *kind, timestamp(*ms)).unwrap(),
+        }
+    }
+    writers.iter().for_each(|writer| writer.flush().unwrap());
+
+    // 直接解码同样的帧得到的事件
+    let options = options();
+    let mut decoders = [Decoder::new(&options), Decoder::new(&options)];
+    let kinds = [SourceKind::Geyser, SourceKind::Shredstream];
+    let expected: Vec<String> = frames
+        .iter()
+        .flat_map(|(index, kind, ms, payload)| {
+            let frame = capture::Frame { source: 0, kind: *kind, timestamp: timestamp(*ms), payload: payload.clone() };
+            capture::decode_frame(&mut decoders[*index], *index, kinds[*index], CompareMode::Slot, &frame)
+        })
+        .map(|event| describe(&event))
+        .collect();
+    assert_eq!(expected.iter().filter(|event| event.starts_with("arrival")).count(), 5);
+
+    let (tx, mut rx) = mpsc::channel(64);
+    let sources = capture::spawn_replay(&paths, &options, tx).unwrap();
+    assert_eq!(sources.iter().map(|s| s.label.as_str()).collect::<Vec<_>>(), ["grpc", "shred"]);
+    assert!(sources[0].kind == kinds[0] && sources[1].kind == kinds[1]);
+    let mut replayed = Vec::new();
+    while let Some(event) = rx.recv().await {
+        replayed.push(describe(&event));
+    }
+    paths.iter().for_each(|path| std::fs::remove_file(path).unwrap());
+
+    assert_eq!(replayed, expected);
+    assert_eq!(replayed[..2], ["connected 0 1000000", "connected 1 2000000"]);
+    assert!(replayed.contains(&"disconnected 1 15000000".to_string()));
+    assert!(replayed.contains(&"connected 1 25000000".to_string()));
+}
@@ -0,0 +1,47 @@
+// 集成测试共用的辅助函数, 各测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use std::net::{SocketAddr, TcpListener, UdpSocket};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+// 由系统分配一个空闲的本地 TCP 端口
+pub fn free_tcp_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+// 由系统分配一个空闲的本地 UDP 端口
+pub fn free_udp_addr() -> SocketAddr {
+    UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+// 测试用的临时文件路径, 按测试名和进程号区分
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("shred-vs-grpc-{}-{}", std::process::id(), name))
+}
+
+pub fn binary() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_shred-vs-grpc"))
+}
+
+// 后台运行的子进程, 测试结束时结束进程
+pub struct Background(Child);
+
+impl Background {
+    pub fn spawn(args: &[&str]) -> Self {
+        let child = binary()
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("无法启动测试进程");
+        Self(child)
+    }
+}
+
+impl Drop for Background {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
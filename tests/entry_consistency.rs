@@ -0,0 +1,58 @@
+// 按 entry 序号对比时的一致性核对: 丢消息造成的序号错位不计为哈希不一致
+use shred_vs_grpc::clock::Timestamp;
+use shred_vs_grpc::compare::Comparator;
+use shred_vs_grpc::source::{Arrival, EntryInfo, MatchKey};
+use solana_sdk::hash::{hash, Hash};
+
+const GRPC: usize = 0;
+const SHRED: usize = 1;
+
+fn entry_hash(slot: u64, index: u64) -> Hash {
+    hash(&[slot.to_le_bytes(), index.to_le_bytes()].concat())
+}
+
+fn record(comparator: &mut Comparator, source: usize, slot: u64, entry: EntryInfo, ms: u64) {
+    let timestamp = Timestamp { mono_ns: ms * 1_000_000, wall_ns: ms * 1_000_000 };
+    comparator.record(Arrival {
+        source,
+        key: MatchKey::Entry(slot, entry.index),
+        slot,
+        timestamp,
+        entry: Some(entry),
+        position: None,
+        failed: None,
+    });
+}
+
+#[test]
+fn dropped_message_counts_as_index_drift() {
+    let mut comparator = Comparator::new(vec!["grpc".to_string(), "shred".to_string()], 300);
+    let entry = |index, hash| EntryInfo { index, hash, transactions: 4 };
+
+    // slot 10 有 5 个 entry, Shredstream 丢了第 2 个, 之后的序号都少 1
+    for index in 0..5 {
+        record(&mut comparator, GRPC, 10, entry(index, entry_hash(10, index)), index);
+    }
+    for (position, index) in [0, 1, 3, 4].into_iter().enumerate() {
+        record(&mut comparator, SHRED, 10, entry(position as u64, entry_hash(10, index)), 10 + index);
+    }
+
+    // slot 11 的第 0 个 entry 内容确实不同
+    record(&mut comparator, GRPC, 11, entry(0, entry_hash(11, 0)), 400);
+    record(&mut comparator, SHRED, 11, EntryInfo { index: 0, hash: Hash::new_unique(), transactions: 3 }, 410);
+
+    // 推进到后面的 slot, 使 slot 10 和 11 在结束时被淘汰并核对
+    record(&mut comparator, GRPC, 20, entry(0, entry_hash(20, 0)), 4000);
+    comparator.finish();
+
+    let summary = comparator.summary();
+    let grpc = &summary.sources[GRPC];
+    let shred = &summary.sources[SHRED];
+    assert_eq!(grpc.entries_checked, 0);
+    assert_eq!(shred.entries_checked, 5);
+    assert_eq!(shred.index_drifts, 2);
+    assert_eq!(shred.hash_mismatches, 1);
+    assert_eq!(shred.tx_count_mismatches, 1);
+    // 序号 4 只有 Geyser 收到
+    assert_eq!(shred.missed, 1);
+}
@@ -0,0 +1,110 @@
+// Geyser 客户端的连接行为: 由测试控制的模拟 Geyser发送 Ping 并主动结束数据流, 以及推送在解码前打时间戳
+mod common;
+
+use shred_vs_grpc::clock::Timestamp;
+use shred_vs_grpc::geyser;
+use shred_vs_grpc::mock::geyser::{serve_mock, MockGeyser, ScriptedSession};
+use shred_vs_grpc::source::{spawn_sources, CompareMode, Source, SourceEvent, SourceKind, StreamOptions};
+use shred_vs_grpc::subscription::Subscription;
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::geyser::SubscribeRequest;
+use yellowstone_grpc_proto::prost::Message as _;
+
+const WAIT: Duration = Duration::from_secs(10);
+
+// 启动把每次订阅交给测试处理的模拟 Geyser, 返回地址和每次订阅
+fn serve() -> (String, mpsc::UnboundedReceiver<ScriptedSession>) {
+    let addr = common::free_tcp_addr();
+    let (geyser, sessions) = MockGeyser::scripted();
+    tokio::spawn(serve_mock(addr, geyser));
+    (format!("http://{}", addr), sessions)
+}
+
+async fn next_request(session: &mut ScriptedSession) -> SubscribeRequest {
+    timeout(WAIT, session.next_request()).await.expect("等待客户端请求超时").expect("客户端请求流已结束")
+}
+
+async fn ping(session: &ScriptedSession) {
+    assert!(session.ping().await, "客户端已断开");
+}
+
+// 启动服务端和连接它的 Geyser 数据源, 返回每次订阅和数据源事件
+fn start() -> (mpsc::UnboundedReceiver<ScriptedSession>, mpsc::Receiver<SourceEvent>) {
+    let (url, sessions) = serve();
+    let source = Source { label: "grpc".to_string(), kind: SourceKind::Geyser, url };
+    let options = StreamOptions {
+        mode: CompareMode::Signature,
+        slot_depth: 300,
+        subscription: Default::default(),
+        auth: None,
+        heartbeat: None,
+        timeline: false,
+    };
+    let (tx, events) = mpsc::channel(64);
+    spawn_sources(&[source], options, None, None, tx);
+    (sessions, events)
+}
+
+async fn next_session(sessions: &mut mpsc::UnboundedReceiver<ScriptedSession>) -> ScriptedSession {
+    timeout(WAIT, sessions.recv()).await.expect("等待客户端订阅超时").unwrap()
+}
+
+async fn next_event(events: &mut mpsc::Receiver<SourceEvent>) -> SourceEvent {
+    timeout(WAIT, events.recv()).await.expect("等待数据源事件超时").unwrap()
+}
+
+#[tokio::test]
+async fn replies_to_each_ping_with_increasing_id() {
+    let (mut sessions, _events) = start();
+    let mut session = next_session(&mut sessions).await;
+    let request = next_request(&mut session).await;
+    assert!(request.ping.is_none());
+    assert_eq!(request.transactions.len(), Subscription::default().transactions.len());
+
+    for id in 1..=3 {
+        ping(&session).await;
+        let pong = next_request(&mut session).await.ping.expect("应当回复 ping");
+        assert_eq!(pong.id, id);
+    }
+}
+
+#[tokio::test]
+async fn reconnects_after_stream_ends() {
+    let (mut sessions, mut events) = start();
+    let mut first = next_session(&mut sessions).await;
+    next_request(&mut first).await;
+    assert!(matches!(next_event(&mut events).await, SourceEvent::Connected(0, _)));
+
+    // 服务端结束数据流, 客户端应当报告断线并重新订阅
+    drop(first);
+    assert!(matches!(next_event(&mut events).await, SourceEvent::Disconnected(0, _)));
+    let mut second = next_session(&mut sessions).await;
+    assert!(next_request(&mut second).await.ping.is_none());
+    assert!(matches!(next_event(&mut events).await, SourceEvent::Connected(0, _)));
+
+    // 重连后的会话同样回复 ping
+    ping(&second).await;
+    assert!(next_request(&mut second).await.ping.is_some());
+}
+
+#[tokio::test]
+async fn updates_are_stamped_before_decoding() {
+    let (url, mut sessions) = serve();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let request = Subscription::default().to_request();
+    let (_requests, mut stream) = geyser::subscribe(&url, request.clone()).await.unwrap();
+    let mut session = next_session(&mut sessions).await;
+    assert_eq!(next_request(&mut session).await, request);
+
+    // 时间戳在服务端发出之后、客户端拿到消息之前记录, 长度为帧的编码长度
+    let before = Timestamp::now();
+    ping(&session).await;
+    let message = timeout(WAIT, stream.message()).await.expect("等待推送超时").unwrap().unwrap();
+    let after = Timestamp::now();
+    assert!(before.mono_ns <= message.timestamp.mono_ns && message.timestamp.mono_ns <= after.mono_ns);
+    assert_eq!(message.len, message.update.encoded_len());
+    assert!(matches!(message.update.update_oneof, Some(UpdateOneof::Ping(_))));
+}
@@ -0,0 +1,154 @@
+// Shredstream 心跳: 按有效期重发、区域变更立即重发, 以及本地 UDP 端口收到服务端转发的 shred
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use jito_protos::shredstream::shredstream_server::{Shredstream, ShredstreamServer};
+use jito_protos::shredstream::{Heartbeat, HeartbeatResponse};
+use jito_protos::tonic::{self, Request, Response, Status};
+use shred_vs_grpc::heartbeat::HeartbeatClient;
+use shred_vs_grpc::mock::feed::{self, DelayConfig, FeedConfig};
+use shred_vs_grpc::mock::heartbeat;
+use shred_vs_grpc::shred::Shred;
+use tokio::net::UdpSocket;
+use tokio::time::{sleep, timeout, Duration, Instant};
+
+const WAIT: Duration = Duration::from_secs(10);
+
+// 服务端收到的每次心跳: (时间, 区域)
+type Received = Arc<Mutex<Vec<(Instant, Vec<String>)>>>;
+
+// 记录每次心跳的时间和区域, 前 failures 次返回错误
+struct RecordingHeartbeat {
+    ttl: Duration,
+    failures: Mutex<u32>,
+    received: Received,
+}
+
+#[tonic::async_trait]
+impl Shredstream for RecordingHeartbeat {
+    async fn send_heartbeat(&self, request: Request<Heartbeat>) -> Result<Response<HeartbeatResponse>, Status> {
+        let heartbeat = request.into_inner();
+        let socket = heartbeat.socket.unwrap();
+        assert_eq!((socket.ip.as_str(), socket.port), ("127.0.0.1", 20000));
+        let mut failures = self.failures.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            return Err(Status::unavailable("scripted failure"));
+        }
+        self.received.lock().unwrap().push((Instant::now(), heartbeat.regions));
+        Ok(Response::new(HeartbeatResponse { ttl_ms: self.ttl.as_millis() as u32 }))
+    }
+}
+
+fn start(ttl: Duration, failures: u32) -> (String, Received) {
+    let addr = common::free_tcp_addr();
+    let received = Received::default();
+    let service = RecordingHeartbeat { ttl, failures: Mutex::new(failures), received: received.clone() };
+    tokio::spawn(tonic::transport::Server::builder().add_service(ShredstreamServer::new(service)).serve(addr));
+    (format!("http://{}", addr), received)
+}
+
+async fn wait_for(received: &Received, count: usize) {
+    timeout(WAIT, async {
+        while received.lock().unwrap().len() < count {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("等待心跳超时");
+}
+
+fn regions(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[tokio::test]
+async fn resends_at_half_ttl_and_on_region_change() {
+    let ttl = Duration::from_millis(400);
+    let (url, received) = start(ttl, 0);
+    let client = HeartbeatClient::spawn(url, "127.0.0.1:20000".parse().unwrap(), regions(&["ny"]), None);
+
+    wait_for(&received, 4).await;
+    let status = client.status();
+    assert_eq!(status.ttl, Some(ttl));
+    assert_eq!(status.regions, regions(&["ny"]));
+    assert!(!status.expired());
+    {
+        let received = received.lock().unwrap();
+        for pair in received.windows(2) {
+            let interval = pair[1].0 - pair[0].0;
+            assert!(interval >= Duration::from_millis(180) && interval < Duration::from_millis(300), "间隔 {:?}", interval);
+            assert_eq!(pair[1].1, regions(&["ny"]));
+        }
+    }
+
+    // 更换区域后不等有效期过半, 立即重发
+    let count = received.lock().unwrap().len();
+    let changed_at = Instant::now();
+    client.set_regions(regions(&["amsterdam", "frankfurt"]));
+    wait_for(&received, count + 1).await;
+    let (sent_at, sent_regions) = received.lock().unwrap()[count].clone();
+    assert!(sent_at - changed_at < Duration::from_millis(100), "区域变更后 {:?} 才重发", sent_at - changed_at);
+    assert_eq!(sent_regions, regions(&["amsterdam", "frankfurt"]));
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(client.status().regions, regions(&["amsterdam", "frankfurt"]));
+
+    // 丢弃后不再发送
+    drop(client);
+    sleep(Duration::from_millis(50)).await;
+    let count = received.lock().unwrap().len();
+    sleep(ttl).await;
+    assert_eq!(received.lock().unwrap().len(), count);
+}
+
+#[tokio::test]
+async fn failures_are_counted_until_heartbeat_succeeds() {
+    let (url, received) = start(Duration::from_secs(5), 2);
+    let client = HeartbeatClient::spawn(url, "127.0.0.1:20000".parse().unwrap(), regions(&["ny"]), None);
+
+    // 第一次失败后按退避重试, 尚未成功时视为过期
+    timeout(WAIT, async {
+        while client.status().failures == 0 {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("等待心跳失败超时");
+    assert!(client.status().expired());
+
+    wait_for(&received, 1).await;
+    sleep(Duration::from_millis(50)).await;
+    let status = client.status();
+    assert_eq!(status.failures, 0);
+    assert!(status.last_success.is_some() && !status.expired());
+}
+
+#[tokio::test]
+async fn local_udp_receiver_gets_forwarded_shreds() {
+    let feed = feed::spawn_feed(FeedConfig { slot_duration: Duration::from_millis(100), ..Default::default() });
+    let addr = common::free_tcp_addr();
+    tokio::spawn(heartbeat::serve(addr, feed, DelayConfig::default(), Duration::from_millis(500), None));
+
+    let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let socket = receiver.local_addr().unwrap();
+    let client = HeartbeatClient::spawn(format!("http://{}", addr), socket, regions(&["ny"]), None);
+
+    // 登记之后服务端把切分好的 shred 发往本地端口
+    let mut buffer = vec![0u8; 2048];
+    let (mut data, mut coding) = (0, 0);
+    timeout(WAIT, async {
+        while data < 10 || coding < 10 {
+            let len = receiver.recv(&mut buffer).await.unwrap();
+            let shred = Shred::parse(&buffer[..len]).expect("收到的数据包不是合法的 shred");
+            if shred.is_data() {
+                data += 1;
+            } else {
+                coding += 1;
+            }
+        }
+    })
+    .await
+    .expect("等待 shred 超时");
+    assert!(!client.status().expired());
+}
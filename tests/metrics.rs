@@ -0,0 +1,109 @@
+// 通过 HTTP 抓取 /metrics, 检查 Prometheus 文本格式
+mod common;
+
+use std::collections::HashSet;
+
+use shred_vs_grpc::clock::Timestamp;
+use shred_vs_grpc::metrics::{self, Metrics};
+use shred_vs_grpc::source::{MatchKey, SourceEvent};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, Duration};
+
+// 标签中带有需要转义的字符
+const LABELS: [&str; 2] = ["grpc \"a\"", "shred\\b\nc"];
+const ESCAPED: [&str; 2] = ["grpc \\\"a\\\"", "shred\\\\b\\nc"];
+
+async fn get(addr: std::net::SocketAddr, path: &str) -> String {
+    let mut stream = None;
+    for _ in 0..50 {
+        match TcpStream::connect(addr).await {
+            Ok(connected) => {
+                stream = Some(connected);
+                break;
+            }
+            Err(_) => sleep(Duration::from_millis(20)).await,
+        }
+    }
+    let mut stream = stream.expect("指标服务未启动");
+    stream.write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+fn metrics_with_samples() -> Metrics {
+    let metrics = Metrics::new(LABELS.iter().map(|label| label.to_string()).collect(), 300);
+    let at = |ms| Timestamp { mono_ns: ms * 1_000_000, wall_ns: ms * 1_000_000 };
+    for source in 0..LABELS.len() {
+        metrics.observe(SourceEvent::Connected(source, at(0)));
+        metrics.received(source, 100);
+    }
+    // 两个 slot 都是第一个数据源先到, 第二个数据源分别落后 3ms 和 30ms
+    for (slot, lag_ms) in [(10, 3), (11, 30)] {
+        metrics.observe(SourceEvent::arrival(0, MatchKey::Slot(slot), slot, at(slot * 400)));
+        metrics.observe(SourceEvent::arrival(1, MatchKey::Slot(slot), slot, at(slot * 400 + lag_ms)));
+    }
+    metrics
+}
+
+#[tokio::test]
+async fn scrape_returns_valid_exposition_format() {
+    let addr = common::free_tcp_addr();
+    tokio::spawn(metrics::serve(addr, metrics_with_samples()));
+    let response = get(addr, "/metrics").await;
+
+    let (head, body) = response.split_once("\r\n\r\n").expect("响应缺少头部");
+    assert!(head.starts_with("HTTP/1.1 200 OK"), "{}", head);
+    assert!(head.contains("Content-Type: text/plain; version=0.0.4"), "{}", head);
+    assert!(head.contains(&format!("Content-Length: {}", body.len())), "{}", head);
+
+    // 每个样本之前都有对应指标的 HELP 和 TYPE, 样本行为 名称{标签} 数值
+    let (mut helped, mut typed) = (HashSet::new(), HashSet::new());
+    for line in body.lines() {
+        if let Some(rest) = line.strip_prefix("# HELP ") {
+            helped.insert(rest.split(' ').next().unwrap().to_string());
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("# TYPE ") {
+            let (name, kind) = rest.split_once(' ').unwrap();
+            assert!(["counter", "gauge", "histogram"].contains(&kind), "{}", line);
+            typed.insert(name.to_string());
+            continue;
+        }
+        let (series, value) = line.rsplit_once(' ').unwrap_or_else(|| panic!("无效的样本行: {:?}", line));
+        value.parse::<f64>().unwrap_or_else(|_| panic!("无效的数值: {:?}", line));
+        let name = series.split('{').next().unwrap();
+        let family = ["_bucket", "_sum", "_count"]
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix).filter(|family| typed.contains(*family)))
+            .unwrap_or(name);
+        assert!(helped.contains(family) && typed.contains(family), "{} 缺少 HELP 或 TYPE", name);
+        let labels = &series[name.len()..];
+        assert!(labels.is_empty() || (labels.starts_with('{') && labels.ends_with('}')), "{}", line);
+    }
+
+    // 标签值中的双引号、反斜杠和换行已转义, 原始换行不会拆开样本行
+    for escaped in ESCAPED {
+        assert!(body.contains(&format!("shred_vs_grpc_first_arrivals_total{{source=\"{}\"}}", escaped)), "{}", body);
+    }
+    assert!(body.contains(&format!("shred_vs_grpc_first_arrivals_total{{source=\"{}\"}} 2", ESCAPED[0])));
+
+    // 直方图分桶单调不减, +Inf 桶等于样本数
+    let buckets: Vec<f64> = body
+        .lines()
+        .filter(|line| line.starts_with(&format!("shred_vs_grpc_lag_seconds_bucket{{source=\"{}\"", ESCAPED[1])))
+        .map(|line| line.rsplit_once(' ').unwrap().1.parse().unwrap())
+        .collect();
+    assert!(buckets.windows(2).all(|w| w[0] <= w[1]), "{:?}", buckets);
+    assert_eq!(buckets.last(), Some(&2.0));
+    assert!(body.contains(&format!("shred_vs_grpc_lag_seconds_count{{source=\"{}\"}} 2", ESCAPED[1])));
+    assert!(body.contains(&format!("shred_vs_grpc_lag_seconds_bucket{{source=\"{}\",le=\"0.005\"}} 1", ESCAPED[1])));
+}
+
+#[tokio::test]
+async fn other_paths_return_not_found() {
+    let addr = common::free_tcp_addr();
+    tokio::spawn(metrics::serve(addr, metrics_with_samples()));
+    assert!(get(addr, "/").await.starts_with("HTTP/1.1 404 Not Found"));
+}
@@ -0,0 +1,51 @@
+// 用模拟服务端跑一次完整的对比: Geyser 固定比 Shredstream 晚 30ms, 落后延迟的中位数应接近 30ms
+mod common;
+
+use common::{binary, free_tcp_addr, temp_path, Background};
+
+const GEYSER_DELAY_MS: f64 = 30.0;
+
+#[test]
+fn geyser_lag_matches_injected_delay() {
+    let (shred_addr, geyser_addr) = (free_tcp_addr(), free_tcp_addr());
+    let _mock = Background::spawn(&[
+        "mock",
+        "--shred-listen", &shred_addr.to_string(),
+        "--geyser-listen", &geyser_addr.to_string(),
+        "--geyser-delay-ms", "30",
+        "--slot-ms", "100",
+    ]);
+
+    let report = temp_path("mock-compare.json");
+    let output = binary()
+        .args([
+            "compare",
+            "--source", &format!("grpc=geyser:http://{}", geyser_addr),
+            "--source", &format!("proxy=shredstream:http://{}", shred_addr),
+            "--mode", "signature",
+            "--duration", "4",
+            "--warmup", "1",
+            "--report", report.to_str().unwrap(),
+        ])
+        .output()
+        .expect("无法运行 compare");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "compare 失败:\n{}", stdout);
+
+    let report: serde_json::Value = serde_json::from_slice(&std::fs::read(&report).unwrap()).unwrap();
+    let source = |label: &str| {
+        report["sources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|source| source["label"] == label)
+            .unwrap_or_else(|| panic!("报告中没有数据源 {}", label))
+            .clone()
+    };
+    let (grpc, proxy) = (source("grpc"), source("proxy"));
+    assert!(report["total"].as_u64().unwrap() > 500, "样本太少:\n{}", stdout);
+    // Shredstream 几乎总是先到, Geyser 的落后延迟即注入的延迟
+    assert!(proxy["first_percent"].as_f64().unwrap() > 95.0, "Shredstream 应当先到:\n{}", stdout);
+    let p50 = grpc["lag_percentiles_ms"]["p50"].as_f64().unwrap();
+    assert!((p50 - GEYSER_DELAY_MS).abs() < 5.0, "Geyser 落后延迟中位数 {}ms, 预期约 {}ms", p50, GEYSER_DELAY_MS);
+}
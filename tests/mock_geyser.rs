@@ -0,0 +1,65 @@
+// 模拟 Geyser 的 slot 更新: 新 slot 开始时推送, 不受 entry 丢弃的影响
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use shred_vs_grpc::geyser;
+use shred_vs_grpc::mock::feed::{DelayConfig, FeedEntry};
+use shred_vs_grpc::mock::geyser::{serve_mock, MockGeyser};
+use solana_entry::entry::next_entry;
+use solana_sdk::hash::Hash;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout, Duration, Instant};
+use yellowstone_grpc_proto::geyser::subscribe_update::UpdateOneof;
+use yellowstone_grpc_proto::geyser::{SubscribeRequest, SubscribeRequestFilterEntry, SubscribeRequestFilterSlots};
+
+const WAIT: Duration = Duration::from_secs(10);
+
+fn feed_entry(slot: u64, index: usize) -> Arc<FeedEntry> {
+    Arc::new(FeedEntry {
+        slot,
+        index,
+        starting_transaction_index: 0,
+        last_in_slot: index == 1,
+        entry: next_entry(&Hash::new_unique(), 1, Vec::new()),
+        produced_at: Instant::now(),
+    })
+}
+
+#[tokio::test]
+async fn slot_updates_are_sent_when_entries_are_dropped() {
+    let addr = common::free_tcp_addr();
+    let (feed, _) = broadcast::channel(16);
+    // 所有 entry 都被丢弃
+    let config = DelayConfig { drop_rate: 1.0, ..Default::default() };
+    tokio::spawn(serve_mock(addr, MockGeyser::new(feed.clone(), config, Duration::from_secs(3600))));
+    sleep(Duration::from_millis(100)).await;
+
+    let request = SubscribeRequest {
+        slots: HashMap::from([("slots".to_string(), SubscribeRequestFilterSlots::default())]),
+        entry: HashMap::from([("entries".to_string(), SubscribeRequestFilterEntry::default())]),
+        ..Default::default()
+    };
+    let (_requests, mut stream) = geyser::subscribe(&format!("http://{}", addr), request).await.unwrap();
+    // 等待服务端收到订阅请求
+    sleep(Duration::from_millis(100)).await;
+
+    // slot 5 从第二个 entry 开始收到, slot 6 有两个 entry
+    for (slot, index) in [(5, 1), (6, 0), (6, 1), (7, 0)] {
+        assert!(feed.send(feed_entry(slot, index)).is_ok());
+    }
+    let mut slots = Vec::new();
+    while slots.len() < 3 {
+        let message = timeout(WAIT, stream.message()).await.expect("等待推送超时").unwrap().unwrap();
+        match message.update.update_oneof {
+            Some(UpdateOneof::Slot(update)) => {
+                assert_eq!(message.update.filters, ["slots"]);
+                slots.push((update.slot, update.parent));
+            }
+            Some(UpdateOneof::Ping(_)) => {}
+            other => panic!("丢弃的 entry 不应推送: {:?}", other),
+        }
+    }
+    assert_eq!(slots, [(5, Some(4)), (6, Some(5)), (7, Some(6))]);
+}
@@ -0,0 +1,101 @@
+// SearcherService 查询: 用 SEARCHER 角色的令牌查询模拟服务端各区域连接的 leader, 并按区域分组统计
+mod common;
+
+use std::sync::Arc;
+
+use jito_protos::auth::Role;
+use shred_vs_grpc::auth::{AuthConfig, Authenticator};
+use shred_vs_grpc::leaders::{LeaderSchedule, LeaderStats};
+use shred_vs_grpc::mock::auth::{self, MockAuthState};
+use shred_vs_grpc::mock::searcher;
+use shred_vs_grpc::searcher::load_connected_leaders;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout, Duration};
+
+const WAIT: Duration = Duration::from_secs(10);
+
+// 启动要求鉴权的模拟 SearcherService, 返回 (SearcherService 地址, 鉴权配置)
+fn start() -> (String, AuthConfig) {
+    let (auth_addr, searcher_addr) = (common::free_tcp_addr(), common::free_tcp_addr());
+    let state = MockAuthState::new(Duration::from_secs(60), Duration::from_secs(120));
+    tokio::spawn(auth::serve(auth_addr, state.clone()));
+    // 合成数据源不推送, 当前 slot 停在第一个 epoch
+    let (feed, _) = broadcast::channel(1);
+    tokio::spawn(searcher::serve(searcher_addr, feed, Some(state)));
+    let config = AuthConfig {
+        url: format!("http://{}", auth_addr),
+        keypair: Arc::new(Keypair::new()),
+        role: Role::ShredstreamSubscriber,
+    };
+    (format!("http://{}", searcher_addr), config)
+}
+
+// 等待服务端启动后查询, 返回 (区域数, leader 数)
+async fn load(url: &str, auth: &Authenticator, schedule: &mut LeaderSchedule) -> Result<(usize, usize), String> {
+    timeout(WAIT, auth.ready()).await.expect("等待鉴权超时").unwrap();
+    let mut last = String::new();
+    for _ in 0..50 {
+        match load_connected_leaders(url, Some(auth), schedule).await {
+            Ok(loaded) => return Ok(loaded),
+            Err(e) if e.to_string().contains("transport error") => last = e.to_string(),
+            Err(e) => return Err(e.to_string()),
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    panic!("模拟 SearcherService 未启动: {}", last);
+}
+
+// 模拟服务端第 index 个 leader 的身份
+fn identity(index: u8) -> String {
+    Pubkey::new_from_array([index + 1; 32]).to_string()
+}
+
+#[tokio::test]
+async fn searcher_requires_searcher_role() {
+    let (url, config) = start();
+
+    // SHREDSTREAM_SUBSCRIBER 令牌被拒绝
+    let subscriber = Authenticator::spawn(config.clone());
+    let error = load(&url, &subscriber, &mut LeaderSchedule::default()).await.unwrap_err();
+    assert!(error.contains("访问令牌的角色不符"), "{}", error);
+
+    let searcher = Authenticator::spawn(config.with_role(Role::Searcher));
+    let mut schedule = LeaderSchedule::default();
+    assert_eq!(load(&url, &searcher, &mut schedule).await.unwrap(), (4, 4));
+    assert_eq!(schedule.regions(&identity(0)), ["amsterdam"]);
+    assert_eq!(schedule.leader(9), Some(identity(2).as_str()));
+}
+
+#[tokio::test]
+async fn leaders_are_grouped_under_each_connected_region() {
+    let (url, config) = start();
+    let auth = Authenticator::spawn(config.with_role(Role::Searcher));
+    let mut schedule = LeaderSchedule::default();
+    load(&url, &auth, &mut schedule).await.unwrap();
+    // leader 表补充不运行 Jito 的 leader; 第一个 leader 同时连接 ny
+    let path = common::temp_path("searcher-leader-schedule.json");
+    searcher::write_leader_schedule(&path).unwrap();
+    schedule.load_schedule(&path, Some(0)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    schedule.add_connected(&identity(0), "ny", []);
+    assert_eq!(schedule.regions(&identity(0)), ["amsterdam", "ny"]);
+
+    // slot 0 属于第一个 leader (amsterdam 和 ny), slot 8 属于第三个 (ny), slot 16 属于第五个 (非 Jito)
+    let mut stats = LeaderStats::new(schedule, 2);
+    stats.record(0, 0, &[(1, 2_000_000)]);
+    stats.record(8, 1, &[(0, 4_000_000)]);
+    stats.record(16, 0, &[(1, 1_000_000)]);
+    let summary = stats.summary(&["shred".to_string(), "grpc".to_string()]);
+
+    let regions: Vec<(&str, u64)> = summary.by_region.iter().map(|group| (group.name.as_str(), group.total)).collect();
+    assert_eq!(regions, [("ny", 2), ("amsterdam", 1), ("非 Jito", 1)]);
+    let ny = &summary.by_region[0];
+    assert_eq!((ny.sources[0].first, ny.sources[1].first), (1, 1));
+
+    // 按 leader 分组时每个 leader 只计一次, 区域以 / 连接
+    assert_eq!(summary.by_leader.len(), 3);
+    let first = summary.by_leader.iter().find(|group| group.name == identity(0)).unwrap();
+    assert_eq!((first.total, first.region.as_deref()), (1, Some("amsterdam/ny")));
+}
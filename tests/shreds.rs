@@ -0,0 +1,238 @@
+// 本地 shred 还原: 经 127.0.0.1 UDP 收发模拟切分的 shred, 丢弃部分数据 shred 后用编码 shred 恢复,
+// 检查还原出的 entry 与原始内容一致、entry 序号按 shred 序号确定, 越界和远超最新 slot 的 shred 被丢弃,
+// 以及完成的 FEC 集合和批次及时释放
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use shred_vs_grpc::clock::Timestamp;
+use shred_vs_grpc::mock::shredder::MockShredder;
+use shred_vs_grpc::shred::{DeshredStats, Deshredder, Shred};
+use shred_vs_grpc::slot_window::SlotWindow;
+use shred_vs_grpc::source::{CompareMode, Decoder, MatchKey, SourceEvent, StreamOptions};
+use shred_vs_grpc::subscription::Subscription;
+use solana_entry::entry::{next_entry_mut, Entry};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::system_transaction;
+
+const SLOT: u64 = 100;
+
+// 生成 count 个 entry, 每个含 txs 笔转账交易
+fn entries(hash: &mut Hash, count: usize, txs: usize) -> Vec<Entry> {
+    let (payer, recipient) = (Keypair::new(), Pubkey::new_unique());
+    (0..count)
+        .map(|_| {
+            let transactions = (0..txs)
+                .map(|lamports| system_transaction::transfer(&payer, &recipient, lamports as u64 + 1, *hash))
+                .collect();
+            next_entry_mut(hash, 1, transactions)
+        })
+        .collect()
+}
+
+// 切分一个批次, 分为数据 shred 和编码 shred
+fn shred(shredder: &mut MockShredder, slot: u64, entries: &[Entry], last_in_slot: bool) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+    shredder
+        .shred(slot, &bincode::serialize(entries).unwrap(), last_in_slot)
+        .into_iter()
+        .partition(|packet| Shred::parse(packet).unwrap().is_data())
+}
+
+// 经本地 UDP 依次发送所有数据包, 按收到的顺序返回
+fn loopback(packets: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let addr = receiver.local_addr().unwrap();
+    let count = packets.len();
+    let handle = thread::spawn(move || {
+        let mut buffer = vec![0u8; 2048];
+        (0..count)
+            .map(|_| {
+                let len = receiver.recv(&mut buffer).expect("等待 shred 超时");
+                buffer[..len].to_vec()
+            })
+            .collect::<Vec<_>>()
+    });
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    for packet in packets {
+        sender.send_to(packet, addr).unwrap();
+        // 避免超出接收缓冲区
+        thread::sleep(Duration::from_micros(200));
+    }
+    handle.join().unwrap()
+}
+
+fn decoder() -> Decoder {
+    Decoder::new(&StreamOptions {
+        mode: CompareMode::Entry,
+        slot_depth: 300,
+        subscription: Arc::new(Subscription::default()),
+        auth: None,
+        heartbeat: None,
+        timeline: false,
+    })
+}
+
+fn timestamp(ms: u64) -> Timestamp {
+    Timestamp { mono_ns: ms * 1_000_000, wall_ns: ms * 1_000_000 }
+}
+
+#[test]
+fn dropped_data_shreds_are_recovered_and_positioned() {
+    let mut hash = Hash::new_unique();
+    // 第一个批次足够大, 占两个 FEC 集合; 后两个批次各占一个
+    let batches = [entries(&mut hash, 10, 20), entries(&mut hash, 2, 3), entries(&mut hash, 3, 2)];
+    let mut shredder = MockShredder::default();
+    let (first_data, first_code) = shred(&mut shredder, SLOT, &batches[0], false);
+    let (second_data, second_code) = shred(&mut shredder, SLOT, &batches[1], false);
+    let (third_data, third_code) = shred(&mut shredder, SLOT, &batches[2], true);
+    assert!(first_data.len() > 32 && first_code.len() == first_data.len());
+    let (first_len, second_len) = (first_data.len() as u32, second_data.len() as u32);
+
+    // 丢弃第一个集合的 3 个数据 shred 和第二个集合的最后一个数据 shred (批次末尾);
+    // 第一个集合的编码 shred 最后才到, 后两个批次先于第一个批次完成
+    let dropped = [0, 7, 20, first_data.len() - 1];
+    let mut packets: Vec<Vec<u8>> =
+        first_data.iter().enumerate().filter(|(i, _)| !dropped.contains(i)).map(|(_, packet)| packet.clone()).collect();
+    packets.extend(first_code[32..].iter().cloned());
+    packets.extend(second_data.into_iter().chain(second_code).chain(third_data).chain(third_code));
+    packets.extend(first_code[..32].iter().cloned());
+    let received = loopback(&packets);
+    assert_eq!(received, packets);
+
+    let mut deshredder = Deshredder::new(300);
+    let mut decoder = decoder();
+    let (mut completed, mut events) = (Vec::new(), Vec::new());
+    for (i, packet) in received.iter().enumerate() {
+        completed.extend(deshredder.insert(Shred::parse(packet).unwrap()));
+        events.extend(decoder.shreds(0, CompareMode::Entry, Shred::parse(packet).unwrap(), timestamp(i as u64)));
+    }
+    assert_eq!(deshredder.stats(), DeshredStats { recovered: 4, ..Default::default() });
+
+    // 批次按完成顺序输出, 首尾 shred 序号和内容与原始批次一致
+    let order = [1, 2, 0];
+    assert_eq!(completed.len(), 3);
+    for (batch, &original) in completed.iter().zip(&order) {
+        assert_eq!(batch.slot, SLOT);
+        assert_eq!(bincode::deserialize::<Vec<Entry>>(&batch.entries).unwrap(), batches[original]);
+    }
+    assert_eq!((completed[2].first_shred, completed[2].last_shred), (0, first_len - 1));
+    assert_eq!((completed[0].first_shred, completed[0].last_shred), (first_len, first_len + second_len - 1));
+
+    // 按 entry 对比时后两个批次等第一个批次还原后才发出, 序号与 slot 内的位置一致, 到达时间为各自完成的时间
+    let arrivals: Vec<_> = events
+        .iter()
+        .map(|event| match event {
+            SourceEvent::Arrival(arrival) => arrival,
+            _ => panic!("按 entry 对比时只应发出到达事件"),
+        })
+        .collect();
+    let originals: Vec<&Entry> = batches.iter().flatten().collect();
+    assert_eq!(arrivals.len(), originals.len());
+    for (index, (arrival, original)) in arrivals.iter().zip(&originals).enumerate() {
+        assert!(arrival.key == MatchKey::Entry(SLOT, index as u64), "第 {} 个 entry 的匹配键为 {}", index, arrival.key);
+        let entry = arrival.entry.unwrap();
+        assert_eq!((entry.hash, entry.transactions), (original.hash, original.transactions.len() as u64));
+    }
+    let first_arrival = arrivals[0].timestamp;
+    assert!(arrivals[10..].iter().all(|arrival| arrival.timestamp.mono_ns < first_arrival.mono_ns));
+}
+
+#[test]
+fn invalid_and_far_future_shreds_are_dropped() {
+    let mut hash = Hash::new_unique();
+    let batches = [entries(&mut hash, 2, 2), entries(&mut hash, 2, 2)];
+    let mut shredder = MockShredder::default();
+    let (first_data, _) = shred(&mut shredder, SLOT, &batches[0], false);
+    let (second_data, second_code) = shred(&mut shredder, SLOT, &batches[1], true);
+
+    // 数据 shred 序号为 u32::MAX, 编码 shred 的 fec_set_index + num_data 超出 u32
+    let mut overflow_data = second_data[0].clone();
+    overflow_data[73..77].copy_from_slice(&u32::MAX.to_le_bytes());
+    let mut overflow_code = second_code[0].clone();
+    overflow_code[79..83].copy_from_slice(&u32::MAX.to_le_bytes());
+    // 远超最新 slot 的 shred
+    let (far_data, _) = shred(&mut shredder, SLOT + 5000, &batches[1], true);
+
+    let mut packets = first_data.clone();
+    packets.extend([overflow_data, overflow_code]);
+    packets.extend(far_data.iter().cloned());
+    packets.extend(second_data.iter().cloned());
+    let received = loopback(&packets);
+    assert_eq!(received, packets);
+
+    let mut deshredder = Deshredder::new(300);
+    let mut decoder = decoder();
+    let mut completed = Vec::new();
+    for (i, packet) in received.iter().enumerate() {
+        completed.extend(deshredder.insert(Shred::parse(packet).unwrap()));
+        decoder.shreds(0, CompareMode::Entry, Shred::parse(packet).unwrap(), timestamp(i as u64));
+    }
+    let stats = deshredder.stats();
+    assert_eq!((stats.invalid, stats.far_future), (2, far_data.len() as u64));
+    // 远超最新 slot 的 shred 没有淘汰现有的 slot, 后续批次仍能完成
+    assert_eq!(completed.len(), 2);
+    assert_eq!(bincode::deserialize::<Vec<Entry>>(&completed[1].entries).unwrap(), batches[1]);
+
+    // 连续收到足够多的远超最新 slot 的 shred 时认为链确实前进了
+    for _ in 0..64 {
+        deshredder.insert(Shred::parse(&far_data[0]).unwrap());
+    }
+    assert_eq!(deshredder.stats().far_future, far_data.len() as u64 + 63);
+    assert!(deshredder.insert(Shred::parse(&first_data[0]).unwrap()).is_empty());
+
+    // 判断是否过期时 slot + depth 不会溢出
+    let mut window = SlotWindow::<()>::new(300);
+    window.advance(SLOT);
+    assert!(!window.is_expired(u64::MAX));
+}
+
+#[test]
+fn completed_sets_and_batches_are_released() {
+    let mut hash = Hash::new_unique();
+    let batches = [entries(&mut hash, 10, 20), entries(&mut hash, 2, 3)];
+    let mut shredder = MockShredder::default();
+    let (first_data, first_code) = shred(&mut shredder, SLOT, &batches[0], false);
+    let (second_data, second_code) = shred(&mut shredder, SLOT + 1, &batches[1], true);
+    assert!(first_data.len() > 32);
+    let parse = |packet: &Vec<u8>| Shred::parse(packet).unwrap();
+
+    // 下一个 slot 的批次只有数据 shred, 批次已输出但没有编码 shred 确认集合大小, 数据 shred 仍然保留
+    let mut deshredder = Deshredder::default();
+    let completed: Vec<_> = second_data.iter().flat_map(|packet| deshredder.insert(parse(packet))).collect();
+    assert_eq!(completed.len(), 1);
+    assert_eq!(deshredder.buffered(), second_data.len());
+    // 第一个编码 shred 让集合完成, 编码 shred 和数据 shred 全部释放, 之后的编码 shred 不再保存
+    for packet in &second_code {
+        deshredder.insert(parse(packet));
+        assert_eq!(deshredder.buffered(), 0);
+    }
+
+    // 第一个集合缺失一个数据 shred, 收到编码 shred 之前数据 shred 都保留
+    for packet in &first_data[1..] {
+        assert!(deshredder.insert(parse(packet)).is_empty());
+    }
+    assert_eq!(deshredder.buffered(), first_data.len() - 1);
+    // 一个编码 shred 即可恢复, 批次输出, 第一个集合的编码 shred 和数据 shred 释放, 该集合之后的编码 shred 不再保存;
+    // 第二个集合还没有编码 shred, 其数据 shred 保留
+    let batch = deshredder.insert(parse(&first_code[0]));
+    assert_eq!(batch.len(), 1);
+    assert_eq!(bincode::deserialize::<Vec<Entry>>(&batch[0].entries).unwrap(), batches[0]);
+    assert_eq!(deshredder.stats().recovered, 1);
+    for packet in &first_code[..32] {
+        deshredder.insert(parse(packet));
+        assert_eq!(deshredder.buffered(), first_data.len() - 32);
+    }
+    assert!(deshredder.insert(parse(&first_code[32])).is_empty());
+    assert_eq!(deshredder.buffered(), 0);
+
+    // 释放后重复到达的 shred 直接丢弃, 不会再次输出批次或恢复
+    for packet in first_data.iter().chain(&first_code).chain(&second_data) {
+        assert!(deshredder.insert(parse(packet)).is_empty());
+    }
+    assert_eq!(deshredder.buffered(), 0);
+    assert_eq!(deshredder.stats(), DeshredStats { recovered: 1, ..Default::default() });
+}
@@ -0,0 +1,108 @@
+// 送达完整性核对: 失败交易单独统计, 按两端的交易位置判断截断, verify 子命令默认使用 confirmed 级别
+use clap::Parser;
+use shred_vs_grpc::cli::{Cli, Command};
+use shred_vs_grpc::clock::Timestamp;
+use shred_vs_grpc::config::{Commitment, Config, FileConfig};
+use shred_vs_grpc::source::{Arrival, MatchKey};
+use shred_vs_grpc::verify::{SlotRecall, Verifier};
+use solana_sdk::signature::Signature;
+
+const GRPC: usize = 0;
+const SHRED: usize = 1;
+
+fn transaction(source: usize, slot: u64, signature: Signature, position: u64, failed: Option<bool>) -> Arrival {
+    let timestamp = Timestamp { mono_ns: slot * 400_000_000 + position, wall_ns: slot * 400_000_000 + position };
+    Arrival {
+        source,
+        key: MatchKey::Signature(signature),
+        slot,
+        timestamp,
+        entry: None,
+        position: Some(position),
+        failed,
+    }
+}
+
+// 第一个 slot 两端都可能不完整, 不参与核对
+fn verifier() -> Verifier {
+    let mut verifier = Verifier::new(GRPC, SHRED, 300);
+    verifier.record(&transaction(GRPC, 9, Signature::new_unique(), 0, Some(false)));
+    verifier.record(&transaction(SHRED, 9, Signature::new_unique(), 0, None));
+    verifier
+}
+
+// 推进到后面的 slot, 使之前的 slot 在结束时被核对
+fn finish(mut verifier: Verifier) -> Vec<SlotRecall> {
+    verifier.record(&transaction(GRPC, 20, Signature::new_unique(), 0, Some(false)));
+    verifier.finish(2)
+}
+
+// 运行 verify 子命令时使用的配置
+fn verify_config(args: &[&str]) -> Config {
+    let sources = ["--source", "grpc=geyser:http://127.0.0.1:10000", "--source", "shred=shredstream:http://127.0.0.1:9999"];
+    let cli = Cli::try_parse_from(["shred-vs-grpc", "verify"].iter().chain(&sources).chain(args)).unwrap();
+    let Some(Command::Verify(args)) = cli.command else {
+        panic!("应解析为 verify 子命令");
+    };
+    Config::verify(&args, &FileConfig::default()).unwrap()
+}
+
+#[test]
+fn failed_transactions_are_reported_separately() {
+    let mut verifier = verifier();
+    // slot 10: 两笔成功交易和一笔失败交易, Shred 端收到其中一笔成功交易和失败交易, 另有一笔 Geyser 没有推送的交易
+    let (ok, missed, failed, extra) =
+        (Signature::new_unique(), Signature::new_unique(), Signature::new_unique(), Signature::new_unique());
+    for (signature, position, status) in [(ok, 0, false), (failed, 1, true), (missed, 2, false)] {
+        verifier.record(&transaction(GRPC, 10, signature, position, Some(status)));
+    }
+    for (signature, position) in [(ok, 0), (failed, 1), (extra, 3)] {
+        verifier.record(&transaction(SHRED, 10, signature, position, None));
+    }
+
+    let results = finish(verifier);
+    assert_eq!(results.len(), 1);
+    let result = &results[0];
+    assert_eq!(result.slot, 10);
+    assert_eq!(result.both, 1);
+    assert_eq!(result.grpc_only, 1);
+    assert_eq!(result.shred_only, 1);
+    assert_eq!(result.failed, 1);
+    assert_eq!(result.recall, Some(0.5));
+    // Shred 端在漏收的交易之后还收到了 Geyser 没有推送的交易, 不是截断
+    assert!(!result.truncated);
+}
+
+#[test]
+fn truncation_uses_positions_from_both_sides() {
+    let mut verifier = verifier();
+    let signatures: Vec<Signature> = (0..12).map(|_| Signature::new_unique()).collect();
+    let (truncated, gap, undecoded) = (&signatures[..4], &signatures[4..8], &signatures[8..]);
+    for (slot, signatures) in [(10, truncated), (11, gap), (12, undecoded)] {
+        for (position, signature) in signatures.iter().enumerate() {
+            verifier.record(&transaction(GRPC, slot, *signature, position as u64, Some(false)));
+        }
+    }
+    // slot 10: Shred 端只收到前两笔
+    for (position, signature) in truncated[..2].iter().enumerate() {
+        verifier.record(&transaction(SHRED, 10, *signature, position as u64, None));
+    }
+    // slot 11: Shred 端中途丢了一笔, 之后的交易解码出的位置偏小
+    verifier.record(&transaction(SHRED, 11, gap[0], 0, None));
+    verifier.record(&transaction(SHRED, 11, gap[2], 1, None));
+    // slot 12: Shred 端没有位置信息, 按 Geyser 端的位置判断
+    for signature in &undecoded[..2] {
+        verifier.record(&Arrival { position: None, ..transaction(SHRED, 12, *signature, 0, None) });
+    }
+
+    let results = finish(verifier);
+    let results: Vec<(u64, u64, bool)> = results.iter().map(|r| (r.slot, r.grpc_only, r.truncated)).collect();
+    assert_eq!(results, [(10, 2, true), (11, 2, false), (12, 2, true)]);
+}
+
+#[test]
+fn verify_defaults_to_confirmed_commitment() {
+    assert_eq!(verify_config(&[]).commitment, Commitment::Confirmed);
+    // 命令行指定的级别优先
+    assert_eq!(verify_config(&["--commitment", "processed"]).commitment, Commitment::Processed);
+}